@@ -0,0 +1,279 @@
+// Tauri build step plus the typed Agent-0 client generated from openapi/agent0.json
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+const SPEC: &str = "openapi/agent0.json";
+const KEYWORDS: [&str; 8] = ["type", "match", "ref", "mod", "fn", "struct", "enum", "use"];
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", SPEC);
+    let spec: Value = serde_json::from_str(
+        &std::fs::read_to_string(SPEC).unwrap_or_else(|e| panic!("Failed to read {}: {}", SPEC, e)),
+    )
+    .unwrap_or_else(|e| panic!("Invalid JSON in {}: {}", SPEC, e));
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("agent0_api.rs");
+    std::fs::write(&out, generate(&spec)).expect("Failed to write the generated Agent-0 client");
+    tauri_build::build()
+}
+
+fn field_name(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// Rust type for a schema; inline objects and untyped schemas stay free-form
+fn rust_type(schema: &Value) -> String {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return reference
+            .strip_prefix("#/components/schemas/")
+            .unwrap_or_else(|| panic!("Unsupported $ref '{}'", reference))
+            .to_string();
+    }
+    let base = match (schema["type"].as_str(), schema["format"].as_str()) {
+        (Some("string"), _) => "String".to_string(),
+        (Some("integer"), Some("int32")) => "i32".to_string(),
+        (Some("integer"), Some("uint32")) => "u32".to_string(),
+        (Some("integer"), Some("uint64")) => "u64".to_string(),
+        (Some("integer"), _) => "i64".to_string(),
+        (Some("number"), _) => "f64".to_string(),
+        (Some("boolean"), _) => "bool".to_string(),
+        (Some("array"), _) => format!("Vec<{}>", rust_type(&schema["items"])),
+        (Some("object"), _) | (None, _) => "Value".to_string(),
+        (Some(other), _) => panic!("Unsupported schema type '{}'", other),
+    };
+    if schema["nullable"].as_bool().unwrap_or(false) {
+        format!("Option<{}>", base)
+    } else {
+        base
+    }
+}
+
+fn generate_struct(out: &mut String, name: &str, schema: &Value) {
+    let empty = Map::new();
+    let properties = schema["properties"].as_object().unwrap_or(&empty);
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    writeln!(
+        out,
+        "#[derive(Serialize, Deserialize, Clone, Debug, Default)]"
+    )
+    .unwrap();
+    writeln!(out, "pub struct {} {{", name).unwrap();
+    for (field, property) in properties {
+        let ty = rust_type(property);
+        if required.contains(&field.as_str()) {
+            writeln!(out, "    pub {}: {},", field_name(field), ty).unwrap();
+        } else {
+            writeln!(
+                out,
+                "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub {}: Option<{}>,",
+                field_name(field),
+                ty
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out, "}}\n").unwrap();
+}
+
+fn json_schema(content: &Value) -> Option<&Value> {
+    content.get("application/json").map(|c| &c["schema"])
+}
+
+struct Operation<'a> {
+    id: &'a str,
+    method: String,
+    path: &'a str,
+    op: &'a Value,
+}
+
+impl Operation<'_> {
+    fn params(&self) -> Vec<&str> {
+        self.path
+            .split('/')
+            .filter_map(|s| s.strip_prefix('{')?.strip_suffix('}'))
+            .collect()
+    }
+
+    // A `&str` expression for the request path
+    fn path_expr(&self) -> String {
+        let params = self.params();
+        if params.is_empty() {
+            return format!("paths::{}", self.id.to_uppercase());
+        }
+        let mut template = self.path.to_string();
+        for param in &params {
+            template = template.replace(&format!("{{{}}}", param), "{}");
+        }
+        let args: Vec<String> = params.iter().map(|p| format!("segment({})", p)).collect();
+        format!("&format!(\"{}\", {})", template, args.join(", "))
+    }
+
+    fn response(&self) -> Option<&Value> {
+        json_schema(&self.op["responses"]["200"]["content"])
+    }
+
+    fn body(&self) -> Option<String> {
+        json_schema(&self.op["requestBody"]["content"]).map(rust_type)
+    }
+}
+
+fn generate_operation(out: &mut String, op: &Operation) {
+    let mut args = vec!["base: &str".to_string()];
+    args.extend(op.params().iter().map(|p| format!("{}: &str", p)));
+    if let Some(body) = op.body() {
+        args.push(format!("body: &{}", body));
+    }
+    let summary = op.op["summary"].as_str().unwrap_or(op.id);
+    let versioned = op.op["x-versioned"].as_bool().unwrap_or(false);
+    writeln!(out, "// {} ({} {})", summary, op.method, op.path).unwrap();
+    if let Some(key) = op.op["x-paginated"].as_str() {
+        let item = rust_type(
+            &op.response().expect("paginated lists need a schema")["properties"][key]["items"],
+        );
+        writeln!(
+            out,
+            "pub async fn {}({}) -> Result<Vec<{}>, String> {{\n    let list = paginated::{};\n    let items = agent0::get_all(base, list.path, list.key).await?;\n    decode(list.path, Value::Array(items))\n}}\n",
+            op.id,
+            args.join(", "),
+            item,
+            op.id.to_uppercase()
+        )
+        .unwrap();
+        return;
+    }
+    let path = op.path_expr();
+    if versioned && op.method == "GET" {
+        writeln!(
+            out,
+            "pub async fn {}({}) -> Result<Versioned, String> {{\n    agent0::get_versioned(base, {}).await\n}}\n",
+            op.id,
+            args.join(", "),
+            path
+        )
+        .unwrap();
+        return;
+    }
+    if versioned {
+        args.push("etag: Option<&str>".to_string());
+        writeln!(
+            out,
+            "pub async fn {}({}) -> Result<Versioned, WriteError> {{\n    agent0::post_if_match(base, {}, encode(body)?, etag).await\n}}\n",
+            op.id,
+            args.join(", "),
+            path
+        )
+        .unwrap();
+        return;
+    }
+    let (returns, result) = match op.response().map(rust_type) {
+        None => ("()".to_string(), "    Ok(())".to_string()),
+        Some(ty) if ty == "Value" => (ty, "    Ok(value)".to_string()),
+        Some(ty) => (ty, "    decode(path, value)".to_string()),
+    };
+    let body = if op.body().is_some() {
+        "Some(encode(body)?)"
+    } else {
+        "None"
+    };
+    let binding = if returns == "()" { "_value" } else { "value" };
+    // Prompts carry the language and persona hints as headers
+    let call = if op.op["x-routing-hints"].as_bool().unwrap_or(false) {
+        args.push("hints: &std::collections::HashMap<String, String>".to_string());
+        format!(
+            "agent0::call_with_headers(Method::{}, base, path, {}, hints)",
+            op.method, body
+        )
+    } else {
+        format!("agent0::call(Method::{}, base, path, {})", op.method, body)
+    };
+    writeln!(
+        out,
+        "pub async fn {}({}) -> Result<{}, String> {{\n    let path = {};\n    let {} = {}.await?;\n{}\n}}\n",
+        op.id,
+        args.join(", "),
+        returns,
+        path,
+        binding,
+        call,
+        result
+    )
+    .unwrap();
+}
+
+fn generate(spec: &Value) -> String {
+    let mut operations = Vec::new();
+    for (path, item) in spec["paths"].as_object().expect("spec has no paths") {
+        for (method, op) in item.as_object().unwrap() {
+            let id = op["operationId"]
+                .as_str()
+                .unwrap_or_else(|| panic!("{} {} has no operationId", method, path));
+            if !id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            {
+                panic!("operationId '{}' must be snake_case", id);
+            }
+            if operations.iter().any(|o: &Operation| o.id == id) {
+                panic!("Duplicate operationId '{}'", id);
+            }
+            operations.push(Operation {
+                id,
+                method: method.to_uppercase(),
+                path,
+                op,
+            });
+        }
+    }
+
+    let mut out =
+        String::from("// Generated by build.rs from openapi/agent0.json; do not edit\n\n");
+    out.push_str("pub mod paths {\n");
+    for op in operations.iter().filter(|o| o.params().is_empty()) {
+        writeln!(
+            out,
+            "    pub const {}: &str = \"{}\";",
+            op.id.to_uppercase(),
+            op.path
+        )
+        .unwrap();
+    }
+    out.push_str("}\n\npub mod paginated {\n    use super::Paginated;\n\n");
+    for op in &operations {
+        if let Some(key) = op.op["x-paginated"].as_str() {
+            writeln!(
+                out,
+                "    pub const {}: Paginated = Paginated {{ path: \"{}\", key: \"{}\" }};",
+                op.id.to_uppercase(),
+                op.path,
+                key
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("}\n\n");
+
+    for (name, schema) in spec["components"]["schemas"]
+        .as_object()
+        .expect("spec has no schemas")
+    {
+        generate_struct(&mut out, name, schema);
+    }
+    // Operations without a JSON response (metrics, dashboard pages, event streams) only get a
+    // path constant
+    for op in &operations {
+        let ok = &op.op["responses"]["200"];
+        let json_response = op.response().is_some();
+        let empty = ok.is_object() && ok.get("content").is_none();
+        if json_response || empty {
+            generate_operation(&mut out, op);
+        }
+    }
+    out
+}
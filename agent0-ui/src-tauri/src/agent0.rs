@@ -0,0 +1,245 @@
+// Shared helpers for calling the Agent-0 HTTP API
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::vcr;
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:8000";
+
+// Follows the active server profile
+static BASE_URL: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(DEFAULT_BASE_URL.to_string()));
+
+pub const UI_SESSION_HEADER: &str = "X-Agent0-UI-Session";
+
+const PAGE_SIZE: usize = 200;
+// Pages fetched ahead of a slow consumer before the fetcher waits
+const PREFETCH_PAGES: usize = 2;
+
+// Identifies this app instance in the server's audit trail
+static UI_SESSION_ID: Lazy<String> = Lazy::new(|| hex::encode(rand::random::<[u8; 8]>()));
+
+pub fn ui_session_id() -> &'static str {
+    &UI_SESSION_ID
+}
+
+pub fn base_url() -> String {
+    BASE_URL.read().unwrap().clone()
+}
+
+pub fn set_base_url(url: &str) {
+    *BASE_URL.write().unwrap() = url.trim_end_matches('/').to_string();
+}
+
+fn request(method: reqwest::Method, base: &str, path: &str) -> reqwest::RequestBuilder {
+    reqwest::Client::new()
+        .request(method, format!("{}{}", base, path))
+        .header(UI_SESSION_HEADER, ui_session_id())
+}
+
+async fn parse(path: &str, response: reqwest::Response) -> Result<Value, String> {
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read Agent-0 response for {}: {}", path, e))?;
+    tracing::debug!("{} {} ({} bytes)", status, path, text.len());
+    if !status.is_success() {
+        return Err(format!(
+            "Agent-0 returned {} for {}: {}",
+            status, path, text
+        ));
+    }
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON from {}: {}", path, e))
+}
+
+// Transport for the generated client in api.rs; modules call that rather than raw paths
+pub async fn call(
+    method: reqwest::Method,
+    base: &str,
+    path: &str,
+    body: Option<Value>,
+) -> Result<Value, String> {
+    call_with_headers(method, base, path, body, &HashMap::new()).await
+}
+
+// For operations that carry extra headers, such as a prompt's routing hints
+pub async fn call_with_headers(
+    method: reqwest::Method,
+    base: &str,
+    path: &str,
+    body: Option<Value>,
+    headers: &HashMap<String, String>,
+) -> Result<Value, String> {
+    let mut builder = request(method, base, path);
+    for (name, value) in headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = &body {
+        builder = builder.json(body);
+    }
+    let response = vcr::send(builder).await?;
+    parse(path, response).await
+}
+
+// The response itself, for probes that only look at the status and for bodies that are not
+// JSON; it still goes through the VCR
+pub async fn get_raw(
+    base: &str,
+    path: &str,
+    timeout: Duration,
+) -> Result<reqwest::Response, String> {
+    vcr::send(request(reqwest::Method::GET, base, path).timeout(timeout)).await
+}
+
+// One page of a cursor-paginated list: {"<key>": [...], "next_cursor": "..."}
+pub struct Page {
+    pub items: Vec<Value>,
+    pub next_cursor: Option<String>,
+}
+
+async fn get_page(base: &str, path: &str, key: &str, cursor: Option<&str>) -> Result<Page, String> {
+    let mut builder =
+        request(reqwest::Method::GET, base, path).query(&[("limit", PAGE_SIZE.to_string())]);
+    if let Some(cursor) = cursor {
+        builder = builder.query(&[("cursor", cursor)]);
+    }
+    let response = vcr::send(builder).await?;
+    let mut value = parse(path, response).await?;
+    // Servers that do not paginate return the whole list in one go
+    let list = if value.is_array() {
+        value.take()
+    } else {
+        value.get_mut(key).map(Value::take).unwrap_or(Value::Null)
+    };
+    let items = match list {
+        Value::Array(items) => items,
+        _ => return Err(format!("Expected a '{}' list from {}", key, path)),
+    };
+    Ok(Page {
+        items,
+        next_cursor: value["next_cursor"].as_str().map(|c| c.to_string()),
+    })
+}
+
+// Pages arrive in order; dropping the receiver stops the fetcher
+pub fn paginate(base: &str, path: &str, key: &str) -> mpsc::Receiver<Result<Page, String>> {
+    let (tx, rx) = mpsc::channel(PREFETCH_PAGES);
+    let (base, path, key) = (base.to_string(), path.to_string(), key.to_string());
+    tauri::async_runtime::spawn(async move {
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = get_page(&base, &path, &key, cursor.as_deref()).await;
+            let next = page.as_ref().ok().and_then(|p| p.next_cursor.clone());
+            let failed = page.is_err();
+            if tx.send(page).await.is_err() || failed {
+                break;
+            }
+            // A repeated cursor would loop forever
+            match next {
+                Some(next) if cursor.as_ref() != Some(&next) => cursor = Some(next),
+                _ => break,
+            }
+        }
+    });
+    rx
+}
+
+// Every item of a paginated list, following cursors to the end
+pub async fn get_all(base: &str, path: &str, key: &str) -> Result<Vec<Value>, String> {
+    let mut pages = paginate(base, path, key);
+    let mut items = Vec::new();
+    while let Some(page) = pages.recv().await {
+        items.extend(page?.items);
+    }
+    Ok(items)
+}
+
+// A server document together with the ETag it was read at
+#[derive(Serialize, Clone)]
+pub struct Versioned {
+    pub etag: Option<String>,
+    pub value: Value,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WriteError {
+    // Someone else wrote in between; carries both sides so the UI can merge
+    Conflict {
+        path: String,
+        attempted: Value,
+        current: Versioned,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        WriteError::Failed { message }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Conflict { path, .. } => {
+                write!(f, "{} was changed by someone else; reload and merge", path)
+            }
+            WriteError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+fn etag_of(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+pub async fn get_versioned(base: &str, path: &str) -> Result<Versioned, String> {
+    let response = vcr::send(request(reqwest::Method::GET, base, path)).await?;
+    let etag = etag_of(&response);
+    Ok(Versioned {
+        etag,
+        value: parse(path, response).await?,
+    })
+}
+
+// Write guarded by If-Match so concurrent operators cannot clobber each other
+pub async fn post_if_match(
+    base: &str,
+    path: &str,
+    body: Value,
+    etag: Option<&str>,
+) -> Result<Versioned, WriteError> {
+    let mut builder = request(reqwest::Method::POST, base, path).json(&body);
+    if let Some(etag) = etag {
+        builder = builder.header(reqwest::header::IF_MATCH, etag);
+    }
+    let response = vcr::send(builder).await?;
+    if response.status() == reqwest::StatusCode::PRECONDITION_FAILED {
+        return Err(WriteError::Conflict {
+            path: path.to_string(),
+            attempted: body,
+            current: get_versioned(base, path).await?,
+        });
+    }
+    let etag = etag_of(&response);
+    Ok(Versioned {
+        etag,
+        value: parse(path, response).await?,
+    })
+}
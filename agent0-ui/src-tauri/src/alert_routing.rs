@@ -0,0 +1,105 @@
+// Per-server-profile notification policy: which sinks an alert reaches, and when
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::alerts::Severity;
+use crate::schedule::TimeWindow;
+use crate::{journal, profiles, storage};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sink {
+    // OS notification with the runbook and remediation actions
+    Toast,
+    // Unread count on the tray icon
+    Badge,
+    // Through the SMTP settings used for reports
+    Email,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NotificationPolicy {
+    sinks: Vec<Sink>,
+    // Quieter alerts are still recorded in the history, just not sent anywhere
+    min_severity: Severity,
+    // None inherits the default policy's quiet hours; an empty list means none
+    quiet_hours: Option<Vec<TimeWindow>>,
+    // Alerts at or above this still go out during quiet hours
+    break_through: Option<Severity>,
+}
+
+impl Default for NotificationPolicy {
+    fn default() -> Self {
+        NotificationPolicy {
+            sinks: vec![Sink::Toast, Sink::Badge],
+            min_severity: Severity::Info,
+            quiet_hours: None,
+            break_through: Some(Severity::Critical),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RoutingSettings {
+    // For profiles without their own policy
+    default: NotificationPolicy,
+    // Keyed by server profile name
+    profiles: BTreeMap<String, NotificationPolicy>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("alert_routing.json"))
+}
+
+fn load(app: &AppHandle) -> Result<RoutingSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+// Sinks an alert of `severity` from `profile` goes to right now; empty when it stays silent
+pub fn sinks(app: &AppHandle, profile: &str, severity: Severity) -> Result<Vec<Sink>, String> {
+    let settings = load(app)?;
+    let policy = settings.profiles.get(profile).unwrap_or(&settings.default);
+    if severity < policy.min_severity {
+        return Ok(Vec::new());
+    }
+    let quiet_hours = policy
+        .quiet_hours
+        .as_ref()
+        .or(settings.default.quiet_hours.as_ref());
+    let now = Utc::now();
+    let quiet = quiet_hours.map_or(false, |windows| windows.iter().any(|w| w.contains(now)));
+    if quiet && policy.break_through.map_or(true, |floor| severity < floor) {
+        return Ok(Vec::new());
+    }
+    Ok(policy.sinks.clone())
+}
+
+fn validate(policy: &NotificationPolicy) -> Result<(), String> {
+    for window in policy.quiet_hours.iter().flatten() {
+        window.validate()?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_alert_routing(app: AppHandle) -> Result<RoutingSettings, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub fn set_alert_routing(app: AppHandle, settings: RoutingSettings) -> Result<(), String> {
+    validate(&settings.default)?;
+    let known = profiles::load(&app)?.profiles;
+    for (name, policy) in &settings.profiles {
+        if !known.iter().any(|p| p.name == *name) {
+            return Err(format!("Unknown server profile '{}'", name));
+        }
+        validate(policy)?;
+    }
+    journal::write_json(&app, "Edit alert routing", &settings_path(&app)?, &settings)
+}
@@ -0,0 +1,611 @@
+// Alert rules evaluated against the local time-series store
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::alert_routing::{self, Sink};
+use crate::badge::{self, BadgeKind};
+use crate::metrics::{now_secs, MetricsStore, Sample, TimeRange};
+use crate::tags::{self, TagKind};
+use crate::{
+    audit, commands, history, journal, memory, notify, profiles, smtp, storage, store, telemetry,
+};
+
+const EVALUATE_INTERVAL: Duration = Duration::from_secs(60);
+const HISTORY_CAPACITY: usize = 200;
+// Share of the window averaged at each end so single noisy samples do not fire
+const EDGE_FRACTION: f64 = 0.1;
+// A window needs this much sample coverage before its derivative is trusted
+const MIN_COVERAGE: f64 = 0.8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+// Ordered, so notification policies can set a floor
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Warning
+    }
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    Threshold { direction: Direction, value: f64 },
+    // Relative change across the window, e.g. -50 for "dropped by more than half"
+    ChangePercent { window_secs: i64, percent: f64 },
+    // Absolute change across the window, e.g. 1e9 for "climbed by more than 1 GB"
+    Delta { window_secs: i64, delta: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AlertRule {
+    id: String,
+    name: String,
+    series: String,
+    condition: Condition,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default = "default_cooldown_secs")]
+    cooldown_secs: i64,
+    #[serde(default)]
+    runbook_url: Option<String>,
+    #[serde(default)]
+    remediations: Vec<Remediation>,
+    // When set, `series` names a per-device metric (e.g. "vram_used_bytes") and each
+    // listed GPU is watched separately with its own cooldown
+    #[serde(default)]
+    devices: Vec<u32>,
+}
+
+impl AlertRule {
+    // (series, device, cooldown key) for every stream this rule watches
+    fn targets(&self) -> Vec<(String, Option<u32>, String)> {
+        if self.devices.is_empty() {
+            return vec![(self.series.clone(), None, self.id.clone())];
+        }
+        self.devices
+            .iter()
+            .map(|&index| {
+                (
+                    telemetry::device_series(index, &self.series),
+                    Some(index),
+                    format!("{}@gpu{}", self.id, index),
+                )
+            })
+            .collect()
+    }
+}
+
+// A command-registry call offered as a one-click fix
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Remediation {
+    label: String,
+    command: String,
+    #[serde(default)]
+    args: Value,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_cooldown_secs() -> i64 {
+    900
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AlertEvent {
+    pub id: String,
+    pub rule_id: String,
+    pub name: String,
+    pub series: String,
+    #[serde(default)]
+    pub device: Option<u32>,
+    pub message: String,
+    pub value: f64,
+    pub fired_at: i64,
+    pub acknowledged: bool,
+    #[serde(default)]
+    pub severity: Severity,
+    // The server profile that was active when it fired
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub runbook_url: Option<String>,
+    #[serde(default)]
+    pub remediations: Vec<Remediation>,
+}
+
+#[derive(Default)]
+pub struct AlertState {
+    last_fired: Mutex<HashMap<String, i64>>,
+    history: Mutex<VecDeque<AlertEvent>>,
+}
+
+fn event_bytes(event: &AlertEvent) -> usize {
+    serde_json::to_vec(event).map_or(0, |v| v.len())
+}
+
+impl AlertState {
+    pub fn history_usage(&self) -> (usize, usize) {
+        let history = self.history.lock().unwrap();
+        (history.len(), history.iter().map(event_bytes).sum())
+    }
+
+    pub fn fired_between(&self, range: TimeRange) -> Vec<AlertEvent> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.fired_at >= range.from && e.fired_at <= range.to)
+            .cloned()
+            .collect()
+    }
+
+    pub fn ids(&self) -> BTreeSet<String> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.id.clone())
+            .collect()
+    }
+
+    pub fn unacknowledged(&self) -> Vec<AlertEvent> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| !e.acknowledged)
+            .cloned()
+            .collect()
+    }
+}
+
+fn rules_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("alerts.json"))
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("alert_history.json"))
+}
+
+fn load_rules(app: &AppHandle) -> Result<Vec<AlertRule>, String> {
+    storage::read_json(&rules_path(app)?)
+}
+
+fn mean(samples: &[Sample]) -> f64 {
+    samples.iter().map(|s| s.value).sum::<f64>() / samples.len() as f64
+}
+
+// Averages at the start and end of the window, or None without enough coverage
+fn window_edges(samples: &[Sample], window_secs: i64) -> Option<(f64, f64)> {
+    let (first, last) = (samples.first()?, samples.last()?);
+    if ((last.ts - first.ts) as f64) < window_secs as f64 * MIN_COVERAGE {
+        return None;
+    }
+    let edge = ((window_secs as f64 * EDGE_FRACTION) as i64).max(1);
+    let start: Vec<Sample> = samples
+        .iter()
+        .filter(|s| s.ts <= first.ts + edge)
+        .copied()
+        .collect();
+    let end: Vec<Sample> = samples
+        .iter()
+        .filter(|s| s.ts >= last.ts - edge)
+        .copied()
+        .collect();
+    Some((mean(&start), mean(&end)))
+}
+
+fn crossed(change: f64, limit: f64) -> bool {
+    if limit < 0.0 {
+        change <= limit
+    } else {
+        change >= limit
+    }
+}
+
+// Returns the observed value and a description when the rule is breached
+fn check(rule: &AlertRule, series: &str, store: &MetricsStore, now: i64) -> Option<(f64, String)> {
+    let window_secs = match &rule.condition {
+        // Only the latest sample matters, as long as it is recent
+        Condition::Threshold { .. } => 300,
+        Condition::ChangePercent { window_secs, .. } | Condition::Delta { window_secs, .. } => {
+            *window_secs
+        }
+    };
+    let samples = store.range_with_synthetic(
+        series,
+        TimeRange {
+            from: now - window_secs,
+            to: now,
+        },
+    );
+    match &rule.condition {
+        Condition::Threshold { direction, value } => {
+            let latest = samples.last()?.value;
+            let (breached, word) = match direction {
+                Direction::Above => (latest > *value, "above"),
+                Direction::Below => (latest < *value, "below"),
+            };
+            breached.then(|| {
+                (
+                    latest,
+                    format!("{} is {:.2}, {} {:.2}", series, latest, word, value),
+                )
+            })
+        }
+        Condition::ChangePercent { percent, .. } => {
+            let (start, end) = window_edges(&samples, window_secs)?;
+            if start == 0.0 {
+                return None;
+            }
+            let change = (end - start) / start.abs() * 100.0;
+            crossed(change, *percent).then(|| {
+                (
+                    change,
+                    format!(
+                        "{} changed {:+.0}% in {} min ({:.2} -> {:.2})",
+                        series,
+                        change,
+                        window_secs / 60,
+                        start,
+                        end
+                    ),
+                )
+            })
+        }
+        Condition::Delta { delta, .. } => {
+            let (start, end) = window_edges(&samples, window_secs)?;
+            let change = end - start;
+            crossed(change, *delta).then(|| {
+                (
+                    change,
+                    format!(
+                        "{} changed by {:+.2} in {} min",
+                        series,
+                        change,
+                        window_secs / 60
+                    ),
+                )
+            })
+        }
+    }
+}
+
+fn persist_history(app: &AppHandle) -> Result<(), String> {
+    let history = app.state::<AlertState>().history.lock().unwrap().clone();
+    store::refresh_alerts(app);
+    storage::write_json(&history_path(app)?, &history)
+}
+
+fn toast(app: &AppHandle, event: &AlertEvent) {
+    let mut actions = Vec::new();
+    if event.runbook_url.is_some() {
+        actions.push(("runbook".to_string(), "Runbook".to_string()));
+    }
+    for (index, remediation) in event.remediations.iter().enumerate() {
+        actions.push((format!("fix-{}", index), remediation.label.clone()));
+    }
+    let handle = app.clone();
+    let event_id = event.id.clone();
+    let runbook_url = event.runbook_url.clone();
+    notify::show_with_actions(
+        app,
+        format!("Agent-0 alert: {}", event.name),
+        event.message.clone(),
+        actions,
+        move |action| {
+            if action == "runbook" {
+                if let Some(url) = runbook_url {
+                    let _ = webbrowser::open(&url);
+                }
+            } else if let Some(index) = action.strip_prefix("fix-").and_then(|i| i.parse().ok()) {
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = remediate(&handle, &event_id, index).await {
+                        tracing::warn!("Remediation failed: {}", e);
+                    }
+                });
+            }
+        },
+    );
+}
+
+fn email(app: &AppHandle, event: &AlertEvent) -> Result<(), String> {
+    let settings = smtp::load_settings(app)?;
+    let subject = format!("Agent-0 alert: {}", event.name);
+    let html = format!(
+        "<p>{}</p><p>Server profile: {}</p>",
+        escape(&event.message),
+        escape(event.profile.as_deref().unwrap_or("unknown"))
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = smtp::send(&settings, &subject, html, None) {
+            tracing::warn!("Failed to email alert: {}", e);
+        }
+    });
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// The UI always hears about it; the profile's policy picks the louder sinks
+fn deliver(app: &AppHandle, event: &AlertEvent) {
+    let profile = event.profile.as_deref().unwrap_or_default();
+    let sinks = alert_routing::sinks(app, profile, event.severity).unwrap_or_else(|e| {
+        tracing::warn!("Alert routing failed, using defaults: {}", e);
+        vec![Sink::Toast, Sink::Badge]
+    });
+    for sink in sinks {
+        match sink {
+            Sink::Toast => toast(app, event),
+            Sink::Badge => badge::increment(app, BadgeKind::Alerts),
+            Sink::Email => {
+                if let Err(e) = email(app, event) {
+                    tracing::warn!("Failed to email alert: {}", e);
+                }
+            }
+        }
+    }
+    let _ = app.emit_all("alert-fired", event);
+}
+
+fn auto_tags(event: &AlertEvent) -> (String, Vec<String>) {
+    let found = [
+        tags::auto_tag("severity", event.severity.name()),
+        tags::auto_tag("series", &event.series),
+        event
+            .profile
+            .as_deref()
+            .and_then(|p| tags::auto_tag("server", p)),
+    ];
+    (event.id.clone(), found.into_iter().flatten().collect())
+}
+
+fn evaluate(app: &AppHandle) -> Result<(), String> {
+    let rules = load_rules(app)?;
+    let profile = profiles::active(app).ok().map(|p| p.name);
+    let now = now_secs();
+    let store = app.state::<MetricsStore>();
+    let state = app.state::<AlertState>();
+    let mut fired = Vec::new();
+    for rule in rules.iter().filter(|r| r.enabled) {
+        for (series, device, key) in rule.targets() {
+            let (value, message) = match check(rule, &series, &store, now) {
+                Some(breach) => breach,
+                None => continue,
+            };
+            let mut last_fired = state.last_fired.lock().unwrap();
+            if last_fired
+                .get(&key)
+                .map_or(false, |ts| now - ts < rule.cooldown_secs)
+            {
+                continue;
+            }
+            last_fired.insert(key.clone(), now);
+            fired.push(AlertEvent {
+                id: format!("{}-{}", key, now),
+                rule_id: rule.id.clone(),
+                name: rule.name.clone(),
+                series,
+                device,
+                message,
+                value,
+                fired_at: now,
+                acknowledged: false,
+                severity: rule.severity,
+                profile: profile.clone(),
+                runbook_url: rule.runbook_url.clone(),
+                remediations: rule.remediations.clone(),
+            });
+        }
+    }
+    if fired.is_empty() {
+        return Ok(());
+    }
+    let evicted: Vec<String> = {
+        let mut history = state.history.lock().unwrap();
+        let mut before: Vec<String> = history.iter().map(|e| e.id.clone()).collect();
+        for event in &fired {
+            if history.len() == HISTORY_CAPACITY {
+                history.pop_front();
+            }
+            history.push_back(event.clone());
+        }
+        memory::trim_to_budget(
+            &mut history,
+            memory::budgets().event_queue_bytes,
+            event_bytes,
+        );
+        // Evictions come off the front, oldest first
+        let count = (before.len() + fired.len()).saturating_sub(history.len());
+        before.truncate(count.min(before.len()));
+        before
+    };
+    tags::forget(app, TagKind::Alert, &evicted);
+    tags::record_auto(app, TagKind::Alert, fired.iter().map(auto_tags).collect());
+    for event in &fired {
+        deliver(app, event);
+    }
+    persist_history(app)
+}
+
+async fn remediate(app: &AppHandle, event_id: &str, index: usize) -> Result<Value, String> {
+    let (rule_id, remediation) = {
+        let state = app.state::<AlertState>();
+        let history = state.history.lock().unwrap();
+        let event = history
+            .iter()
+            .find(|e| e.id == event_id)
+            .ok_or_else(|| format!("Unknown alert '{}'", event_id))?;
+        let remediation = event
+            .remediations
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("Alert '{}' has no remediation {}", event_id, index))?;
+        (event.rule_id.clone(), remediation)
+    };
+    let result = history::run(
+        app,
+        &format!("alert:{}", rule_id),
+        &remediation.command,
+        remediation.args.clone(),
+    )
+    .await;
+    audit::record(
+        app,
+        &format!("alert:{}", rule_id),
+        &remediation.command,
+        &remediation.args,
+        &result,
+    );
+    result
+}
+
+pub fn start(app: AppHandle) {
+    let mut history: VecDeque<AlertEvent> = history_path(&app)
+        .and_then(|path| storage::read_json(&path))
+        .unwrap_or_default();
+    memory::trim_to_budget(
+        &mut history,
+        memory::budgets().event_queue_bytes,
+        event_bytes,
+    );
+    *app.state::<AlertState>().history.lock().unwrap() = history;
+    store::refresh_alerts(&app);
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(EVALUATE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = evaluate(&app) {
+                tracing::warn!("Alert evaluation failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_alert_rules(app: AppHandle) -> Result<Vec<AlertRule>, String> {
+    load_rules(&app)
+}
+
+#[tauri::command]
+pub fn set_alert_rules(app: AppHandle, rules: Vec<AlertRule>) -> Result<(), String> {
+    for rule in &rules {
+        if rule.id.trim().is_empty() || rule.series.trim().is_empty() {
+            return Err(format!(
+                "Alert rule '{}' needs an id and a series",
+                rule.name
+            ));
+        }
+        match &rule.condition {
+            Condition::ChangePercent { window_secs, .. } | Condition::Delta { window_secs, .. }
+                if *window_secs < 60 =>
+            {
+                return Err(format!(
+                    "Alert rule '{}' needs a window of at least 60 seconds",
+                    rule.name
+                ));
+            }
+            _ => {}
+        }
+        if let Some(url) = &rule.runbook_url {
+            let parsed = reqwest::Url::parse(url).map_err(|e| {
+                format!(
+                    "Alert rule '{}' has an invalid runbook URL: {}",
+                    rule.name, e
+                )
+            })?;
+            if parsed.scheme() != "https" && parsed.scheme() != "http" {
+                return Err(format!(
+                    "Alert rule '{}' runbook must be an http(s) URL",
+                    rule.name
+                ));
+            }
+        }
+        if let Some(unknown) = rule
+            .remediations
+            .iter()
+            .find(|r| commands::lookup(&r.command).is_none())
+        {
+            return Err(format!(
+                "Alert rule '{}' offers unknown command '{}'",
+                rule.name, unknown.command
+            ));
+        }
+    }
+    if rules
+        .iter()
+        .enumerate()
+        .any(|(i, r)| rules[..i].iter().any(|o| o.id == r.id))
+    {
+        return Err("Alert rule ids must be unique".to_string());
+    }
+    journal::write_json(&app, "Edit alert rules", &rules_path(&app)?, &rules)
+}
+
+#[tauri::command]
+pub fn get_alert_history(state: State<'_, AlertState>, limit: Option<usize>) -> Vec<AlertEvent> {
+    let history = state.history.lock().unwrap();
+    let limit = limit.unwrap_or(HISTORY_CAPACITY);
+    history.iter().rev().take(limit).cloned().collect()
+}
+
+#[tauri::command]
+pub fn acknowledge_alerts(app: AppHandle, ids: Option<Vec<String>>) -> Result<(), String> {
+    let state = app.state::<AlertState>();
+    {
+        let mut history = state.history.lock().unwrap();
+        for event in history.iter_mut() {
+            if ids.as_ref().map_or(true, |ids| ids.contains(&event.id)) {
+                event.acknowledged = true;
+            }
+        }
+    }
+    // Acknowledging some of them leaves the rest counted
+    badge::set(&app, BadgeKind::Alerts, state.unacknowledged().len() as u32);
+    persist_history(&app)
+}
+
+#[tauri::command]
+pub async fn run_remediation(
+    app: AppHandle,
+    event_id: String,
+    index: usize,
+) -> Result<Value, String> {
+    remediate(&app, &event_id, index).await
+}
@@ -0,0 +1,45 @@
+// Typed Agent-0 client; paths, payload models and calls come from openapi/agent0.json
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+// A cursor-paginated list and the key its items arrive under
+pub struct Paginated {
+    pub path: &'static str,
+    pub key: &'static str,
+}
+
+// Percent-encodes a path parameter so names with slashes or spaces stay one segment
+pub fn segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn encode<T: Serialize>(body: &T) -> Result<Value, String> {
+    serde_json::to_value(body).map_err(|e| format!("Failed to encode request body: {}", e))
+}
+
+fn decode<T: DeserializeOwned>(path: &str, value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("Invalid response from {}: {}", path, e))
+}
+
+// The whole spec is generated, including operations the app does not call yet
+#[allow(dead_code)]
+mod generated {
+    use reqwest::Method;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::agent0::{self, Versioned, WriteError};
+
+    include!(concat!(env!("OUT_DIR"), "/agent0_api.rs"));
+}
+
+pub use generated::*;
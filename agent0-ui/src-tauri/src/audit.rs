@@ -0,0 +1,154 @@
+// Append-only local audit log of actions run from this app, trimmed to its newest entries
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::metrics::now_secs;
+use crate::tags::{self, TagKind};
+use crate::{agent0, profiles, storage};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    // Entries written before tagging get one from the audit_entry_ids migration
+    #[serde(default)]
+    id: String,
+    ts: i64,
+    ui_session: String,
+    profile: String,
+    // What triggered the action, e.g. "alert:<rule id>"
+    source: String,
+    command: String,
+    args: Value,
+    ok: bool,
+    detail: Value,
+}
+
+pub const AUDIT_FILE: &str = "audit.jsonl";
+// Past this the older half of the log goes, and the tags of what it held with it
+const MAX_LOG_BYTES: u64 = 8 * 1024 * 1024;
+
+// So a trim never loses a line appended while it rewrites the file
+static LOG_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+pub fn new_entry_id(ts: i64) -> String {
+    format!("{}-{}", ts, hex::encode(rand::random::<[u8; 4]>()))
+}
+
+fn audit_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(AUDIT_FILE))
+}
+
+pub fn record(
+    app: &AppHandle,
+    source: &str,
+    command: &str,
+    args: &Value,
+    result: &Result<Value, String>,
+) {
+    let ts = now_secs();
+    let entry = AuditEntry {
+        id: new_entry_id(ts),
+        ts,
+        ui_session: agent0::ui_session_id().to_string(),
+        profile: profiles::active(app).map(|p| p.name).unwrap_or_default(),
+        source: source.to_string(),
+        command: command.to_string(),
+        args: args.clone(),
+        ok: result.is_ok(),
+        detail: match result {
+            Ok(value) => value.clone(),
+            Err(e) => Value::from(e.as_str()),
+        },
+    };
+    let written = audit_path(app).and_then(|path| {
+        // Appends follow the same safe mode and move rules as the settings files
+        storage::check_writable(&path)?;
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to encode audit entry: {}", e))?;
+        let _guard = LOG_LOCK.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| format!("Failed to write audit log: {}", e))?;
+        match fs::metadata(&path) {
+            // The entry is in either way, so a failed trim does not stop it being tagged
+            Ok(meta) if meta.len() > MAX_LOG_BYTES => Ok(trim(&path).unwrap_or_else(|e| {
+                tracing::warn!("Failed to trim audit log: {}", e);
+                Vec::new()
+            })),
+            _ => Ok(Vec::new()),
+        }
+    });
+    match written {
+        Ok(dropped) => tags::forget(app, TagKind::Audit, &dropped),
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return;
+        }
+    }
+    let found = [
+        tags::auto_tag("command", &entry.command),
+        tags::auto_tag("outcome", if entry.ok { "ok" } else { "failed" }),
+        tags::auto_tag("server", &entry.profile),
+    ];
+    tags::record_auto(
+        app,
+        TagKind::Audit,
+        vec![(entry.id, found.into_iter().flatten().collect())],
+    );
+}
+
+// Keeps the newer half of the lines; returns the ids of the entries dropped
+fn trim(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let (dropped, kept) = lines.split_at(lines.len() / 2);
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, kept.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace audit log: {}", e))?;
+    Ok(dropped
+        .iter()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .map(|entry| entry.id)
+        .filter(|id| !id.is_empty())
+        .collect())
+}
+
+pub fn entry_ids(app: &AppHandle) -> Result<BTreeSet<String>, String> {
+    let path = audit_path(app)?;
+    if !path.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .map(|entry| entry.id)
+        .filter(|id| !id.is_empty())
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_audit_log(app: AppHandle, limit: Option<usize>) -> Result<Vec<AuditEntry>, String> {
+    let path = audit_path(&app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    // Newest first; a torn last line from a crash is skipped rather than failing the read
+    Ok(text
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit.unwrap_or(500))
+        .collect())
+}
@@ -0,0 +1,234 @@
+// Unread response and unacknowledged alert counter drawn as a tray icon badge
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Icon, Manager, State};
+
+const BASE_ICON: &[u8] = include_bytes!("../icons/icon.png");
+
+const BADGE_COLOR: [u8; 4] = [0xe5, 0x39, 0x35, 0xff];
+const TEXT_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+// 3x5 glyphs, one row per byte, high bit on the left
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const PLUS: [u8; 5] = [0b000, 0b010, 0b111, 0b010, 0b000];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeKind {
+    Responses,
+    Alerts,
+}
+
+impl BadgeKind {
+    // Window whose focus counts as having seen these items; alerts are shown in the main
+    // window too, as it is the only one
+    fn window(self) -> &'static str {
+        match self {
+            BadgeKind::Responses | BadgeKind::Alerts => "main",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct BadgeState {
+    responses: u32,
+    alerts: u32,
+    total: u32,
+}
+
+#[derive(Default)]
+pub struct BadgeStore {
+    state: Mutex<BadgeState>,
+}
+
+struct Raster {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+static BASE: Lazy<Result<Raster, String>> = Lazy::new(|| decode(BASE_ICON));
+
+fn decode(bytes: &[u8]) -> Result<Raster, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("Failed to decode tray icon: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("Failed to decode tray icon: {}", e))?;
+    let pixels = &buf[..info.buffer_size()];
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 0xff])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&g| [g, g, g, 0xff]).collect(),
+        other => return Err(format!("Unsupported tray icon color type {:?}", other)),
+    };
+    Ok(Raster {
+        rgba,
+        width: info.width,
+        height: info.height,
+    })
+}
+
+fn put(raster: &mut Raster, x: i64, y: i64, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= raster.width as i64 || y >= raster.height as i64 {
+        return;
+    }
+    let i = ((y as u32 * raster.width + x as u32) * 4) as usize;
+    raster.rgba[i..i + 4].copy_from_slice(&color);
+}
+
+// Red disc in the top-right corner with the count, capped at "9+"
+fn render(base: &Raster, count: Option<u32>) -> Raster {
+    let mut raster = Raster {
+        rgba: base.rgba.clone(),
+        width: base.width,
+        height: base.height,
+    };
+    let count = match count {
+        Some(count) => count,
+        None => return raster,
+    };
+    let size = base.width.min(base.height) as i64;
+    let radius = size * 3 / 10;
+    let (cx, cy) = (size - radius - 1, radius);
+    for y in cy - radius..=cy + radius {
+        for x in cx - radius..=cx + radius {
+            if (x - cx).pow(2) + (y - cy).pow(2) <= radius * radius {
+                put(&mut raster, x, y, BADGE_COLOR);
+            }
+        }
+    }
+
+    let glyphs: Vec<[u8; 5]> = if count > 9 {
+        vec![DIGITS[9], PLUS]
+    } else {
+        vec![DIGITS[count as usize]]
+    };
+    let scale = (radius * 2 / (glyphs.len() as i64 * 4 + 1)).max(1);
+    let text_width = (glyphs.len() as i64 * 4 - 1) * scale;
+    let left = cx - text_width / 2;
+    let top = cy - 5 * scale / 2;
+    for (g, glyph) in glyphs.iter().enumerate() {
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                let x0 = left + (g as i64 * 4 + col) * scale;
+                let y0 = top + row as i64 * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        put(&mut raster, x0 + dx, y0 + dy, TEXT_COLOR);
+                    }
+                }
+            }
+        }
+    }
+    raster
+}
+
+fn refresh(app: &AppHandle, state: BadgeState) {
+    let _ = app.emit_all("badge-changed", state);
+    let base = match BASE.as_ref() {
+        Ok(base) => base,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return;
+        }
+    };
+    let raster = render(base, Some(state.total).filter(|&total| total > 0));
+    let tray = app.tray_handle();
+    // A template icon would wash the badge out to monochrome
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(state.total == 0);
+    if let Err(e) = tray.set_icon(Icon::Rgba {
+        rgba: raster.rgba,
+        width: raster.width,
+        height: raster.height,
+    }) {
+        tracing::warn!("Failed to update tray badge: {}", e);
+    }
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut BadgeState)) {
+    let store = app.state::<BadgeStore>();
+    let state = {
+        let mut state = store.state.lock().unwrap();
+        change(&mut state);
+        state.total = state.responses + state.alerts;
+        *state
+    };
+    refresh(app, state);
+}
+
+fn counter(state: &mut BadgeState, kind: BadgeKind) -> &mut u32 {
+    match kind {
+        BadgeKind::Responses => &mut state.responses,
+        BadgeKind::Alerts => &mut state.alerts,
+    }
+}
+
+// Items that arrive while their window is focused are already seen
+pub fn increment(app: &AppHandle, kind: BadgeKind) {
+    let seen = app
+        .get_window(kind.window())
+        .map_or(false, |w| w.is_focused().unwrap_or(false));
+    if !seen {
+        update(app, |state| *counter(state, kind) += 1);
+    }
+}
+
+pub fn clear(app: &AppHandle, kind: BadgeKind) {
+    set(app, kind, 0);
+}
+
+pub fn set(app: &AppHandle, kind: BadgeKind, count: u32) {
+    update(app, |state| *counter(state, kind) = count);
+}
+
+pub fn on_focus(app: &AppHandle, label: &str) {
+    for kind in [BadgeKind::Responses, BadgeKind::Alerts] {
+        if kind.window() == label {
+            clear(app, kind);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_badge_state(store: State<'_, BadgeStore>) -> BadgeState {
+    *store.state.lock().unwrap()
+}
+
+// Called by the webview when an answer completes
+#[tauri::command]
+pub fn mark_unread(app: AppHandle, kind: BadgeKind) {
+    increment(&app, kind);
+}
+
+#[tauri::command]
+pub fn clear_badge(app: AppHandle, kind: BadgeKind) {
+    clear(&app, kind);
+}
@@ -0,0 +1,323 @@
+// Batched delete, tag and export of conversations and templates, each run as one operation;
+// tagging also takes alerts and audit entries
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::alerts::AlertState;
+use crate::metrics::now_secs;
+use crate::progress::{self, Operation};
+use crate::tags::{self, TagKind};
+use crate::trash::{self, TrashEntry};
+use crate::{agent0, api, audit, storage};
+
+const MAX_BATCH: usize = 1000;
+const NOT_EXPORTABLE: &str = "Only conversations and templates can be exported";
+
+#[derive(Serialize, Clone)]
+pub struct BulkFailure {
+    // None when the batch as a whole failed, e.g. saving the index
+    id: Option<String>,
+    error: String,
+}
+
+// All or nothing: when any item fails, none of the batch takes effect
+#[derive(Serialize, Default)]
+pub struct BulkReport {
+    committed: bool,
+    succeeded: Vec<String>,
+    failed: Vec<BulkFailure>,
+    // The file bulk_export wrote
+    path: Option<String>,
+}
+
+impl BulkReport {
+    fn fail(&mut self, id: Option<&str>, error: String) {
+        self.failed.push(BulkFailure {
+            id: id.map(str::to_string),
+            error,
+        });
+    }
+}
+
+// Duplicates are dropped, keeping the first occurrence's position
+fn check_ids(ids: Vec<String>) -> Result<Vec<String>, String> {
+    let mut seen = BTreeSet::new();
+    let ids: Vec<String> = ids
+        .into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+    if ids.is_empty() || ids.len() > MAX_BATCH {
+        return Err(format!(
+            "A batch needs between 1 and {} items, got {}",
+            MAX_BATCH,
+            ids.len()
+        ));
+    }
+    Ok(ids)
+}
+
+fn template_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(name);
+    if name.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(format!("Invalid template name '{}'", name));
+    }
+    let path = storage::data_dir(app)?.join("templates").join(relative);
+    if !path.is_file() {
+        return Err(format!("No template named '{}'", name));
+    }
+    Ok(path)
+}
+
+// The work gets its own task so a cancel lands between items and still leaves the batch whole
+fn run<F, Fut>(app: &AppHandle, command: &'static str, ids: Vec<String>, work: F) -> String
+where
+    F: FnOnce(AppHandle, Vec<String>, Operation) -> Fut + Send + 'static,
+    Fut: Future<Output = BulkReport> + Send + 'static,
+{
+    let handle = app.clone();
+    progress::spawn(app, command, move |operation| async move {
+        let args = json!({ "count": ids.len() });
+        let app = handle.clone();
+        let report = tauri::async_runtime::spawn(work(handle, ids, operation))
+            .await
+            .map_err(|e| format!("Bulk task failed: {}", e))?;
+        audit::record(
+            &app,
+            "bulk",
+            command,
+            &args,
+            &Ok(json!({
+                "committed": report.committed,
+                "succeeded": report.succeeded.len(),
+                "failed": report.failed.len(),
+            })),
+        );
+        progress::encode(report)
+    })
+}
+
+async fn delete_conversations(
+    app: AppHandle,
+    ids: Vec<String>,
+    operation: Operation,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    let mut trashed: Vec<(String, TrashEntry)> = Vec::new();
+    let total = ids.len() as u64;
+    // Stops at the first failure, since everything before it is put back anyway
+    for (done, id) in ids.iter().enumerate() {
+        operation.report_ratio("deleting", done as u64, total);
+        let result = match operation.check() {
+            Ok(()) => trash::trash_conversation(&app, id).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(entry) => trashed.push((id.clone(), entry)),
+            Err(e) => {
+                report.fail(Some(id.as_str()), e);
+                break;
+            }
+        }
+    }
+    if report.failed.is_empty() {
+        let entries: Vec<TrashEntry> = trashed.iter().map(|(_, e)| e.clone()).collect();
+        match trash::add_to_index(&app, &entries) {
+            Ok(()) => {
+                report.committed = true;
+                report.succeeded = ids;
+                return report;
+            }
+            Err(e) => report.fail(None, e),
+        }
+    }
+
+    operation.report("rolling back", None);
+    let mut stranded = Vec::new();
+    for (id, entry) in trashed.into_iter().rev() {
+        if let Err(e) = trash::undo_trash_conversation(&app, &entry).await {
+            report.fail(
+                Some(id.as_str()),
+                format!("Could not be put back, left in the trash: {}", e),
+            );
+            stranded.push(entry);
+        }
+    }
+    if !stranded.is_empty() {
+        if let Err(e) = trash::add_to_index(&app, &stranded) {
+            report.fail(None, e);
+        }
+    }
+    report
+}
+
+async fn tag_items(
+    app: AppHandle,
+    kind: TagKind,
+    ids: Vec<String>,
+    tags: Vec<String>,
+    operation: Operation,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    operation.report("checking", None);
+    let known: Result<BTreeSet<String>, String> = match kind {
+        TagKind::Conversation => api::list_conversations(&agent0::base_url())
+            .await
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item["id"].as_str().map(str::to_string))
+                    .collect()
+            }),
+        TagKind::Template => Ok(ids
+            .iter()
+            .filter(|id| template_path(&app, id).is_ok())
+            .cloned()
+            .collect()),
+        TagKind::Alert => Ok(app.state::<AlertState>().ids()),
+        TagKind::Audit => audit::entry_ids(&app),
+    };
+    let known = match known {
+        Ok(known) => known,
+        Err(e) => {
+            report.fail(None, e);
+            return report;
+        }
+    };
+    for id in ids.iter().filter(|id| !known.contains(*id)) {
+        report.fail(Some(id.as_str()), format!("No such item '{}'", id));
+    }
+    if !report.failed.is_empty() {
+        return report;
+    }
+
+    operation.report("tagging", Some(50.0));
+    let saved = tags::update(&app, |index| {
+        for id in &ids {
+            index.add_user(kind, id, &tags);
+        }
+    });
+    match saved {
+        Ok(()) => {
+            report.committed = true;
+            report.succeeded = ids;
+        }
+        Err(e) => report.fail(None, e),
+    }
+    report
+}
+
+async fn export_items(
+    app: AppHandle,
+    kind: TagKind,
+    label: &'static str,
+    ids: Vec<String>,
+    operation: Operation,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    let mut items = Vec::new();
+    let total = ids.len() as u64;
+    let base = agent0::base_url();
+    for (done, id) in ids.iter().enumerate() {
+        operation.report_ratio("reading", done as u64, total);
+        if let Err(e) = operation.check() {
+            report.fail(None, e);
+            return report;
+        }
+        let item = match kind {
+            TagKind::Conversation => api::get_conversation(&base, id).await,
+            // Hex, as remote sync keeps them, so binary templates survive
+            TagKind::Template => template_path(&app, id).and_then(|path| {
+                fs::read(&path)
+                    .map(|bytes| json!({ "name": id, "hex": hex::encode(bytes) }))
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            }),
+            TagKind::Alert | TagKind::Audit => Err(NOT_EXPORTABLE.to_string()),
+        };
+        match item {
+            Ok(item) => items.push(item),
+            Err(e) => report.fail(Some(id.as_str()), e),
+        }
+    }
+    if !report.failed.is_empty() {
+        return report;
+    }
+
+    operation.report("writing", Some(100.0));
+    let mut bundle = json!({ "exported_at": now_secs() });
+    bundle[label] = Value::Array(items);
+    let written = storage::data_dir(&app).and_then(|dir| {
+        let path = dir.join("exports").join(format!(
+            "agent0-{}-{}.json",
+            label,
+            Local::now().format("%Y-%m-%d-%H%M%S")
+        ));
+        storage::write_json(&path, &bundle).map(|_| path)
+    });
+    match written {
+        Ok(path) => {
+            report.committed = true;
+            report.succeeded = ids;
+            report.path = Some(path.display().to_string());
+        }
+        Err(e) => report.fail(None, e),
+    }
+    report
+}
+
+// Moves the conversations to the trash; returns an operation id, the report arrives with its
+// final progress event
+#[tauri::command]
+pub fn bulk_delete_conversations(app: AppHandle, ids: Vec<String>) -> Result<String, String> {
+    let ids = check_ids(ids)?;
+    Ok(run(
+        &app,
+        "bulk_delete_conversations",
+        ids,
+        delete_conversations,
+    ))
+}
+
+// Adds `tags` to every item, keeping the tags each already has
+#[tauri::command]
+pub fn bulk_tag(
+    app: AppHandle,
+    kind: TagKind,
+    ids: Vec<String>,
+    tags: Vec<String>,
+) -> Result<String, String> {
+    let ids = check_ids(ids)?;
+    let tags = tags
+        .iter()
+        .map(|t| tags::normalize(t))
+        .collect::<Result<Vec<String>, String>>()?;
+    if tags.is_empty() {
+        return Err("No tags given".to_string());
+    }
+    Ok(run(&app, "bulk_tag", ids, move |app, ids, operation| {
+        tag_items(app, kind, ids, tags, operation)
+    }))
+}
+
+// Writes one JSON bundle to the data directory's "exports" folder
+#[tauri::command]
+pub fn bulk_export(app: AppHandle, kind: TagKind, ids: Vec<String>) -> Result<String, String> {
+    let label = match kind {
+        TagKind::Conversation => "conversations",
+        TagKind::Template => "templates",
+        TagKind::Alert | TagKind::Audit => return Err(NOT_EXPORTABLE.to_string()),
+    };
+    let ids = check_ids(ids)?;
+    Ok(run(&app, "bulk_export", ids, move |app, ids, operation| {
+        export_items(app, kind, label, ids, operation)
+    }))
+}
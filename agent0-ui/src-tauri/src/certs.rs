@@ -0,0 +1,182 @@
+// Self-signed certificate management for the LAN proxy
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use crate::proxy::ProxyState;
+use crate::storage;
+
+const VALIDITY_DAYS: i64 = 90;
+// Certificates this close to expiry are replaced the next time they are loaded
+const ROTATE_BEFORE_DAYS: i64 = 14;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CertificateInfo {
+    fingerprint: String,
+    created_at: i64,
+    expires_at: i64,
+    subject_alt_names: Vec<String>,
+}
+
+pub struct CertBundle {
+    cert_pem: String,
+    key_pem: String,
+    info: CertificateInfo,
+}
+
+fn cert_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("certs"))
+}
+
+// Address other LAN devices most likely reach us on; connecting a UDP socket sends nothing
+pub fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn generate(dir: &Path) -> Result<CertBundle, String> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    if let Some(ip) = lan_ip() {
+        names.push(ip.to_string());
+    }
+
+    let mut params = CertificateParams::new(names.clone())
+        .map_err(|e| format!("Invalid certificate names: {}", e))?;
+    let now = time::OffsetDateTime::now_utc();
+    let expires = now + time::Duration::days(VALIDITY_DAYS);
+    // Backdate a little so phones with skewed clocks still accept it
+    params.not_before = now - time::Duration::days(1);
+    params.not_after = expires;
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, "Agent-0 LAN Proxy");
+    params.distinguished_name = dn;
+
+    let key = KeyPair::generate().map_err(|e| format!("Failed to generate key: {}", e))?;
+    let cert = params
+        .self_signed(&key)
+        .map_err(|e| format!("Failed to generate certificate: {}", e))?;
+    let bundle = CertBundle {
+        cert_pem: cert.pem(),
+        key_pem: key.serialize_pem(),
+        info: CertificateInfo {
+            fingerprint: fingerprint(cert.der()),
+            created_at: now.unix_timestamp(),
+            expires_at: expires.unix_timestamp(),
+            subject_alt_names: names,
+        },
+    };
+
+    // Checked before the key pair is replaced, so the recorded fingerprint never goes stale
+    storage::check_writable(&dir.join("proxy.json"))?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    fs::write(dir.join("proxy.pem"), &bundle.cert_pem)
+        .map_err(|e| format!("Failed to write certificate: {}", e))?;
+    write_private(&dir.join("proxy.key"), &bundle.key_pem)?;
+    storage::write_json(&dir.join("proxy.json"), &bundle.info)?;
+    Ok(bundle)
+}
+
+fn load(dir: &Path) -> Result<Option<CertBundle>, String> {
+    let info: CertificateInfo = storage::read_json(&dir.join("proxy.json"))?;
+    if info.fingerprint.is_empty() {
+        return Ok(None);
+    }
+    match (
+        fs::read_to_string(dir.join("proxy.pem")),
+        fs::read_to_string(dir.join("proxy.key")),
+    ) {
+        (Ok(cert_pem), Ok(key_pem)) => Ok(Some(CertBundle {
+            cert_pem,
+            key_pem,
+            info,
+        })),
+        _ => Ok(None),
+    }
+}
+
+// Current certificate, generated on first use and rotated when close to expiry
+pub fn ensure_current(app: &AppHandle) -> Result<CertBundle, String> {
+    let dir = cert_dir(app)?;
+    let rotate_after =
+        time::OffsetDateTime::now_utc().unix_timestamp() + ROTATE_BEFORE_DAYS * 86400;
+    match load(&dir)? {
+        Some(bundle) if bundle.info.expires_at > rotate_after => Ok(bundle),
+        _ => generate(&dir),
+    }
+}
+
+pub fn acceptor(bundle: &CertBundle) -> Result<tokio_native_tls::TlsAcceptor, String> {
+    let identity =
+        native_tls::Identity::from_pkcs8(bundle.cert_pem.as_bytes(), bundle.key_pem.as_bytes())
+            .map_err(|e| format!("Failed to load certificate: {}", e))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity)
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+    Ok(tokio_native_tls::TlsAcceptor::from(acceptor))
+}
+
+#[tauri::command]
+pub fn get_certificate_fingerprint(app: AppHandle) -> Result<CertificateInfo, String> {
+    Ok(ensure_current(&app)?.info)
+}
+
+#[tauri::command]
+pub fn rotate_certificate(
+    app: AppHandle,
+    proxy: State<'_, ProxyState>,
+) -> Result<CertificateInfo, String> {
+    let bundle = generate(&cert_dir(&app)?)?;
+    proxy.reload_tls(acceptor(&bundle)?);
+    Ok(bundle.info)
+}
+
+// The QR payload carries the fingerprint so mobile clients can pin it on first connect
+#[tauri::command]
+pub fn export_certificate_qr(
+    app: AppHandle,
+    port: u16,
+    path: Option<String>,
+) -> Result<String, String> {
+    let bundle = ensure_current(&app)?;
+    let host = lan_ip().unwrap_or(IpAddr::from([127, 0, 0, 1]));
+    let payload = serde_json::json!({
+        "url": format!("https://{}", SocketAddr::new(host, port)),
+        "sha256": bundle.info.fingerprint,
+    })
+    .to_string();
+
+    let svg = QrCode::new(payload.as_bytes())
+        .map_err(|e| format!("Failed to encode QR code: {}", e))?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build();
+    if let Some(path) = path {
+        fs::write(&path, &svg).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    Ok(svg)
+}
@@ -0,0 +1,228 @@
+// Registry of backend commands that can be run by name outside the webview
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use tauri::{AppHandle, Manager};
+
+use crate::managed::{self, ManagedState};
+use crate::{
+    config, energy, gpu_processes, launch, power_plan, profiles, runtime_config, snapshot,
+};
+
+type CommandFuture = Pin<Box<dyn Future<Output = Result<Value, String>> + Send>>;
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    // Mutating commands change server or app state and need confirmation to re-run
+    pub mutating: bool,
+    run: fn(AppHandle, Value) -> CommandFuture,
+}
+
+// Arguments arrive keyed the way the webview sends them (camelCase)
+fn arg<T: DeserializeOwned>(args: &Value, key: &str) -> Result<T, String> {
+    serde_json::from_value(args.get(key).cloned().unwrap_or(Value::Null))
+        .map_err(|e| format!("Invalid argument '{}': {}", key, e))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to encode command result: {}", e))
+}
+
+fn pause_service(app: AppHandle, _args: Value) -> CommandFuture {
+    Box::pin(async { crate::pause_service(app).await.map(Value::from) })
+}
+
+fn resume_service(app: AppHandle, _args: Value) -> CommandFuture {
+    Box::pin(async { crate::resume_service(app).await.map(Value::from) })
+}
+
+fn set_energy_settings(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(energy::set_energy_settings(app, arg(&args, "settings")?)?) })
+}
+
+fn set_power_plan(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(power_plan::set_power_plan(app, arg(&args, "plan")?).await?) })
+}
+
+fn set_power_override(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        to_value(power_plan::set_power_override(app, arg(&args, "powerOverride")?).await?)
+    })
+}
+
+fn get_power_plan_status(app: AppHandle, _args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(power_plan::get_power_plan_status(app)?) })
+}
+
+fn set_server_config(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        let result =
+            config::set_server_config(app, arg(&args, "config")?, arg(&args, "etag")?).await;
+        to_value(result.map_err(|e| e.to_string())?)
+    })
+}
+
+fn set_server_flag(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        runtime_config::set_server_flag(
+            app,
+            arg(&args, "name")?,
+            arg(&args, "value")?,
+            arg(&args, "expiresInSecs")?,
+        )
+        .await
+    })
+}
+
+fn set_server_log_level(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        runtime_config::set_server_log_level(
+            app,
+            arg(&args, "logger")?,
+            arg(&args, "level")?,
+            arg(&args, "expiresInSecs")?,
+        )
+        .await
+    })
+}
+
+fn run_pre_training_hook(app: AppHandle, _args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(gpu_processes::run_pre_training_hook(app)?) })
+}
+
+fn set_active_profile(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(profiles::set_active_profile(app, arg(&args, "name")?)?) })
+}
+
+fn open_view(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(launch::open_view(&app, arg(&args, "view")?)?) })
+}
+
+fn ask(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move { to_value(launch::ask(&app, arg(&args, "prompt")?)?) })
+}
+
+fn capture_panel_snapshot(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        to_value(
+            snapshot::capture_panel_snapshot(
+                app,
+                arg(&args, "panel")?,
+                arg(&args, "range")?,
+                arg(&args, "path")?,
+            )
+            .await?,
+        )
+    })
+}
+
+fn start_managed_server(app: AppHandle, args: Value) -> CommandFuture {
+    Box::pin(async move {
+        let started = managed::start(&app, arg(&args, "onConflict")?).await;
+        to_value(started.map_err(|e| e.to_string())?)
+    })
+}
+
+fn stop_managed_server(app: AppHandle, _args: Value) -> CommandFuture {
+    Box::pin(async move {
+        to_value(managed::stop_managed_server(
+            app.clone(),
+            app.state::<ManagedState>(),
+        )?)
+    })
+}
+
+static REGISTRY: &[CommandSpec] = &[
+    CommandSpec {
+        name: "pause_service",
+        mutating: true,
+        run: pause_service,
+    },
+    CommandSpec {
+        name: "resume_service",
+        mutating: true,
+        run: resume_service,
+    },
+    CommandSpec {
+        name: "set_energy_settings",
+        mutating: true,
+        run: set_energy_settings,
+    },
+    CommandSpec {
+        name: "set_power_plan",
+        mutating: true,
+        run: set_power_plan,
+    },
+    CommandSpec {
+        name: "set_power_override",
+        mutating: true,
+        run: set_power_override,
+    },
+    CommandSpec {
+        name: "get_power_plan_status",
+        mutating: false,
+        run: get_power_plan_status,
+    },
+    CommandSpec {
+        name: "set_server_config",
+        mutating: true,
+        run: set_server_config,
+    },
+    CommandSpec {
+        name: "set_server_flag",
+        mutating: true,
+        run: set_server_flag,
+    },
+    CommandSpec {
+        name: "set_server_log_level",
+        mutating: true,
+        run: set_server_log_level,
+    },
+    CommandSpec {
+        name: "run_pre_training_hook",
+        mutating: true,
+        run: run_pre_training_hook,
+    },
+    CommandSpec {
+        name: "set_active_profile",
+        mutating: true,
+        run: set_active_profile,
+    },
+    CommandSpec {
+        name: "open_view",
+        mutating: false,
+        run: open_view,
+    },
+    CommandSpec {
+        name: "ask",
+        mutating: false,
+        run: ask,
+    },
+    CommandSpec {
+        name: "capture_panel_snapshot",
+        // Writes a file, possibly over one at a caller-chosen path
+        mutating: true,
+        run: capture_panel_snapshot,
+    },
+    CommandSpec {
+        name: "start_managed_server",
+        mutating: true,
+        run: start_managed_server,
+    },
+    CommandSpec {
+        name: "stop_managed_server",
+        mutating: true,
+        run: stop_managed_server,
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static CommandSpec> {
+    REGISTRY.iter().find(|spec| spec.name == name)
+}
+
+pub async fn run(app: &AppHandle, name: &str, args: Value) -> Result<Value, String> {
+    let spec = lookup(name).ok_or_else(|| format!("Unknown command '{}'", name))?;
+    (spec.run)(app.clone(), args).await
+}
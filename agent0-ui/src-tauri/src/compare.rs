@@ -0,0 +1,148 @@
+// Side-by-side comparison of one metric over two time windows, e.g. this week vs last
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::{MetricsStore, Sample, TimeRange};
+
+// Aim for roughly this many points per aligned series
+const TARGET_POINTS: i64 = 200;
+const MIN_BUCKET_SECS: i64 = 60;
+
+#[derive(Serialize, Default)]
+pub struct WindowSummary {
+    pub samples: usize,
+    pub mean: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+}
+
+// Bucket averages at the same offset from the start of each window
+#[derive(Serialize)]
+pub struct AlignedPoint {
+    offset_secs: i64,
+    a: Option<f64>,
+    b: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct Delta {
+    pub absolute: f64,
+    // None when the baseline is zero
+    pub percent: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct WindowComparison {
+    metric: String,
+    window_a: TimeRange,
+    window_b: TimeRange,
+    bucket_secs: i64,
+    points: Vec<AlignedPoint>,
+    summary_a: WindowSummary,
+    summary_b: WindowSummary,
+    // Window b relative to window a, so a regression this week shows as b worse than a
+    mean_delta: Option<Delta>,
+    p95_delta: Option<Delta>,
+}
+
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    Some(sorted[rank.min(sorted.len() - 1)])
+}
+
+pub fn summarize(samples: &[Sample]) -> WindowSummary {
+    if samples.is_empty() {
+        return WindowSummary::default();
+    }
+    let mut values: Vec<f64> = samples.iter().map(|s| s.value).collect();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    WindowSummary {
+        samples: values.len(),
+        mean: Some(values.iter().sum::<f64>() / values.len() as f64),
+        min: values.first().copied(),
+        max: values.last().copied(),
+        p50: percentile(&values, 50.0),
+        p95: percentile(&values, 95.0),
+    }
+}
+
+pub fn delta(a: Option<f64>, b: Option<f64>) -> Option<Delta> {
+    let (a, b) = (a?, b?);
+    Some(Delta {
+        absolute: b - a,
+        percent: (a != 0.0).then(|| (b - a) / a.abs() * 100.0),
+    })
+}
+
+fn bucketize(samples: &[Sample], from: i64, bucket_secs: i64, buckets: usize) -> Vec<Option<f64>> {
+    let mut sums = vec![(0.0, 0usize); buckets];
+    for sample in samples {
+        let bucket = ((sample.ts - from) / bucket_secs) as usize;
+        if let Some((sum, count)) = sums.get_mut(bucket) {
+            *sum += sample.value;
+            *count += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|(sum, count)| (count > 0).then(|| sum / count as f64))
+        .collect()
+}
+
+#[tauri::command]
+pub fn compare_metric_windows(
+    app: AppHandle,
+    metric: String,
+    window_a: TimeRange,
+    window_b: TimeRange,
+    bucket_secs: Option<i64>,
+) -> Result<WindowComparison, String> {
+    for window in [&window_a, &window_b] {
+        if window.to <= window.from {
+            return Err(format!(
+                "Invalid window {}..{}: end must be after start",
+                window.from, window.to
+            ));
+        }
+    }
+    // Windows of different lengths are aligned on the longer one
+    let span = (window_a.to - window_a.from).max(window_b.to - window_b.from);
+    let bucket_secs = bucket_secs
+        .unwrap_or(span / TARGET_POINTS)
+        .max(MIN_BUCKET_SECS);
+    let buckets = (span / bucket_secs + 1) as usize;
+
+    let store = app.state::<MetricsStore>();
+    let samples_a = store.range(&metric, window_a);
+    let samples_b = store.range(&metric, window_b);
+    let a = bucketize(&samples_a, window_a.from, bucket_secs, buckets);
+    let b = bucketize(&samples_b, window_b.from, bucket_secs, buckets);
+    let points = a
+        .into_iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (a, b))| AlignedPoint {
+            offset_secs: i as i64 * bucket_secs,
+            a,
+            b,
+        })
+        .collect();
+
+    let summary_a = summarize(&samples_a);
+    let summary_b = summarize(&samples_b);
+    Ok(WindowComparison {
+        mean_delta: delta(summary_a.mean, summary_b.mean),
+        p95_delta: delta(summary_a.p95, summary_b.p95),
+        metric,
+        window_a,
+        window_b,
+        bucket_secs,
+        points,
+        summary_a,
+        summary_b,
+    })
+}
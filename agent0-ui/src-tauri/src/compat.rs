@@ -0,0 +1,209 @@
+// Hardware compatibility checks run before model switches and adapter loads
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use tauri::{AppHandle, Manager};
+
+use crate::agent0::{self, Versioned, WriteError};
+use crate::api::{self, AdapterRef, ModelRequirements, ModelSelection};
+use crate::formatting::Formatter;
+use crate::gpu::{Gpu, GpuDevice};
+use crate::{install_mode, model_diff};
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum SwitchError {
+    IncompatibleHardware {
+        model: String,
+        requirements: ModelRequirements,
+        problems: Vec<String>,
+        gpus: Vec<GpuDevice>,
+    },
+    // The model was switched by someone else since it was read
+    Conflict {
+        path: String,
+        attempted: Value,
+        current: Versioned,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for SwitchError {
+    fn from(message: String) -> Self {
+        SwitchError::Failed { message }
+    }
+}
+
+impl From<WriteError> for SwitchError {
+    fn from(error: WriteError) -> Self {
+        match error {
+            WriteError::Conflict {
+                path,
+                attempted,
+                current,
+            } => SwitchError::Conflict {
+                path,
+                attempted,
+                current,
+            },
+            WriteError::Failed { message } => SwitchError::Failed { message },
+        }
+    }
+}
+
+impl fmt::Display for SwitchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwitchError::IncompatibleHardware {
+                model, problems, ..
+            } => write!(
+                f,
+                "This machine cannot run {}: {}",
+                model,
+                problems.join("; ")
+            ),
+            SwitchError::Conflict { path, .. } => {
+                write!(f, "{} was changed by someone else; reload and merge", path)
+            }
+            SwitchError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Result<(i32, i32), String> {
+    let mut parts = version.trim().splitn(2, '.');
+    let major = parts.next().unwrap_or("").parse::<i32>();
+    let minor = parts.next().unwrap_or("0").parse::<i32>();
+    match (major, minor) {
+        (Ok(major), Ok(minor)) => Ok((major, minor)),
+        _ => Err(format!("Invalid version '{}'", version)),
+    }
+}
+
+async fn requirements(base: &str, model: &str) -> Result<ModelRequirements, String> {
+    api::get_model_requirements(base, model).await
+}
+
+// Problems found; empty when at least one GPU satisfies everything
+fn evaluate(
+    gpu: &Gpu,
+    format: &Formatter,
+    requirements: &ModelRequirements,
+) -> Result<(Vec<String>, Vec<GpuDevice>), String> {
+    let needs_gpu = requirements.min_cuda.is_some()
+        || requirements.min_vram_bytes.is_some()
+        || requirements.min_compute_capability.is_some();
+    if !needs_gpu {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let devices = match gpu.devices() {
+        Ok(devices) if !devices.is_empty() => devices,
+        _ => return Ok((vec!["No NVIDIA GPU detected".to_string()], Vec::new())),
+    };
+
+    let mut problems = Vec::new();
+    if let Some(min_cuda) = &requirements.min_cuda {
+        let needed = parse_version(min_cuda)?;
+        let driver = gpu.cuda_driver_version()?;
+        if driver < needed {
+            problems.push(format!(
+                "driver supports CUDA {}.{}, model needs {}",
+                driver.0, driver.1, min_cuda
+            ));
+        }
+    }
+    let min_capability = match &requirements.min_compute_capability {
+        Some(cc) => Some(parse_version(cc)?),
+        None => None,
+    };
+    let min_vram = requirements.min_vram_bytes.unwrap_or(0);
+    let fits = |d: &GpuDevice| {
+        d.total_memory_bytes >= min_vram
+            && min_capability.map_or(true, |cc| d.compute_capability >= cc)
+    };
+    if !devices.iter().any(fits) {
+        for device in &devices {
+            let mut reasons = Vec::new();
+            if device.total_memory_bytes < min_vram {
+                reasons.push(format!(
+                    "{} VRAM < {}",
+                    format.bytes(device.total_memory_bytes as f64),
+                    format.bytes(min_vram as f64)
+                ));
+            }
+            if let Some(cc) = min_capability {
+                if device.compute_capability < cc {
+                    reasons.push(format!(
+                        "compute capability {}.{} < {}.{}",
+                        device.compute_capability.0, device.compute_capability.1, cc.0, cc.1
+                    ));
+                }
+            }
+            problems.push(format!(
+                "GPU {} ({}): {}",
+                device.index,
+                device.name,
+                reasons.join(", ")
+            ));
+        }
+    }
+    Ok((problems, devices))
+}
+
+pub async fn ensure_compatible(
+    app: &AppHandle,
+    base: &str,
+    model: &str,
+) -> Result<(), SwitchError> {
+    // No local hardware to check against; the server validates its own
+    if install_mode::is_remote_only() {
+        return Ok(());
+    }
+    let requirements = requirements(base, model).await?;
+    let (problems, gpus) = evaluate(&app.state::<Gpu>(), &Formatter::load(app), &requirements)?;
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(SwitchError::IncompatibleHardware {
+            model: model.to_string(),
+            requirements,
+            problems,
+            gpus,
+        })
+    }
+}
+
+// Adapters inherit the requirements of the base model they were trained on; adapters
+// that only exist on the server have no local config and are left to the server to check
+pub fn adapter_base_model(app: &AppHandle, adapter: &str) -> Option<String> {
+    let config = model_diff::read_adapter_config(app, adapter).ok()?;
+    config["base_model_name_or_path"]
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+#[tauri::command]
+pub async fn check_model_compatibility(app: AppHandle, model: String) -> Result<(), SwitchError> {
+    ensure_compatible(&app, &agent0::base_url(), &model).await
+}
+
+#[tauri::command]
+pub async fn switch_model(app: AppHandle, model: String) -> Result<(), SwitchError> {
+    let base = agent0::base_url();
+    ensure_compatible(&app, &base, &model).await?;
+    // Written against the ETag it was read at, so two operators switching cannot clobber
+    let current = api::get_model(&base).await?;
+    api::set_model(&base, &ModelSelection { model }, current.etag.as_deref()).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_adapter(app: AppHandle, name: String) -> Result<(), SwitchError> {
+    if let Some(base) = adapter_base_model(&app, &name) {
+        ensure_compatible(&app, &agent0::base_url(), &base).await?;
+    }
+    api::load_adapter(&agent0::base_url(), &AdapterRef { name }).await?;
+    Ok(())
+}
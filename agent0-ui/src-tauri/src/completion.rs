@@ -0,0 +1,142 @@
+// OS notifications with an answer preview when slow chat responses finish out of sight
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, ClipboardManager, Manager};
+
+use crate::badge::{self, BadgeKind};
+use crate::{journal, notify, storage};
+
+const PREVIEW_CHARS: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CompletionSettings {
+    enabled: bool,
+    // Faster answers are assumed to have been watched as they streamed
+    min_duration_secs: u64,
+    muted_conversations: Vec<String>,
+}
+
+impl Default for CompletionSettings {
+    fn default() -> Self {
+        CompletionSettings {
+            enabled: true,
+            min_duration_secs: 20,
+            muted_conversations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct OpenConversation {
+    conversation_id: String,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("completion.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<CompletionSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+fn preview(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+// Unfocused covers hidden, minimised and buried behind other apps
+fn out_of_sight(app: &AppHandle) -> bool {
+    app.get_window("main")
+        .map_or(true, |w| !w.is_focused().unwrap_or(false))
+}
+
+fn open_conversation(app: &AppHandle, conversation_id: String) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit_all("open-conversation", OpenConversation { conversation_id });
+}
+
+fn show(app: &AppHandle, title: String, body: String, conversation_id: String, text: String) {
+    let actions = vec![
+        ("open".to_string(), "Open".to_string()),
+        ("copy".to_string(), "Copy".to_string()),
+    ];
+    let handle = app.clone();
+    notify::show_with_actions(app, title, body, actions, move |action| match action {
+        "open" | "default" => open_conversation(&handle, conversation_id),
+        "copy" => {
+            if let Err(e) = handle.clipboard_manager().write_text(text) {
+                tracing::warn!("Failed to copy answer: {}", e);
+            }
+        }
+        _ => {}
+    });
+}
+
+// Called by the webview when a streamed answer finishes; returns whether it notified
+#[tauri::command]
+pub fn notify_response_complete(
+    app: AppHandle,
+    conversation_id: String,
+    title: Option<String>,
+    text: String,
+    duration_ms: u64,
+) -> Result<bool, String> {
+    if !out_of_sight(&app) {
+        return Ok(false);
+    }
+    badge::increment(&app, BadgeKind::Responses);
+    let settings = load_settings(&app)?;
+    if !settings.enabled
+        || duration_ms < settings.min_duration_secs * 1000
+        || settings.muted_conversations.contains(&conversation_id)
+    {
+        return Ok(false);
+    }
+    let title = title.unwrap_or_else(|| "Agent-0 answer ready".to_string());
+    show(&app, title, preview(&text), conversation_id, text);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_completion_settings(app: AppHandle) -> Result<CompletionSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_completion_settings(app: AppHandle, settings: CompletionSettings) -> Result<(), String> {
+    journal::write_json(
+        &app,
+        "Edit completion notifications",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
+
+#[tauri::command]
+pub fn set_conversation_muted(
+    app: AppHandle,
+    conversation_id: String,
+    muted: bool,
+) -> Result<CompletionSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings
+        .muted_conversations
+        .retain(|id| *id != conversation_id);
+    if muted {
+        settings.muted_conversations.push(conversation_id);
+    }
+    let label = if muted {
+        "Mute conversation"
+    } else {
+        "Unmute conversation"
+    };
+    journal::write_json(&app, label, &settings_path(&app)?, &settings)?;
+    Ok(settings)
+}
@@ -0,0 +1,96 @@
+// Versioned Agent-0 server config with conflict-safe writes and three-way merge
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use tauri::AppHandle;
+
+use crate::agent0::{self, Versioned, WriteError};
+use crate::api;
+use crate::journal::{self, JournalTarget};
+
+#[derive(Serialize)]
+pub struct MergedConfig {
+    merged: Value,
+    // JSON pointers both sides changed differently; the server's value was kept
+    conflicts: Vec<String>,
+}
+
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn merge(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &str,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+    if let (Some(Value::Object(ours)), Some(Value::Object(theirs))) = (ours, theirs) {
+        let empty = Map::new();
+        let base = match base {
+            Some(Value::Object(base)) => base,
+            _ => &empty,
+        };
+        let keys: BTreeSet<&String> = base
+            .keys()
+            .chain(ours.keys())
+            .chain(theirs.keys())
+            .collect();
+        let mut merged = Map::new();
+        for key in keys {
+            let child = format!("{}/{}", path, pointer_segment(key));
+            if let Some(value) = merge(
+                base.get(key),
+                ours.get(key),
+                theirs.get(key),
+                &child,
+                conflicts,
+            ) {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    conflicts.push(if path.is_empty() { "/" } else { path }.to_string());
+    theirs.cloned()
+}
+
+#[tauri::command]
+pub async fn get_server_config() -> Result<Versioned, String> {
+    api::get_config(&agent0::base_url()).await
+}
+
+#[tauri::command]
+pub async fn set_server_config(
+    app: AppHandle,
+    config: Value,
+    etag: Option<String>,
+) -> Result<Versioned, WriteError> {
+    let base = agent0::base_url();
+    let before = api::get_config(&base).await?;
+    let written = api::set_config(&base, &config, etag.as_deref()).await?;
+    journal::record(
+        &app,
+        "Edit server config",
+        JournalTarget::ServerConfig { base_url: base },
+        before.value,
+        config,
+    );
+    Ok(written)
+}
+
+// base is what the operator loaded, ours their edit, theirs the server's current copy
+#[tauri::command]
+pub fn merge_config(base: Value, ours: Value, theirs: Value) -> MergedConfig {
+    let mut conflicts = Vec::new();
+    let merged =
+        merge(Some(&base), Some(&ours), Some(&theirs), "", &mut conflicts).unwrap_or(Value::Null);
+    MergedConfig { merged, conflicts }
+}
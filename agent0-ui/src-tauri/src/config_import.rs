@@ -0,0 +1,244 @@
+// Onboarding from an existing Agent-0 deployment config (YAML, TOML, JSON or the live server)
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::power_plan::{self, PowerPlan, TariffWindow};
+use crate::profiles::{self, ServerProfile};
+use crate::{agent0, api};
+
+// Keys Agent-0 has used for its listening endpoint, oldest layout last
+const HOST_KEYS: [&str; 3] = ["/server/host", "/host", "/api/host"];
+const PORT_KEYS: [&str; 3] = ["/server/port", "/port", "/api/port"];
+const EXTRA_PORT_KEYS: [(&str, &str); 4] = [
+    ("metrics", "/metrics/port"),
+    ("metrics", "/metrics_port"),
+    ("admin", "/admin/port"),
+    ("shim", "/shim/port"),
+];
+const MODEL_DIR_KEYS: [&str; 5] = [
+    "/models/dir",
+    "/models/dirs",
+    "/model_dir",
+    "/adapters/dir",
+    "/adapter_dir",
+];
+// Read but not needed by the UI, so not worth flagging
+const IGNORED_KEYS: [&str; 2] = ["/version", "/models/default"];
+
+#[derive(Serialize)]
+pub struct ImportedConfig {
+    source: String,
+    profile: ServerProfile,
+    // Shown for review only; the app has no setting of its own for these
+    ports: BTreeMap<String, u16>,
+    model_dirs: Vec<String>,
+    // Tariff windows from the server's schedules, left disabled until reviewed
+    power_plan: Option<PowerPlan>,
+    // JSON pointers of fields the importer does not understand
+    unrecognized: Vec<String>,
+    warnings: Vec<String>,
+    // The profile, and the power plan when none was set up yet
+    saved: bool,
+}
+
+fn parse_text(path: &Path, text: &str) -> Result<Value, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let invalid = |e: String| format!("Invalid config {}: {}", path.display(), e);
+    match ext.as_str() {
+        "toml" => toml::from_str(text).map_err(|e| invalid(e.to_string())),
+        "json" => serde_json::from_str(text).map_err(|e| invalid(e.to_string())),
+        "yaml" | "yml" => serde_yaml::from_str(text).map_err(|e| invalid(e.to_string())),
+        _ => Err(format!(
+            "Unsupported config format for {}, expected .yaml, .toml or .json",
+            path.display()
+        )),
+    }
+}
+
+// Removes and returns the value at a JSON pointer, pruning objects left empty
+fn take(root: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, key) = pointer.rsplit_once('/')?;
+    let container = if parent.is_empty() {
+        root.as_object_mut()?
+    } else {
+        root.pointer_mut(parent)?.as_object_mut()?
+    };
+    let value = container.remove(key)?;
+    if container.is_empty() && !parent.is_empty() {
+        take(root, parent);
+    }
+    Some(value)
+}
+
+fn first(root: &mut Value, pointers: &[&str]) -> Option<Value> {
+    let mut found = None;
+    // Every alias is consumed so the losers are not reported as unrecognized
+    for pointer in pointers {
+        if let Some(value) = take(root, pointer) {
+            found.get_or_insert(value);
+        }
+    }
+    found
+}
+
+fn leaves(value: &Value, path: &str, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                leaves(child, &format!("{}/{}", path, key), out);
+            }
+        }
+        _ => out.push(path.to_string()),
+    }
+}
+
+fn as_port(value: &Value) -> Option<u16> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|n| u16::try_from(n).ok()),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn map_config(source: &str, mut config: Value, fallback_url: Option<&str>) -> ImportedConfig {
+    let mut warnings = Vec::new();
+    if !config.is_object() {
+        warnings.push("Config is not a table of settings".to_string());
+        config = Value::Object(Map::new());
+    }
+
+    let name = take(&mut config, "/name").and_then(|v| v.as_str().map(|s| s.to_string()));
+    let host = first(&mut config, &HOST_KEYS).and_then(|v| v.as_str().map(|s| s.to_string()));
+    let port = first(&mut config, &PORT_KEYS);
+    let port = port.as_ref().and_then(as_port);
+    // A wildcard bind address is reached through loopback from this machine
+    let host = match host.as_deref() {
+        None | Some("0.0.0.0") | Some("::") | Some("") => "localhost".to_string(),
+        Some(host) => host.to_string(),
+    };
+    let base_url = match (fallback_url, port) {
+        (Some(url), _) => url.trim_end_matches('/').to_string(),
+        (None, Some(port)) => format!("http://{}:{}", host, port),
+        (None, None) => {
+            warnings.push(format!(
+                "No port found, assuming {}",
+                agent0::DEFAULT_BASE_URL
+            ));
+            agent0::DEFAULT_BASE_URL.to_string()
+        }
+    };
+
+    let mut ports = BTreeMap::new();
+    if let Some(port) = port {
+        ports.insert("api".to_string(), port);
+    }
+    for (label, pointer) in EXTRA_PORT_KEYS.iter() {
+        if let Some(value) = take(&mut config, pointer) {
+            match as_port(&value) {
+                Some(port) => {
+                    ports.insert(label.to_string(), port);
+                }
+                None => warnings.push(format!("{} is not a valid port", pointer)),
+            }
+        }
+    }
+
+    let mut model_dirs = Vec::new();
+    for pointer in MODEL_DIR_KEYS.iter() {
+        match take(&mut config, pointer) {
+            Some(Value::String(dir)) => model_dirs.push(dir),
+            Some(Value::Array(dirs)) => model_dirs.extend(
+                dirs.iter()
+                    .filter_map(|d| d.as_str().map(|s| s.to_string())),
+            ),
+            Some(_) => warnings.push(format!("{} is not a directory path", pointer)),
+            None => {}
+        }
+    }
+    // The same directory can appear under several aliases, not always next to each other
+    let mut seen = BTreeSet::new();
+    model_dirs.retain(|dir| seen.insert(dir.clone()));
+
+    let power_plan = match take(&mut config, "/schedules") {
+        Some(Value::Array(items)) => {
+            let mut windows = Vec::new();
+            for (i, item) in items.into_iter().enumerate() {
+                match serde_json::from_value::<TariffWindow>(item) {
+                    Ok(window) => windows.push(window),
+                    Err(e) => warnings.push(format!("Schedule {} was skipped: {}", i, e)),
+                }
+            }
+            Some(PowerPlan::disabled(windows))
+        }
+        Some(_) => {
+            warnings.push("/schedules is not a list".to_string());
+            None
+        }
+        None => None,
+    };
+
+    for pointer in IGNORED_KEYS.iter() {
+        take(&mut config, pointer);
+    }
+    let mut unrecognized = Vec::new();
+    leaves(&config, "", &mut unrecognized);
+
+    ImportedConfig {
+        source: source.to_string(),
+        profile: ServerProfile {
+            name: name.unwrap_or_else(|| host.clone()),
+            base_url,
+        },
+        ports,
+        model_dirs,
+        power_plan,
+        unrecognized,
+        warnings,
+        saved: false,
+    }
+}
+
+// A path reads a config file; an http(s) URL asks that server for its running config
+#[tauri::command]
+pub async fn import_server_config(
+    app: AppHandle,
+    path_or_url: String,
+    save: bool,
+) -> Result<ImportedConfig, String> {
+    let source = path_or_url.trim();
+    let mut imported = if source.starts_with("http://") || source.starts_with("https://") {
+        let base = source.trim_end_matches('/');
+        let config = api::get_config(base).await?.value;
+        map_config(source, config, Some(base))
+    } else {
+        let path = Path::new(source);
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        map_config(source, parse_text(path, &text)?, None)
+    };
+    if save {
+        profiles::save_server_profile(app.clone(), imported.profile.clone())?;
+        if let Some(plan) = imported.power_plan.clone() {
+            // Never over a plan already set up here; the import stays disabled until reviewed
+            if !power_plan::get_power_plan(app.clone())?.has_windows() {
+                power_plan::set_power_plan(app, plan).await?;
+            } else {
+                imported.warnings.push(
+                    "The power plan already has tariff windows, so the imported schedules were \
+                     not saved"
+                        .to_string(),
+                );
+            }
+        }
+        imported.saved = true;
+    }
+    Ok(imported)
+}
@@ -0,0 +1,213 @@
+// Token costs attributed to conversations and personas, with optional monthly quotas per persona
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::metrics::now_secs;
+use crate::{journal, storage};
+
+// Requests sent without a persona are billed to this one
+pub const DEFAULT_PERSONA: &str = "default";
+// Months of per-persona totals kept, including the current one
+const MONTHS_KEPT: usize = 13;
+
+// Serialises the ledger's read-modify-write between concurrent chats
+static LEDGER_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Usage {
+    requests: u64,
+    tokens: u64,
+    cost_usd: f64,
+}
+
+impl Usage {
+    fn add(&mut self, tokens: u64, cost_usd: f64) {
+        self.requests += 1;
+        self.tokens += tokens;
+        self.cost_usd += cost_usd;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ConversationCost {
+    total: Usage,
+    by_persona: BTreeMap<String, Usage>,
+    // Council members that answered, e.g. "analyst"
+    by_voice: BTreeMap<String, Usage>,
+    by_model: BTreeMap<String, Usage>,
+    updated: i64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Ledger {
+    conversations: BTreeMap<String, ConversationCost>,
+    // Local calendar month ("2026-10") to persona to what it used
+    monthly: BTreeMap<String, BTreeMap<String, Usage>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CostQuotas {
+    // Persona to USD per calendar month; personas not listed are unlimited
+    monthly_usd: BTreeMap<String, f64>,
+}
+
+fn ledger_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("costs.json"))
+}
+
+fn quotas_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("cost_quotas.json"))
+}
+
+fn load_ledger(app: &AppHandle) -> Result<Ledger, String> {
+    storage::read_json(&ledger_path(app)?)
+}
+
+fn load_quotas(app: &AppHandle) -> Result<CostQuotas, String> {
+    storage::read_json(&quotas_path(app)?)
+}
+
+fn current_month() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+// Refuses a submission once the persona has spent its quota for this month
+pub fn check_quota(app: &AppHandle, persona: &str) -> Result<(), String> {
+    let quota = match load_quotas(app)?.monthly_usd.get(persona) {
+        Some(quota) => *quota,
+        None => return Ok(()),
+    };
+    let spent = load_ledger(app)?
+        .monthly
+        .get(&current_month())
+        .and_then(|personas| personas.get(persona))
+        .map_or(0.0, |usage| usage.cost_usd);
+    if spent >= quota {
+        return Err(format!(
+            "Quota exceeded: persona '{}' has used ${:.2} of its ${:.2} monthly quota; it resets at the start of next month",
+            persona, spent, quota
+        ));
+    }
+    Ok(())
+}
+
+// An explicit persona wins over the one language routing picked
+pub fn persona(requested: Option<&str>, routed: Option<&str>) -> String {
+    requested
+        .or(routed)
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or(DEFAULT_PERSONA)
+        .to_string()
+}
+
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().filter(|n| n.is_finite() && *n >= 0.0)
+}
+
+// Bills a chat reply ({"cost_usd", "voices": [{voice, model, tokens, cost}]}) to its persona and,
+// when known, its conversation; offline demo replies cost nothing and are skipped
+pub fn attribute(app: &AppHandle, conversation_id: Option<&str>, persona: &str, reply: &Value) {
+    if reply["offline"].as_bool() == Some(true) {
+        return;
+    }
+    let voices = reply["voices"].as_array().cloned().unwrap_or_default();
+    let voice_cost: f64 = voices.iter().filter_map(|v| number(&v["cost"])).sum();
+    let voice_tokens: u64 = voices.iter().filter_map(|v| v["tokens"].as_u64()).sum();
+    let cost = number(&reply["cost_usd"]).unwrap_or(voice_cost);
+    let tokens = reply["tokens"].as_u64().unwrap_or(voice_tokens);
+
+    let _guard = LEDGER_LOCK.lock().unwrap();
+    let recorded = load_ledger(app).and_then(|mut ledger| {
+        if let Some(id) = conversation_id {
+            let conversation = ledger.conversations.entry(id.to_string()).or_default();
+            conversation.total.add(tokens, cost);
+            conversation
+                .by_persona
+                .entry(persona.to_string())
+                .or_default()
+                .add(tokens, cost);
+            for voice in &voices {
+                let tokens = voice["tokens"].as_u64().unwrap_or(0);
+                let cost = number(&voice["cost"]).unwrap_or(0.0);
+                if let Some(name) = voice["voice"].as_str() {
+                    conversation
+                        .by_voice
+                        .entry(name.to_string())
+                        .or_default()
+                        .add(tokens, cost);
+                }
+                if let Some(model) = voice["model"].as_str() {
+                    conversation
+                        .by_model
+                        .entry(model.to_string())
+                        .or_default()
+                        .add(tokens, cost);
+                }
+            }
+            conversation.updated = now_secs();
+        }
+        ledger
+            .monthly
+            .entry(current_month())
+            .or_default()
+            .entry(persona.to_string())
+            .or_default()
+            .add(tokens, cost);
+        while ledger.monthly.len() > MONTHS_KEPT {
+            let oldest = ledger.monthly.keys().next().cloned().unwrap_or_default();
+            ledger.monthly.remove(&oldest);
+        }
+        storage::write_json(&ledger_path(app)?, &ledger)
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record chat cost: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_conversation_cost(app: AppHandle, id: String) -> Result<ConversationCost, String> {
+    load_ledger(&app)?
+        .conversations
+        .remove(&id)
+        .ok_or_else(|| format!("No costs recorded for conversation '{}'", id))
+}
+
+// Defaults to the current month
+#[tauri::command]
+pub fn get_persona_costs(
+    app: AppHandle,
+    month: Option<String>,
+) -> Result<BTreeMap<String, Usage>, String> {
+    let month = month.unwrap_or_else(current_month);
+    Ok(load_ledger(&app)?
+        .monthly
+        .remove(&month)
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_cost_quotas(app: AppHandle) -> Result<CostQuotas, String> {
+    load_quotas(&app)
+}
+
+#[tauri::command]
+pub fn set_cost_quotas(app: AppHandle, quotas: CostQuotas) -> Result<(), String> {
+    for (persona, quota) in &quotas.monthly_usd {
+        if persona.trim().is_empty() {
+            return Err("Persona names cannot be empty".to_string());
+        }
+        if !quota.is_finite() || *quota < 0.0 {
+            return Err(format!("Invalid monthly quota for '{}'", persona));
+        }
+    }
+    journal::write_json(&app, "Edit cost quotas", &quotas_path(&app)?, &quotas)
+}
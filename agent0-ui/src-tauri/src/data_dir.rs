@@ -0,0 +1,285 @@
+// Moving the app data directory to another drive, with progress and an integrity check
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::MetricsStore;
+use crate::progress::{self, Operation};
+use crate::storage::{self, DataLocation, LOCATION_FILE};
+use crate::{audit, safe_mode, tags};
+
+// Progress events are throttled to one per this many bytes copied or verified
+const PROGRESS_STEP_BYTES: u64 = 8 * 1024 * 1024;
+
+static MIGRATING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Clone)]
+pub struct DataDirStatus {
+    path: String,
+    default_path: String,
+    custom: bool,
+    migrating: bool,
+}
+
+// Every file below `dir`, relative to it; the location pointer never moves
+pub fn files(dir: &Path) -> Result<Vec<(PathBuf, u64)>, String> {
+    let mut found = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let entries = fs::read_dir(dir.join(&relative))
+            .map_err(|e| format!("Failed to list {}: {}", dir.join(&relative).display(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?;
+            let path = relative.join(entry.file_name());
+            let meta = entry
+                .metadata()
+                .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            if meta.is_dir() {
+                pending.push(path);
+            } else if path != Path::new(LOCATION_FILE) {
+                found.push((path, meta.len()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn digest(path: &Path) -> Result<Vec<u8>, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn validate_target(current: &Path, target: &Path) -> Result<(), String> {
+    if !target.is_absolute() {
+        return Err("The new data directory must be an absolute path".to_string());
+    }
+    if target.starts_with(current) || current.starts_with(target) {
+        return Err(
+            "The new data directory cannot contain or be inside the current one".to_string(),
+        );
+    }
+    // The default directory keeps the location pointer, which is fine to move back over
+    if target.exists() {
+        let mut entries = fs::read_dir(target)
+            .map_err(|e| format!("Failed to read {}: {}", target.display(), e))?
+            .flatten()
+            .filter(|e| e.file_name() != LOCATION_FILE);
+        if entries.next().is_some() {
+            return Err(format!("{} is not empty", target.display()));
+        }
+    }
+    Ok(())
+}
+
+fn remove_copied(dir: &Path, files: &[(PathBuf, u64)]) {
+    for (relative, _) in files {
+        let _ = fs::remove_file(dir.join(relative));
+    }
+}
+
+// Only what still matches the new copy goes; a file something wrote to after the check is
+// left in place rather than lost
+fn remove_moved(current: &Path, target: &Path, files: &[(PathBuf, u64)]) {
+    for (relative, _) in files {
+        let (old, new) = (current.join(relative), target.join(relative));
+        match (digest(&old), digest(&new)) {
+            (Ok(a), Ok(b)) if a == b => {
+                let _ = fs::remove_file(&old);
+            }
+            _ => tracing::warn!(
+                "{} changed after the move was verified; leaving it in place",
+                old.display()
+            ),
+        }
+    }
+}
+
+// Copies, verifies, then points the app at the new directory; the old copy goes last.
+// Cancelling is honoured until the switch, and removes whatever was copied.
+fn migrate(
+    app: &AppHandle,
+    default: &Path,
+    current: &Path,
+    target: &Path,
+    operation: &Operation,
+) -> Result<(), String> {
+    let location_path = default.join(LOCATION_FILE);
+    safe_mode::check_writable(&location_path)?;
+    validate_target(current, target)?;
+    fs::create_dir_all(target)
+        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    app.state::<MetricsStore>().flush()?;
+    tags::flush()?;
+    // Settings writes fail from here to the switch instead of landing behind the copy
+    storage::hold_writes(true);
+    let files = files(current)?;
+    let total: u64 = files.iter().map(|(_, len)| len).sum();
+    operation.report_ratio("copying", 0, total);
+    let (mut done, mut last_reported) = (0, 0);
+    for (relative, len) in &files {
+        if let Err(e) = operation.check() {
+            remove_copied(target, &files);
+            return Err(e);
+        }
+        let (from, to) = (current.join(relative), target.join(relative));
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let copied = fs::copy(&from, &to).map_err(|e| {
+            format!(
+                "Failed to copy {} to {}: {}",
+                from.display(),
+                to.display(),
+                e
+            )
+        });
+        if let Err(e) = copied {
+            remove_copied(target, &files);
+            return Err(e);
+        }
+        done += len;
+        if done - last_reported >= PROGRESS_STEP_BYTES {
+            last_reported = done;
+            operation.report_ratio("copying", done, total);
+        }
+    }
+
+    // A file written to during the copy fails here, and the move is abandoned
+    operation.report_ratio("verifying", 0, total);
+    let (mut done, mut last_reported) = (0, 0);
+    for (relative, len) in &files {
+        if let Err(e) = operation.check() {
+            remove_copied(target, &files);
+            return Err(e);
+        }
+        let same = digest(&current.join(relative))? == digest(&target.join(relative))?;
+        if !same {
+            remove_copied(target, &files);
+            return Err(format!(
+                "{} changed while it was being copied; try again",
+                relative.display()
+            ));
+        }
+        done += len;
+        if done - last_reported >= PROGRESS_STEP_BYTES {
+            last_reported = done;
+            operation.report_ratio("verifying", done, total);
+        }
+    }
+
+    operation.report("switching", Some(100.0));
+    let location = DataLocation {
+        path: if target == default {
+            None
+        } else {
+            Some(target.to_path_buf())
+        },
+    };
+    storage::write_json_unguarded(&location_path, &location)?;
+    storage::set_data_dir(target.to_path_buf());
+    app.state::<MetricsStore>().relocate(target);
+    storage::hold_writes(false);
+    remove_moved(current, target, &files);
+    Ok(())
+}
+
+fn status(app: &AppHandle) -> Result<DataDirStatus, String> {
+    let path = storage::data_dir(app)?;
+    let default = storage::default_data_dir(app)?;
+    Ok(DataDirStatus {
+        custom: path != default,
+        path: path.display().to_string(),
+        default_path: default.display().to_string(),
+        migrating: MIGRATING.load(Ordering::SeqCst),
+    })
+}
+
+#[tauri::command]
+pub fn get_data_dir(app: AppHandle) -> Result<DataDirStatus, String> {
+    status(&app)
+}
+
+// The whole move runs on the blocking pool so it finishes (or rolls back) even if cancelled
+fn relocate(
+    app: &AppHandle,
+    default: &Path,
+    current: &Path,
+    target: &Path,
+    operation: &Operation,
+) -> Result<(), String> {
+    let result = migrate(app, default, current, target, operation);
+    storage::hold_writes(false);
+    MIGRATING.store(false, Ordering::SeqCst);
+    audit::record(
+        app,
+        "data_dir",
+        "set_data_dir",
+        &json!({ "from": current, "to": target }),
+        &result.clone().map(|_| Value::Null),
+    );
+    result?;
+    // Directories the old copy leaves behind are cleaned up best-effort
+    let _ = remove_empty_dirs(current);
+    if current != default {
+        let _ = fs::remove_dir(current);
+    }
+    Ok(())
+}
+
+// Moving back to the default directory clears the custom location. Returns an operation
+// id, or None when the directory is already there.
+#[tauri::command]
+pub fn set_data_dir(app: AppHandle, path: String) -> Result<Option<String>, String> {
+    let default = storage::default_data_dir(&app)?;
+    let current = storage::data_dir(&app)?;
+    let target = PathBuf::from(path.trim());
+    if target == current {
+        return Ok(None);
+    }
+    if MIGRATING.swap(true, Ordering::SeqCst) {
+        return Err("The data directory is already being moved".to_string());
+    }
+    let handle = app.clone();
+    Ok(Some(progress::spawn(
+        &app,
+        "data_dir",
+        move |operation| async move {
+            let app = handle.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                relocate(&handle, &default, &current, &target, &operation)
+            })
+            .await
+            .map_err(|e| format!("Migration task failed: {}", e))??;
+            progress::encode(status(&app)?)
+        },
+    )))
+}
+
+fn remove_empty_dirs(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path)?;
+            let _ = fs::remove_dir(&path);
+        }
+    }
+    Ok(())
+}
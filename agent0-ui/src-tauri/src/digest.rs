@@ -0,0 +1,60 @@
+// Daily digest notification summarising the previous 24 hours
+use chrono::{NaiveTime, Utc};
+use std::time::Duration;
+use tauri::api::notification::Notification;
+use tauri::AppHandle;
+
+use crate::energy;
+use crate::formatting::Formatter;
+use crate::metrics::{self, TimeRange};
+use crate::schedule::{self, Zone};
+
+const DIGEST_HOUR: u32 = 9;
+
+fn until_next_digest() -> Duration {
+    let now = Utc::now();
+    let time = NaiveTime::from_hms_opt(DIGEST_HOUR, 0, 0).unwrap();
+    schedule::next_fire(Zone::System, &[], time, now)
+        .and_then(|next| (next - now).to_std().ok())
+        .unwrap_or(Duration::from_secs(3600))
+}
+
+fn compose(app: &AppHandle) -> Vec<String> {
+    let now = metrics::now_secs();
+    let range = TimeRange {
+        from: now - 86400,
+        to: now,
+    };
+    let format = Formatter::load(app);
+    let mut lines = Vec::new();
+    match energy::report(app, range) {
+        Ok(report) if report.covered_seconds > 0 => lines.push(format!(
+            "Energy: {} kWh, {}, {} kg CO₂",
+            format.number(report.kwh, 2),
+            format.currency(report.cost, Some(&report.currency)),
+            format.number(report.co2_kg, 2)
+        )),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to build energy digest: {}", e),
+    }
+    lines
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(until_next_digest()).await;
+            let lines = compose(&app);
+            if lines.is_empty() {
+                continue;
+            }
+            if let Err(e) = Notification::new(&app.config().tauri.bundle.identifier)
+                .title("Agent-0 daily digest")
+                .body(lines.join("\n"))
+                .show()
+            {
+                tracing::warn!("Failed to show daily digest: {}", e);
+            }
+        }
+    });
+}
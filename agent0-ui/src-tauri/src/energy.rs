@@ -0,0 +1,156 @@
+// GPU energy usage estimation from sampled power draw
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::gpu::Gpu;
+use crate::metrics::{self, MetricsStore, Sample, TimeRange};
+use crate::{install_mode, journal, storage};
+
+pub const POWER_SERIES: &str = "gpu.power_watts";
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+// Failed reads back off to one attempt per this many ticks
+const MAX_BACKOFF_TICKS: u32 = 32;
+// Longer gaps mean the app was closed or NVML failed, so they are not integrated
+const MAX_GAP_SECS: i64 = 300;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnergySettings {
+    price_per_kwh: f64,
+    currency: String,
+    co2_grams_per_kwh: f64,
+}
+
+impl Default for EnergySettings {
+    fn default() -> Self {
+        EnergySettings {
+            price_per_kwh: 0.30,
+            currency: "USD".to_string(),
+            co2_grams_per_kwh: 400.0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EnergyReport {
+    pub from: i64,
+    pub to: i64,
+    pub kwh: f64,
+    pub cost: f64,
+    pub currency: String,
+    pub co2_kg: f64,
+    pub average_watts: f64,
+    pub peak_watts: f64,
+    pub covered_seconds: i64,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("energy.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<EnergySettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+// Trapezoidal integration of watts over time, returning (joules, seconds covered)
+fn integrate(samples: &[Sample]) -> (f64, i64) {
+    samples
+        .windows(2)
+        .filter(|pair| pair[1].ts - pair[0].ts <= MAX_GAP_SECS)
+        .fold((0.0, 0), |(joules, covered), pair| {
+            let dt = pair[1].ts - pair[0].ts;
+            let watts = (pair[0].value + pair[1].value) / 2.0;
+            (joules + watts * dt as f64, covered + dt)
+        })
+}
+
+pub fn report(app: &AppHandle, range: TimeRange) -> Result<EnergyReport, String> {
+    let settings = load_settings(app)?;
+    let samples = app.state::<MetricsStore>().range(POWER_SERIES, range);
+    let (joules, covered_seconds) = integrate(&samples);
+    let kwh = joules / 3_600_000.0;
+    Ok(EnergyReport {
+        from: range.from,
+        to: range.to,
+        kwh,
+        cost: kwh * settings.price_per_kwh,
+        currency: settings.currency,
+        co2_kg: kwh * settings.co2_grams_per_kwh / 1000.0,
+        average_watts: if covered_seconds > 0 {
+            joules / covered_seconds as f64
+        } else {
+            0.0
+        },
+        peak_watts: samples.iter().map(|s| s.value).fold(0.0, f64::max),
+        covered_seconds,
+    })
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+        let mut failures = 0u32;
+        let mut skip = 0u32;
+        loop {
+            ticker.tick().await;
+            if install_mode::is_remote_only() {
+                continue;
+            }
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            match app.state::<Gpu>().total_power_watts() {
+                Ok(watts) => {
+                    if failures > 0 {
+                        tracing::info!("Energy sampling resumed");
+                        failures = 0;
+                    }
+                    app.state::<MetricsStore>().record(POWER_SERIES, watts)
+                }
+                // NVML errors are often transient, so keep retrying, less often each time and
+                // logged only once; a machine with no GPU ends up trying every half hour
+                Err(e) => {
+                    if failures == 0 {
+                        tracing::warn!("Energy sampling paused: {}", e);
+                    }
+                    failures = failures.saturating_add(1);
+                    skip = (1u32 << failures.min(6)).min(MAX_BACKOFF_TICKS) - 1;
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_energy_report(app: AppHandle, range: Option<TimeRange>) -> Result<EnergyReport, String> {
+    install_mode::require_local("Energy reporting")?;
+    let range = range.unwrap_or_else(|| {
+        let now = metrics::now_secs();
+        TimeRange {
+            from: now - 86400,
+            to: now,
+        }
+    });
+    report(&app, range)
+}
+
+#[tauri::command]
+pub fn get_energy_settings(app: AppHandle) -> Result<EnergySettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_energy_settings(app: AppHandle, settings: EnergySettings) -> Result<(), String> {
+    install_mode::require_local("Energy reporting")?;
+    if settings.price_per_kwh < 0.0 || settings.co2_grams_per_kwh < 0.0 {
+        return Err("Energy rates cannot be negative".to_string());
+    }
+    journal::write_json(
+        &app,
+        "Edit energy settings",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
@@ -0,0 +1,459 @@
+// Live Agent-0 events over WebSocket, falling back to SSE and then long-polling where a proxy
+// blocks or buffers the stream; every transport delivers the same "server-event" to the webview
+use futures_util::StreamExt;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::metrics::now_secs;
+use crate::{agent0, api};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// The server greets each subscriber at once; a proxy that buffers the stream holds that back
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(10);
+// The server sends a keepalive at least every 30 seconds
+const IDLE_TIMEOUT: Duration = Duration::from_secs(75);
+// How long the server may hold a long-poll open before answering with no events
+const POLL_WAIT: Duration = Duration::from_secs(25);
+// A fallback transport gives WebSocket another go this often
+const UPGRADE_INTERVAL_SECS: i64 = 600;
+const MIN_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(60);
+const MAX_FAILURES: usize = 10;
+// A connection that drops within this long of opening is counted against its transport
+const BRIEF_SECS: i64 = 30;
+// Brief drops in a row before the next transport is tried, as with a proxy that cuts every
+// stream after a few seconds
+const MAX_BRIEF_DROPS: u32 = 3;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    WebSocket,
+    Sse,
+    LongPoll,
+}
+
+impl Transport {
+    // Most preferred first
+    const ALL: [Transport; 3] = [Transport::WebSocket, Transport::Sse, Transport::LongPoll];
+}
+
+#[derive(Serialize, Clone)]
+pub struct TransportFailure {
+    transport: Transport,
+    error: String,
+    at: i64,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct TransportStatus {
+    // The transport in use, or last used while reconnecting
+    transport: Option<Transport>,
+    connected: bool,
+    connected_since: Option<i64>,
+    last_event_at: Option<i64>,
+    // Delivered since startup, across transports
+    events: u64,
+    // Newest last
+    failures: Vec<TransportFailure>,
+    // When a fallback transport will next try WebSocket
+    next_upgrade_at: Option<i64>,
+}
+
+#[derive(Default)]
+pub struct EventState {
+    status: Mutex<TransportStatus>,
+}
+
+fn update(app: &AppHandle, notify: bool, change: impl FnOnce(&mut TransportStatus)) {
+    let state = app.state::<EventState>();
+    let mut status = state.status.lock().unwrap();
+    change(&mut status);
+    if notify {
+        let _ = app.emit_all("transport-changed", &*status);
+    }
+}
+
+fn record_failure(app: &AppHandle, transport: Transport, error: String) {
+    tracing::info!("Event transport {:?} failed: {}", transport, error);
+    update(app, true, |status| {
+        status.connected = false;
+        status.connected_since = None;
+        status.failures.push(TransportFailure {
+            transport,
+            error,
+            at: now_secs(),
+        });
+        let excess = status.failures.len().saturating_sub(MAX_FAILURES);
+        status.failures.drain(..excess);
+    });
+}
+
+enum Outcome {
+    // Never delivered a frame; the next transport is tried
+    Refused(String),
+    // Worked for a while, then dropped; the same transport is retried unless it keeps
+    // dropping soon after connecting
+    Dropped { error: String, brief: bool },
+    // A fallback's time is up or the active server changed
+    Renegotiate,
+}
+
+struct Subscriber<'a> {
+    app: &'a AppHandle,
+    base: String,
+    transport: Transport,
+    // Id of the last event delivered, so a new connection resumes after it
+    cursor: &'a mut Option<String>,
+    live: bool,
+    // Unix seconds of the first frame
+    live_since: i64,
+    // Unix seconds; only fallbacks have one
+    deadline: Option<i64>,
+}
+
+impl Subscriber<'_> {
+    fn expired(&self) -> bool {
+        agent0::base_url() != self.base || self.deadline.map_or(false, |at| now_secs() >= at)
+    }
+
+    fn wait(&self) -> Duration {
+        if self.live {
+            IDLE_TIMEOUT
+        } else {
+            FIRST_FRAME_TIMEOUT
+        }
+    }
+
+    fn fail(&self, error: String) -> Outcome {
+        if self.live {
+            Outcome::Dropped {
+                error,
+                brief: now_secs() - self.live_since < BRIEF_SECS,
+            }
+        } else {
+            Outcome::Refused(error)
+        }
+    }
+
+    // Any frame, keepalives included, shows the stream gets through
+    fn alive(&mut self) {
+        if self.live {
+            return;
+        }
+        self.live = true;
+        self.live_since = now_secs();
+        let (transport, deadline) = (self.transport, self.deadline);
+        update(self.app, true, |status| {
+            status.transport = Some(transport);
+            status.connected = true;
+            status.connected_since = Some(now_secs());
+            status.next_upgrade_at = deadline;
+        });
+    }
+
+    fn deliver(&mut self, event: Value, id: Option<String>) {
+        let id = id.or_else(|| match &event["id"] {
+            Value::String(id) => Some(id.clone()),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        });
+        if id.is_some() {
+            *self.cursor = id;
+        }
+        if event["type"] == "keepalive" {
+            return;
+        }
+        let _ = self.app.emit_all("server-event", &event);
+        update(self.app, false, |status| {
+            status.events += 1;
+            status.last_event_at = Some(now_secs());
+        });
+    }
+
+    fn receive(&mut self, text: &str, id: Option<String>) {
+        match serde_json::from_str(text) {
+            Ok(event) => self.deliver(event, id),
+            Err(e) => tracing::warn!("Dropped an unreadable server event: {}", e),
+        }
+    }
+
+    // Waits on `next` unless the subscription should give way first
+    async fn within<T>(&self, next: impl Future<Output = T>) -> Result<T, Outcome> {
+        if self.expired() {
+            return Err(Outcome::Renegotiate);
+        }
+        timeout(self.wait(), next)
+            .await
+            .map_err(|_| self.fail("No events or keepalives from the server".to_string()))
+    }
+}
+
+fn ws_url(base: &str) -> Result<String, String> {
+    if let Some(rest) = base.strip_prefix("https://") {
+        Ok(format!("wss://{}{}", rest, api::paths::EVENT_SOCKET))
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        Ok(format!("ws://{}{}", rest, api::paths::EVENT_SOCKET))
+    } else {
+        Err(format!("Cannot open a WebSocket to {}", base))
+    }
+}
+
+async fn websocket(sub: &mut Subscriber<'_>) -> Outcome {
+    let mut request = match ws_url(&sub.base).and_then(|url| {
+        url.into_client_request()
+            .map_err(|e| format!("Invalid WebSocket URL: {}", e))
+    }) {
+        Ok(request) => request,
+        Err(e) => return Outcome::Refused(e),
+    };
+    request.headers_mut().insert(
+        agent0::UI_SESSION_HEADER,
+        HeaderValue::from_static(agent0::ui_session_id()),
+    );
+    let mut stream = match timeout(CONNECT_TIMEOUT, tokio_tungstenite::connect_async(request)).await
+    {
+        Ok(Ok((stream, _))) => stream,
+        Ok(Err(e)) => return Outcome::Refused(format!("WebSocket handshake failed: {}", e)),
+        Err(_) => return Outcome::Refused("WebSocket handshake timed out".to_string()),
+    };
+    loop {
+        match sub.within(stream.next()).await {
+            Err(outcome) => return outcome,
+            Ok(None) | Ok(Some(Ok(Message::Close(_)))) => {
+                return sub.fail("WebSocket closed".to_string())
+            }
+            Ok(Some(Err(e))) => return sub.fail(format!("WebSocket failed: {}", e)),
+            Ok(Some(Ok(Message::Text(text)))) => {
+                sub.alive();
+                sub.receive(&text, None);
+            }
+            // Pings are answered by the library
+            Ok(Some(Ok(_))) => sub.alive(),
+        }
+    }
+}
+
+// The data and id of one "\n\n"-terminated SSE block; None for keepalive comments
+fn parse_sse_block(block: &str) -> Option<(String, Option<String>)> {
+    let mut data = Vec::new();
+    let mut id = None;
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => data.push(value),
+            "id" => id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        None
+    } else {
+        Some((data.join("\n"), id))
+    }
+}
+
+fn sse_block(sub: &mut Subscriber<'_>, block: &str) {
+    if let Some((data, id)) = parse_sse_block(block) {
+        sub.receive(&data, id);
+    }
+}
+
+async fn sse(sub: &mut Subscriber<'_>) -> Outcome {
+    let mut request = reqwest::Client::new()
+        .get(format!("{}{}", sub.base, api::paths::EVENT_STREAM))
+        .header(agent0::UI_SESSION_HEADER, agent0::ui_session_id())
+        .header(ACCEPT, "text/event-stream");
+    if let Some(cursor) = sub.cursor.as_deref() {
+        request = request.header("Last-Event-ID", cursor);
+    }
+    // Streams bypass the VCR, which records whole responses
+    let mut response = match timeout(CONNECT_TIMEOUT, request.send()).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => return Outcome::Refused(format!("Event stream request failed: {}", e)),
+        Err(_) => return Outcome::Refused("Event stream request timed out".to_string()),
+    };
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    // Captive portals and filtering proxies answer with a page of their own
+    if !response.status().is_success() || !content_type.starts_with("text/event-stream") {
+        return Outcome::Refused(format!(
+            "Event stream answered {} with '{}'",
+            response.status(),
+            content_type
+        ));
+    }
+    let mut buffer: Vec<u8> = Vec::new();
+    loop {
+        let chunk = match sub.within(response.chunk()).await {
+            Err(outcome) => return outcome,
+            Ok(Err(e)) => return sub.fail(format!("Event stream failed: {}", e)),
+            Ok(Ok(None)) => return sub.fail("Event stream ended".to_string()),
+            Ok(Ok(Some(chunk))) => chunk,
+        };
+        sub.alive();
+        buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+        while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = buffer.drain(..end + 2).collect();
+            sse_block(sub, &String::from_utf8_lossy(&block));
+        }
+    }
+}
+
+async fn long_poll(sub: &mut Subscriber<'_>) -> Outcome {
+    loop {
+        if sub.expired() {
+            return Outcome::Renegotiate;
+        }
+        let mut path = format!(
+            "{}?timeout={}",
+            api::paths::POLL_EVENTS,
+            POLL_WAIT.as_secs()
+        );
+        if let Some(cursor) = sub.cursor.as_deref() {
+            path.push_str(&format!("&cursor={}", api::segment(cursor)));
+        }
+        let poll = agent0::call(Method::GET, &sub.base, &path, None);
+        let reply = match timeout(POLL_WAIT + CONNECT_TIMEOUT, poll).await {
+            Err(_) => return sub.fail("Long-poll timed out".to_string()),
+            Ok(Err(e)) => return sub.fail(e),
+            Ok(Ok(reply)) => reply,
+        };
+        sub.alive();
+        for event in reply["events"].as_array().into_iter().flatten() {
+            sub.deliver(event.clone(), None);
+        }
+        if let Some(cursor) = reply["cursor"].as_str() {
+            *sub.cursor = Some(cursor.to_string());
+        }
+    }
+}
+
+// Moves on to the next transport; false when there is none left
+fn demote(preferred: &mut usize, upgrade_at: &mut i64) -> bool {
+    if *preferred + 1 >= Transport::ALL.len() {
+        return false;
+    }
+    *preferred += 1;
+    if *preferred == 1 {
+        *upgrade_at = now_secs() + UPGRADE_INTERVAL_SECS;
+    }
+    true
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut cursor = None;
+        let mut preferred = 0;
+        let mut upgrade_at = 0;
+        let mut retry = MIN_RETRY;
+        let mut brief_drops = 0;
+        let mut base = agent0::base_url();
+        loop {
+            // A cursor means nothing to another server
+            if agent0::base_url() != base {
+                base = agent0::base_url();
+                cursor = None;
+                preferred = 0;
+                brief_drops = 0;
+            }
+            if preferred > 0 && now_secs() >= upgrade_at {
+                preferred = 0;
+                brief_drops = 0;
+            }
+            let transport = Transport::ALL[preferred];
+            let mut sub = Subscriber {
+                app: &app,
+                base: base.clone(),
+                transport,
+                cursor: &mut cursor,
+                live: false,
+                live_since: 0,
+                deadline: (preferred > 0).then(|| upgrade_at),
+            };
+            let outcome = match transport {
+                Transport::WebSocket => websocket(&mut sub).await,
+                Transport::Sse => sse(&mut sub).await,
+                Transport::LongPoll => long_poll(&mut sub).await,
+            };
+            match outcome {
+                Outcome::Refused(e) => {
+                    record_failure(&app, transport, e);
+                    brief_drops = 0;
+                    if demote(&mut preferred, &mut upgrade_at) {
+                        continue;
+                    }
+                    // Nothing gets through, most likely the server is down
+                    preferred = 0;
+                    tokio::time::sleep(retry).await;
+                    retry = (retry * 2).min(MAX_RETRY);
+                }
+                Outcome::Dropped { error, brief } => {
+                    record_failure(&app, transport, error);
+                    retry = MIN_RETRY;
+                    brief_drops = if brief { brief_drops + 1 } else { 0 };
+                    if brief_drops >= MAX_BRIEF_DROPS {
+                        brief_drops = 0;
+                        if demote(&mut preferred, &mut upgrade_at) {
+                            continue;
+                        }
+                    }
+                    tokio::time::sleep(retry).await;
+                }
+                Outcome::Renegotiate => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_transport_status(state: State<'_, EventState>) -> TransportStatus {
+    state.status.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_block_joins_data_lines_and_keeps_the_id() {
+        let block = "id: 42\ndata: {\"a\":\ndata: 1}\nevent: token";
+        assert_eq!(
+            parse_sse_block(block),
+            Some(("{\"a\":\n1}".to_string(), Some("42".to_string())))
+        );
+    }
+
+    #[test]
+    fn sse_block_strips_only_one_leading_space() {
+        assert_eq!(
+            parse_sse_block("data:  padded\ndata:tight"),
+            Some((" padded\ntight".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn sse_keepalive_comments_carry_no_event() {
+        assert_eq!(parse_sse_block(": keepalive"), None);
+        assert_eq!(parse_sse_block("id: 7"), None);
+    }
+
+    #[test]
+    fn sse_field_without_colon_has_an_empty_value() {
+        assert_eq!(parse_sse_block("data"), Some((String::new(), None)));
+    }
+}
@@ -0,0 +1,217 @@
+// Read-only API explorer over the server's own OpenAPI document; only GET endpoints can be tried
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::managed::{self, REDACTED};
+use crate::metrics::now_secs;
+use crate::{agent0, api, storage, vcr};
+
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+// Under the templates folder, so saved requests sync and trash like any other template
+const TEMPLATE_DIR: &str = "api-explorer";
+
+#[derive(Serialize, Clone)]
+pub struct EndpointParam {
+    name: String,
+    // "path" or "query"
+    location: String,
+    required: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ApiEndpoint {
+    method: String,
+    path: String,
+    summary: Option<String>,
+    operation_id: Option<String>,
+    params: Vec<EndpointParam>,
+    // Whether try_endpoint will run it
+    safe: bool,
+}
+
+#[derive(Serialize)]
+pub struct EndpointResponse {
+    // Path and query as sent
+    request: String,
+    elapsed_ms: u64,
+    value: Value,
+    pretty: String,
+    // Template the request was saved as, relative to the templates folder
+    template: Option<String>,
+}
+
+fn params_of(operation: &Value, inherited: &Value) -> Vec<EndpointParam> {
+    inherited
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain(operation["parameters"].as_array().into_iter().flatten())
+        .filter_map(|p| {
+            let location = p["in"].as_str()?;
+            if location != "path" && location != "query" {
+                return None;
+            }
+            Some(EndpointParam {
+                name: p["name"].as_str()?.to_string(),
+                location: location.to_string(),
+                required: location == "path" || p["required"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+fn endpoints(spec: &Value) -> Result<Vec<ApiEndpoint>, String> {
+    let paths = spec["paths"]
+        .as_object()
+        .ok_or_else(|| "The server's OpenAPI document has no paths".to_string())?;
+    let mut found = Vec::new();
+    for (path, item) in paths {
+        for method in METHODS {
+            let operation = &item[method];
+            if operation.is_null() {
+                continue;
+            }
+            found.push(ApiEndpoint {
+                method: method.to_uppercase(),
+                path: path.clone(),
+                summary: operation["summary"].as_str().map(str::to_string),
+                operation_id: operation["operationId"].as_str().map(str::to_string),
+                params: params_of(operation, &item["parameters"]),
+                safe: method == "get",
+            });
+        }
+    }
+    found.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+    Ok(found)
+}
+
+// Fills path parameters and appends query ones, each percent-encoded
+fn request_path(
+    endpoint: &ApiEndpoint,
+    params: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    if let Some(unknown) = params
+        .keys()
+        .find(|name| !endpoint.params.iter().any(|p| &p.name == *name))
+    {
+        return Err(format!(
+            "{} takes no parameter '{}'",
+            endpoint.path, unknown
+        ));
+    }
+    let mut path = endpoint.path.clone();
+    let mut query = Vec::new();
+    for param in &endpoint.params {
+        let value = match params.get(&param.name) {
+            Some(value) => value,
+            None if param.required => {
+                return Err(format!("Missing required parameter '{}'", param.name))
+            }
+            None => continue,
+        };
+        if param.location == "path" {
+            // Dot segments would be resolved away and reach a different endpoint
+            if value.is_empty() || value == "." || value == ".." {
+                return Err(format!("Invalid value for '{}'", param.name));
+            }
+            path = path.replace(&format!("{{{}}}", param.name), &api::segment(value));
+        } else {
+            query.push(format!(
+                "{}={}",
+                api::segment(&param.name),
+                api::segment(value)
+            ));
+        }
+    }
+    if !query.is_empty() {
+        path = format!("{}?{}", path, query.join("&"));
+    }
+    Ok(path)
+}
+
+fn check_template_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(|c| c == '/' || c == '\\') || name.starts_with('.') {
+        return Err(format!("Invalid template name '{}'", name));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_api_endpoints() -> Result<Vec<ApiEndpoint>, String> {
+    endpoints(&api::get_openapi_spec(&agent0::base_url()).await?)
+}
+
+// Runs a GET the server's document lists; `save_as` keeps the request and its response as a
+// template for later
+#[tauri::command]
+pub async fn try_endpoint(
+    app: AppHandle,
+    method: String,
+    path: String,
+    params: Option<BTreeMap<String, String>>,
+    save_as: Option<String>,
+) -> Result<EndpointResponse, String> {
+    if !method.eq_ignore_ascii_case("GET") {
+        return Err("Only GET endpoints can be tried from the explorer".to_string());
+    }
+    if let Some(name) = &save_as {
+        check_template_name(name)?;
+    }
+    let base = agent0::base_url();
+    let endpoint = endpoints(&api::get_openapi_spec(&base).await?)?
+        .into_iter()
+        .find(|e| e.safe && e.path == path)
+        .ok_or_else(|| format!("{} is not a GET endpoint of this server", path))?;
+    let params = params.unwrap_or_default();
+    let request = request_path(&endpoint, &params)?;
+
+    let started = Instant::now();
+    let value = agent0::call(Method::GET, &base, &request, None).await?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    let pretty = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to format response: {}", e))?;
+
+    let template = match save_as {
+        Some(name) => {
+            let relative = format!("{}/{}.json", TEMPLATE_DIR, name);
+            // Templates sync to peers and the remote, so secrets are redacted as in a cassette
+            let mut response = value.clone();
+            vcr::redact_value(&mut response);
+            let params: BTreeMap<&String, &str> = params
+                .iter()
+                .map(|(name, value)| {
+                    let shown = if managed::sensitive(name) {
+                        REDACTED
+                    } else {
+                        value.as_str()
+                    };
+                    (name, shown)
+                })
+                .collect();
+            let saved = json!({
+                "method": "GET",
+                "path": endpoint.path,
+                "params": params,
+                "saved_at": now_secs(),
+                "response": response,
+            });
+            storage::write_json(
+                &storage::data_dir(&app)?.join("templates").join(&relative),
+                &saved,
+            )?;
+            Some(relative)
+        }
+        None => None,
+    };
+    Ok(EndpointResponse {
+        request,
+        elapsed_ms,
+        value,
+        pretty,
+        template,
+    })
+}
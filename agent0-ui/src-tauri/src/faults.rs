@@ -0,0 +1,129 @@
+// Synthetic failures fed into telemetry so alert rules and their sinks can be rehearsed; their
+// samples go to separate series that reports, charts and the heatmap never read
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::metrics::{now_secs, synthetic_series, MetricsStore};
+use crate::telemetry;
+
+const MAX_DURATION_SECS: u64 = 3600;
+// What a latency spike reports in place of the measured health latency
+const SPIKE_LATENCY_MS: f64 = 5000.0;
+// Release builds only expose injection when this is set
+const DEV_TOOLS_ENV: &str = "AGENT0_DEV_TOOLS";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    HealthDown,
+    LatencySpike,
+    TrainingFailure,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Injection {
+    kind: FailureKind,
+    started_at: i64,
+    until: i64,
+}
+
+#[derive(Default)]
+pub struct FaultState {
+    active: Mutex<Vec<Injection>>,
+}
+
+impl FaultState {
+    fn current(&self) -> Vec<Injection> {
+        let now = now_secs();
+        let mut active = self.active.lock().unwrap();
+        active.retain(|i| i.until > now);
+        active.clone()
+    }
+
+    pub fn is_active(&self, kind: FailureKind) -> bool {
+        self.current().iter().any(|i| i.kind == kind)
+    }
+}
+
+fn enabled() -> bool {
+    cfg!(debug_assertions) || std::env::var(DEV_TOOLS_ENV).map_or(false, |v| v == "1")
+}
+
+// Health probe latency an active injection reports in place of the measured one, where None
+// means the server is down; None while nothing is injected
+pub fn injected_health(app: &AppHandle, measured: Option<f64>) -> Option<Option<f64>> {
+    let faults = app.state::<FaultState>();
+    if faults.is_active(FailureKind::HealthDown) {
+        Some(None)
+    } else if faults.is_active(FailureKind::LatencySpike) {
+        Some(measured.map(|_| SPIKE_LATENCY_MS))
+    } else {
+        None
+    }
+}
+
+// Written straight away so injections shorter than the sampling interval still land
+fn record_now(app: &AppHandle, kind: FailureKind) {
+    let store = app.state::<MetricsStore>();
+    let (series, value) = match kind {
+        FailureKind::HealthDown => (telemetry::UP_SERIES, 0.0),
+        FailureKind::LatencySpike => (telemetry::LATENCY_SERIES, SPIKE_LATENCY_MS),
+        FailureKind::TrainingFailure => (telemetry::TRAINING_FAILED_SERIES, 1.0),
+    };
+    store.record(&synthetic_series(series), value);
+}
+
+#[tauri::command]
+pub fn inject_failure(
+    app: AppHandle,
+    state: State<'_, FaultState>,
+    kind: FailureKind,
+    duration_secs: u64,
+) -> Result<Injection, String> {
+    if !enabled() {
+        return Err(format!(
+            "Failure injection is only available in development builds or with {}=1",
+            DEV_TOOLS_ENV
+        ));
+    }
+    if duration_secs == 0 || duration_secs > MAX_DURATION_SECS {
+        return Err(format!(
+            "Injection duration must be between 1 and {} seconds",
+            MAX_DURATION_SECS
+        ));
+    }
+    let now = now_secs();
+    let injection = Injection {
+        kind,
+        started_at: now,
+        until: now + duration_secs as i64,
+    };
+    {
+        let mut active = state.active.lock().unwrap();
+        active.retain(|i| i.kind != kind);
+        active.push(injection.clone());
+    }
+    record_now(&app, kind);
+    audit::record(
+        &app,
+        "faults",
+        "inject_failure",
+        &json!({ "kind": kind, "durationSecs": duration_secs }),
+        &Ok(Value::Null),
+    );
+    let _ = app.emit_all("failure-injected", &injection);
+    Ok(injection)
+}
+
+#[tauri::command]
+pub fn list_injected_failures(state: State<'_, FaultState>) -> Vec<Injection> {
+    state.current()
+}
+
+#[tauri::command]
+pub fn clear_injected_failures(state: State<'_, FaultState>) {
+    state.active.lock().unwrap().clear();
+}
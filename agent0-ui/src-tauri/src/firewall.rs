@@ -0,0 +1,319 @@
+// Inbound firewall rules for the app's LAN listeners, added with OS elevation
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::{Command, Output};
+use tauri::AppHandle;
+
+use crate::audit;
+
+// The app's inbound listeners. It has no webhook receiver to open a port for, and a deep-link
+// protocol is handed over by the OS without any network traffic, so neither needs a rule; the
+// sync listener does, so it takes their place
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FirewallTarget {
+    LanProxy,
+    SyncListener,
+}
+
+impl FirewallTarget {
+    fn rule_name(self) -> &'static str {
+        match self {
+            FirewallTarget::LanProxy => "Agent-0 Desktop LAN proxy",
+            FirewallTarget::SyncListener => "Agent-0 Desktop sync listener",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct FirewallRequest {
+    target: FirewallTarget,
+    port: u16,
+}
+
+impl FirewallRequest {
+    pub fn name(&self) -> &'static str {
+        self.target.rule_name()
+    }
+
+    // None when the firewall cannot be queried without elevation
+    pub fn installed(&self) -> Option<bool> {
+        if platform::backend().1 == Some(false) {
+            return Some(false);
+        }
+        platform::allowed(self.name(), self.port)
+    }
+
+    pub fn remove(&self) -> Result<(), String> {
+        platform::remove(self.name(), self.port)
+    }
+}
+
+#[derive(Serialize)]
+pub struct RuleStatus {
+    target: FirewallTarget,
+    port: u16,
+    // None when the firewall cannot be queried without elevation
+    allowed: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct FirewallStatus {
+    backend: String,
+    enabled: Option<bool>,
+    rules: Vec<RuleStatus>,
+}
+
+fn run(program: &str, args: &[&str]) -> Result<Output, String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))
+}
+
+fn stdout(program: &str, args: &[&str]) -> Option<String> {
+    run(program, args)
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+fn succeeded(output: Output, action: &str) -> Result<(), String> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to {}: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    pub fn backend() -> (String, Option<bool>) {
+        let enabled =
+            stdout("netsh", &["advfirewall", "show", "currentprofile", "state"]).map(|out| {
+                out.lines()
+                    .any(|l| l.starts_with("State") && l.contains("ON"))
+            });
+        ("windows_defender".to_string(), enabled)
+    }
+
+    pub fn allowed(name: &str, _port: u16) -> Option<bool> {
+        let output = run(
+            "netsh",
+            &[
+                "advfirewall",
+                "firewall",
+                "show",
+                "rule",
+                &format!("name={}", name),
+            ],
+        )
+        .ok()?;
+        Some(output.status.success())
+    }
+
+    // Start-Process -Verb RunAs raises the UAC prompt, which is the user's consent
+    fn elevated_netsh(arguments: &str, action: &str) -> Result<(), String> {
+        let script = format!(
+            "$p = Start-Process netsh -Verb RunAs -Wait -PassThru -WindowStyle Hidden -ArgumentList '{}'; exit $p.ExitCode",
+            arguments
+        );
+        succeeded(
+            run("powershell", &["-NoProfile", "-Command", &script])?,
+            action,
+        )
+    }
+
+    pub fn add(name: &str, port: u16) -> Result<(), String> {
+        elevated_netsh(
+            &format!(
+                "advfirewall firewall add rule name=\"{}\" dir=in action=allow protocol=TCP localport={}",
+                name, port
+            ),
+            "add firewall rule",
+        )
+    }
+
+    pub fn remove(name: &str, _port: u16) -> Result<(), String> {
+        elevated_netsh(
+            &format!("advfirewall firewall delete rule name=\"{}\"", name),
+            "remove firewall rule",
+        )
+    }
+}
+
+// The macOS application firewall works per executable, not per port
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    const SOCKETFILTERFW: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+    fn exe() -> Result<String, String> {
+        std::env::current_exe()
+            .map(|p| p.display().to_string())
+            .map_err(|e| format!("Failed to locate the app executable: {}", e))
+    }
+
+    pub fn backend() -> (String, Option<bool>) {
+        let enabled =
+            stdout(SOCKETFILTERFW, &["--getglobalstate"]).map(|out| out.contains("enabled"));
+        ("application_firewall".to_string(), enabled)
+    }
+
+    pub fn allowed(_name: &str, _port: u16) -> Option<bool> {
+        let out = stdout(SOCKETFILTERFW, &["--getappblocked", &exe().ok()?])?;
+        Some(out.contains("permitted"))
+    }
+
+    fn elevated(command: &str, action: &str) -> Result<(), String> {
+        let script = format!(
+            "do shell script \"{}\" with administrator privileges",
+            command
+        );
+        succeeded(run("osascript", &["-e", &script])?, action)
+    }
+
+    pub fn add(_name: &str, _port: u16) -> Result<(), String> {
+        let exe = exe()?;
+        elevated(
+            &format!(
+                "{fw} --add '{exe}' && {fw} --unblockapp '{exe}'",
+                fw = SOCKETFILTERFW,
+                exe = exe
+            ),
+            "allow the app through the firewall",
+        )
+    }
+
+    pub fn remove(_name: &str, _port: u16) -> Result<(), String> {
+        elevated(
+            &format!("{} --remove '{}'", SOCKETFILTERFW, exe()?),
+            "remove the app from the firewall",
+        )
+    }
+}
+
+// firewalld or ufw, changed through pkexec so the desktop shows its own auth prompt
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::*;
+
+    fn firewalld() -> bool {
+        stdout("firewall-cmd", &["--state"]).map_or(false, |out| out.trim() == "running")
+    }
+
+    fn ufw() -> bool {
+        run("ufw", &["--version"]).map_or(false, |o| o.status.success())
+    }
+
+    pub fn backend() -> (String, Option<bool>) {
+        if firewalld() {
+            ("firewalld".to_string(), Some(true))
+        } else if ufw() {
+            // ufw only reports its state to root
+            ("ufw".to_string(), None)
+        } else {
+            ("none".to_string(), Some(false))
+        }
+    }
+
+    pub fn allowed(_name: &str, port: u16) -> Option<bool> {
+        if firewalld() {
+            let out = stdout("firewall-cmd", &[&format!("--query-port={}/tcp", port)])?;
+            Some(out.trim() == "yes")
+        } else if ufw() {
+            None
+        } else {
+            Some(true)
+        }
+    }
+
+    pub fn add(name: &str, port: u16) -> Result<(), String> {
+        let command = if firewalld() {
+            format!(
+                "firewall-cmd --permanent --add-port={}/tcp && firewall-cmd --reload",
+                port
+            )
+        } else if ufw() {
+            format!("ufw allow {}/tcp comment '{}'", port, name)
+        } else {
+            return Ok(());
+        };
+        succeeded(run("pkexec", &["sh", "-c", &command])?, "add firewall rule")
+    }
+
+    pub fn remove(_name: &str, port: u16) -> Result<(), String> {
+        let command = if firewalld() {
+            format!(
+                "firewall-cmd --permanent --remove-port={}/tcp && firewall-cmd --reload",
+                port
+            )
+        } else if ufw() {
+            format!("ufw delete allow {}/tcp", port)
+        } else {
+            return Ok(());
+        };
+        succeeded(
+            run("pkexec", &["sh", "-c", &command])?,
+            "remove firewall rule",
+        )
+    }
+}
+
+#[tauri::command]
+pub fn check_firewall_status(rules: Vec<FirewallRequest>) -> FirewallStatus {
+    let (backend, enabled) = platform::backend();
+    FirewallStatus {
+        backend,
+        enabled,
+        rules: rules
+            .into_iter()
+            .map(|r| RuleStatus {
+                target: r.target,
+                port: r.port,
+                allowed: if enabled == Some(false) {
+                    Some(true)
+                } else {
+                    platform::allowed(r.target.rule_name(), r.port)
+                },
+            })
+            .collect(),
+    }
+}
+
+// The UI asks first; the OS elevation prompt is the final consent
+#[tauri::command]
+pub fn add_firewall_rule(app: AppHandle, target: FirewallTarget, port: u16) -> Result<(), String> {
+    let result = platform::add(target.rule_name(), port);
+    audit::record(
+        &app,
+        "firewall",
+        "add_firewall_rule",
+        &json!({ "target": target, "port": port }),
+        &result.clone().map(|_| Value::Null),
+    );
+    result
+}
+
+#[tauri::command]
+pub fn remove_firewall_rule(
+    app: AppHandle,
+    target: FirewallTarget,
+    port: u16,
+) -> Result<(), String> {
+    let result = platform::remove(target.rule_name(), port);
+    audit::record(
+        &app,
+        "firewall",
+        "remove_firewall_rule",
+        &json!({ "target": target, "port": port }),
+        &result.clone().map(|_| Value::Null),
+    );
+    result
+}
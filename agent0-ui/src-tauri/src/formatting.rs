@@ -0,0 +1,316 @@
+// Locale-aware formatting of timestamps, durations, bytes and money shared by every surface
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::{journal, storage};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HourCycle {
+    H12,
+    H24,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteUnits {
+    // kB, MB, GB in powers of 1000
+    Decimal,
+    // KiB, MiB, GiB in powers of 1024
+    Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FormatPrefs {
+    // BCP 47 tag such as "en-US" or "de-DE"
+    locale: String,
+    // None follows the locale's convention
+    #[serde(default)]
+    hour_cycle: Option<HourCycle>,
+    byte_units: ByteUnits,
+    currency: String,
+}
+
+impl Default for FormatPrefs {
+    fn default() -> Self {
+        FormatPrefs {
+            locale: "en-US".to_string(),
+            hour_cycle: None,
+            byte_units: ByteUnits::Decimal,
+            currency: "USD".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FormatRequest {
+    // Unix seconds
+    Timestamp {
+        value: i64,
+        #[serde(default)]
+        date_only: bool,
+    },
+    DurationSecs {
+        value: f64,
+    },
+    Bytes {
+        value: f64,
+    },
+    Currency {
+        value: f64,
+        #[serde(default)]
+        currency: Option<String>,
+    },
+    Number {
+        value: f64,
+        #[serde(default)]
+        decimals: Option<usize>,
+    },
+}
+
+// Separators and date layout per language (and region, where it differs)
+struct Conventions {
+    decimal: char,
+    group: char,
+    // strftime date pattern
+    date: &'static str,
+    hour_cycle: HourCycle,
+    // Currency symbol after the amount, as in "12,50 €"
+    currency_after: bool,
+}
+
+fn conventions(locale: &str) -> Conventions {
+    let locale = locale.replace('_', "-");
+    let language = locale.split('-').next().unwrap_or("").to_lowercase();
+    let region = locale.split('-').nth(1).unwrap_or("").to_uppercase();
+    let european = |date| Conventions {
+        decimal: ',',
+        group: '.',
+        date,
+        hour_cycle: HourCycle::H24,
+        currency_after: true,
+    };
+    match (language.as_str(), region.as_str()) {
+        ("en", "US") | ("en", "") => Conventions {
+            decimal: '.',
+            group: ',',
+            date: "%m/%d/%Y",
+            hour_cycle: HourCycle::H12,
+            currency_after: false,
+        },
+        ("en", _) => Conventions {
+            decimal: '.',
+            group: ',',
+            date: "%d/%m/%Y",
+            hour_cycle: HourCycle::H24,
+            currency_after: false,
+        },
+        ("de", _) => european("%d.%m.%Y"),
+        ("nl", _) => european("%d-%m-%Y"),
+        ("es", _) | ("it", _) | ("pt", _) => european("%d/%m/%Y"),
+        ("fr", _) | ("ru", _) | ("pl", _) | ("sv", _) => Conventions {
+            group: '\u{a0}',
+            ..european("%d/%m/%Y")
+        },
+        ("ja", _) | ("zh", _) | ("ko", _) => Conventions {
+            decimal: '.',
+            group: ',',
+            date: "%Y/%m/%d",
+            hour_cycle: HourCycle::H24,
+            currency_after: false,
+        },
+        _ => Conventions {
+            decimal: '.',
+            group: ',',
+            date: "%Y-%m-%d",
+            hour_cycle: HourCycle::H24,
+            currency_after: false,
+        },
+    }
+}
+
+fn currency_symbol(code: &str) -> String {
+    match code.to_uppercase().as_str() {
+        "USD" => "$".to_string(),
+        "EUR" => "€".to_string(),
+        "GBP" => "£".to_string(),
+        "JPY" => "¥".to_string(),
+        "INR" => "₹".to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub struct Formatter {
+    prefs: FormatPrefs,
+    conventions: Conventions,
+}
+
+impl Formatter {
+    pub fn new(prefs: FormatPrefs) -> Self {
+        let conventions = conventions(&prefs.locale);
+        Formatter { prefs, conventions }
+    }
+
+    // Falls back to defaults so a broken prefs file never blocks a notification
+    pub fn load(app: &AppHandle) -> Self {
+        Formatter::new(load_prefs(app).unwrap_or_default())
+    }
+
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", decimals, value.abs());
+        let (int, frac) = match text.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (text.as_str(), None),
+        };
+        let mut grouped = String::new();
+        for (i, digit) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                grouped.push(self.conventions.group);
+            }
+            grouped.push(digit);
+        }
+        let mut out = String::new();
+        if value < 0.0 && text.chars().any(|c| c != '0' && c != '.') {
+            out.push('-');
+        }
+        out.push_str(&grouped);
+        if let Some(frac) = frac {
+            out.push(self.conventions.decimal);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    pub fn timestamp(&self, ts: i64, date_only: bool) -> String {
+        let time = match Local.timestamp_opt(ts, 0).single() {
+            Some(time) => time,
+            None => return ts.to_string(),
+        };
+        if date_only {
+            return time.format(self.conventions.date).to_string();
+        }
+        let clock = match self.prefs.hour_cycle.unwrap_or(self.conventions.hour_cycle) {
+            HourCycle::H12 => "%-I:%M %p",
+            HourCycle::H24 => "%H:%M",
+        };
+        time.format(&format!("{} {}", self.conventions.date, clock))
+            .to_string()
+    }
+
+    // Two most significant units, e.g. "2 h 5 min" or "42 s"
+    pub fn duration(&self, secs: f64) -> String {
+        if secs < 1.0 {
+            return format!("{} ms", self.number(secs * 1000.0, 0));
+        }
+        if secs < 60.0 {
+            let decimals = if secs < 10.0 { 1 } else { 0 };
+            return format!("{} s", self.number(secs, decimals));
+        }
+        let total = secs.round() as u64;
+        let parts = [
+            (total / 86400, "d"),
+            (total % 86400 / 3600, "h"),
+            (total % 3600 / 60, "min"),
+            (total % 60, "s"),
+        ];
+        let first = parts.iter().position(|(n, _)| *n > 0).unwrap_or(3);
+        parts[first..]
+            .iter()
+            .take(2)
+            .filter(|(n, _)| *n > 0)
+            .map(|(n, unit)| format!("{} {}", n, unit))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn bytes(&self, bytes: f64) -> String {
+        let (base, units): (f64, [&str; 5]) = match self.prefs.byte_units {
+            ByteUnits::Decimal => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+            ByteUnits::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"]),
+        };
+        let mut value = bytes;
+        let mut unit = 0;
+        while value.abs() >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        let decimals = if unit == 0 { 0 } else { 1 };
+        format!("{} {}", self.number(value, decimals), units[unit])
+    }
+
+    pub fn currency(&self, amount: f64, code: Option<&str>) -> String {
+        let code = code.unwrap_or(&self.prefs.currency);
+        let decimals = if code.eq_ignore_ascii_case("JPY") {
+            0
+        } else {
+            2
+        };
+        let number = self.number(amount, decimals);
+        let symbol = currency_symbol(code);
+        if self.conventions.currency_after {
+            format!("{} {}", number, symbol)
+        } else {
+            format!("{}{}", symbol, number)
+        }
+    }
+
+    pub fn format(&self, request: &FormatRequest) -> String {
+        match request {
+            FormatRequest::Timestamp { value, date_only } => self.timestamp(*value, *date_only),
+            FormatRequest::DurationSecs { value } => self.duration(*value),
+            FormatRequest::Bytes { value } => self.bytes(*value),
+            FormatRequest::Currency { value, currency } => {
+                self.currency(*value, currency.as_deref())
+            }
+            FormatRequest::Number { value, decimals } => self.number(*value, decimals.unwrap_or(2)),
+        }
+    }
+}
+
+fn prefs_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("format.json"))
+}
+
+fn load_prefs(app: &AppHandle) -> Result<FormatPrefs, String> {
+    storage::read_json(&prefs_path(app)?)
+}
+
+#[tauri::command]
+pub fn get_format_prefs(app: AppHandle) -> Result<FormatPrefs, String> {
+    load_prefs(&app)
+}
+
+#[tauri::command]
+pub fn set_format_prefs(app: AppHandle, prefs: FormatPrefs) -> Result<(), String> {
+    if prefs.locale.trim().is_empty() {
+        return Err("Locale cannot be empty".to_string());
+    }
+    if prefs.currency.len() != 3 || !prefs.currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Invalid currency code '{}', expected ISO 4217 like EUR",
+            prefs.currency
+        ));
+    }
+    journal::write_json(
+        &app,
+        "Edit formatting preferences",
+        &prefs_path(&app)?,
+        &prefs,
+    )
+}
+
+// One round trip for a whole table or notification's worth of values
+#[tauri::command]
+pub fn format_values(app: AppHandle, batch: Vec<FormatRequest>) -> Vec<String> {
+    let formatter = Formatter::load(&app);
+    batch
+        .iter()
+        .map(|request| formatter.format(request))
+        .collect()
+}
@@ -0,0 +1,215 @@
+// Local GPU telemetry via NVML
+use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+use nvml_wrapper::enums::device::UsedGpuMemory;
+use nvml_wrapper::Nvml;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use crate::install_mode;
+
+#[derive(Serialize, Clone)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub gpu_indices: Vec<u32>,
+    // None when the driver hides per-process usage (e.g. under WDDM)
+    pub used_memory_bytes: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct GpuDevice {
+    pub index: u32,
+    pub name: String,
+    pub total_memory_bytes: u64,
+    pub free_memory_bytes: u64,
+    pub compute_capability: (i32, i32),
+}
+
+// Point-in-time readings for one device; fields the driver refuses to report are None
+#[derive(Serialize, Clone)]
+pub struct GpuStats {
+    pub index: u32,
+    pub name: String,
+    pub utilization_percent: Option<u32>,
+    pub memory_used_bytes: Option<u64>,
+    pub memory_total_bytes: Option<u64>,
+    pub temperature_c: Option<u32>,
+    pub power_watts: Option<f64>,
+}
+
+// NVML is loaded on first use so driver initialization stays off the startup path
+pub struct Gpu {
+    nvml: OnceCell<Option<Nvml>>,
+}
+
+impl Gpu {
+    pub fn new() -> Self {
+        Gpu {
+            nvml: OnceCell::new(),
+        }
+    }
+
+    pub fn preload(&self) {
+        let _ = self.nvml();
+    }
+
+    fn nvml(&self) -> Result<&Nvml, String> {
+        self.nvml
+            .get_or_init(|| match Nvml::init() {
+                Ok(nvml) => Some(nvml),
+                Err(e) => {
+                    tracing::warn!("NVML unavailable, GPU telemetry disabled: {}", e);
+                    None
+                }
+            })
+            .as_ref()
+            .ok_or_else(|| "NVML is not available on this machine".to_string())
+    }
+
+    // Combined board power draw across all devices
+    pub fn total_power_watts(&self) -> Result<f64, String> {
+        let nvml = self.nvml()?;
+        let count = nvml
+            .device_count()
+            .map_err(|e| format!("Failed to count GPUs: {}", e))?;
+        let mut milliwatts = 0u64;
+        for index in 0..count {
+            let device = nvml
+                .device_by_index(index)
+                .map_err(|e| format!("Failed to open GPU {}: {}", index, e))?;
+            milliwatts += device
+                .power_usage()
+                .map_err(|e| format!("Failed to read power draw of GPU {}: {}", index, e))?
+                as u64;
+        }
+        Ok(milliwatts as f64 / 1000.0)
+    }
+
+    // Device memory in use across all devices
+    pub fn total_memory_used_bytes(&self) -> Result<f64, String> {
+        let nvml = self.nvml()?;
+        let count = nvml
+            .device_count()
+            .map_err(|e| format!("Failed to count GPUs: {}", e))?;
+        let mut used = 0u64;
+        for index in 0..count {
+            let device = nvml
+                .device_by_index(index)
+                .map_err(|e| format!("Failed to open GPU {}: {}", index, e))?;
+            used += device
+                .memory_info()
+                .map_err(|e| format!("Failed to read memory of GPU {}: {}", index, e))?
+                .used;
+        }
+        Ok(used as f64)
+    }
+
+    // Compute and graphics processes, merged per pid across devices
+    pub fn processes(&self) -> Result<Vec<GpuProcess>, String> {
+        let nvml = self.nvml()?;
+        let count = nvml
+            .device_count()
+            .map_err(|e| format!("Failed to count GPUs: {}", e))?;
+        let mut by_pid: BTreeMap<u32, GpuProcess> = BTreeMap::new();
+        for index in 0..count {
+            let device = nvml
+                .device_by_index(index)
+                .map_err(|e| format!("Failed to open GPU {}: {}", index, e))?;
+            let mut infos = device
+                .running_compute_processes()
+                .map_err(|e| format!("Failed to list processes on GPU {}: {}", index, e))?;
+            infos.extend(device.running_graphics_processes().unwrap_or_default());
+            for info in infos {
+                let used = match info.used_gpu_memory {
+                    UsedGpuMemory::Used(bytes) => Some(bytes),
+                    UsedGpuMemory::Unavailable => None,
+                };
+                let entry = by_pid.entry(info.pid).or_insert(GpuProcess {
+                    pid: info.pid,
+                    gpu_indices: Vec::new(),
+                    used_memory_bytes: None,
+                });
+                if !entry.gpu_indices.contains(&index) {
+                    entry.gpu_indices.push(index);
+                }
+                if let Some(bytes) = used {
+                    entry.used_memory_bytes = Some(entry.used_memory_bytes.unwrap_or(0) + bytes);
+                }
+            }
+        }
+        Ok(by_pid.into_values().collect())
+    }
+
+    // Highest CUDA version the installed driver supports, as (major, minor)
+    pub fn cuda_driver_version(&self) -> Result<(i32, i32), String> {
+        let version = self
+            .nvml()?
+            .sys_cuda_driver_version()
+            .map_err(|e| format!("Failed to read CUDA driver version: {}", e))?;
+        Ok((
+            nvml_wrapper::cuda_driver_version_major(version),
+            nvml_wrapper::cuda_driver_version_minor(version),
+        ))
+    }
+
+    pub fn devices(&self) -> Result<Vec<GpuDevice>, String> {
+        let nvml = self.nvml()?;
+        let count = nvml
+            .device_count()
+            .map_err(|e| format!("Failed to count GPUs: {}", e))?;
+        (0..count)
+            .map(|index| {
+                let device = nvml
+                    .device_by_index(index)
+                    .map_err(|e| format!("Failed to open GPU {}: {}", index, e))?;
+                let memory = device
+                    .memory_info()
+                    .map_err(|e| format!("Failed to read memory of GPU {}: {}", index, e))?;
+                let capability = device.cuda_compute_capability().map_err(|e| {
+                    format!("Failed to read compute capability of GPU {}: {}", index, e)
+                })?;
+                Ok(GpuDevice {
+                    index,
+                    name: device.name().unwrap_or_default(),
+                    total_memory_bytes: memory.total,
+                    free_memory_bytes: memory.free,
+                    compute_capability: (capability.major, capability.minor),
+                })
+            })
+            .collect()
+    }
+
+    pub fn device_count(&self) -> Result<u32, String> {
+        self.nvml()?
+            .device_count()
+            .map_err(|e| format!("Failed to count GPUs: {}", e))
+    }
+
+    pub fn stats(&self) -> Result<Vec<GpuStats>, String> {
+        let nvml = self.nvml()?;
+        (0..self.device_count()?)
+            .map(|index| {
+                let device = nvml
+                    .device_by_index(index)
+                    .map_err(|e| format!("Failed to open GPU {}: {}", index, e))?;
+                let memory = device.memory_info().ok();
+                Ok(GpuStats {
+                    index,
+                    name: device.name().unwrap_or_default(),
+                    utilization_percent: device.utilization_rates().ok().map(|u| u.gpu),
+                    memory_used_bytes: memory.as_ref().map(|m| m.used),
+                    memory_total_bytes: memory.as_ref().map(|m| m.total),
+                    temperature_c: device.temperature(TemperatureSensor::Gpu).ok(),
+                    power_watts: device.power_usage().ok().map(|mw| mw as f64 / 1000.0),
+                })
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub fn get_gpu_stats(gpu: State<'_, Gpu>) -> Result<Vec<GpuStats>, String> {
+    install_mode::require_local("GPU monitoring")?;
+    gpu.stats()
+}
@@ -0,0 +1,236 @@
+// GPU memory consumers and the optional "kill other GPU hogs before training" hook
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+use crate::gpu::Gpu;
+use crate::{audit, install_mode, journal, managed, storage};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GpuHogSettings {
+    kill_before_training: bool,
+    // Substrings of the command line that mark a process as part of Agent-0
+    agent0_patterns: Vec<String>,
+    // Process names that are never killed, e.g. the desktop compositor
+    allowlist: Vec<String>,
+}
+
+impl Default for GpuHogSettings {
+    fn default() -> Self {
+        GpuHogSettings {
+            kill_before_training: false,
+            agent0_patterns: vec![
+                "agent0".to_string(),
+                "autogen_api_shim".to_string(),
+                "uvicorn".to_string(),
+                "vllm".to_string(),
+            ],
+            allowlist: vec![
+                "Xorg".to_string(),
+                "gnome-shell".to_string(),
+                "kwin_x11".to_string(),
+                "kwin_wayland".to_string(),
+                "dwm.exe".to_string(),
+                "explorer.exe".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProcessView {
+    pid: u32,
+    name: String,
+    command_line: String,
+    gpu_indices: Vec<u32>,
+    used_memory_bytes: Option<u64>,
+    agent0: bool,
+    allowlisted: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct HookReport {
+    enabled: bool,
+    killed: Vec<ProcessView>,
+    spared: Vec<ProcessView>,
+    errors: Vec<String>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("gpu_hogs.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<GpuHogSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+// Returns (name, command line); empty when the process is gone or inaccessible
+#[cfg(target_os = "linux")]
+pub fn describe(pid: u32) -> (String, String) {
+    let name = std::fs::read_to_string(format!("/proc/{}/comm", pid)).unwrap_or_default();
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let cmdline = String::from_utf8_lossy(&cmdline).replace('\0', " ");
+    (name.trim().to_string(), cmdline.trim().to_string())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn describe(pid: u32) -> (String, String) {
+    let output = |field: &str| {
+        Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", field])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default()
+    };
+    let name = output("comm=");
+    let name = name.rsplit('/').next().unwrap_or("").to_string();
+    (name, output("args="))
+}
+
+#[cfg(windows)]
+pub fn describe(pid: u32) -> (String, String) {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    // "name.exe","1234",...
+    let name = output
+        .split(',')
+        .next()
+        .unwrap_or("")
+        .trim_matches('"')
+        .to_string();
+    // tasklist has no command line; another user's process leaves it empty
+    let command_line = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine",
+                pid
+            ),
+        ])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default();
+    (name, command_line)
+}
+
+#[cfg(unix)]
+pub fn kill(pid: u32) -> Result<(), String> {
+    let status = Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill exited with {}", status))
+    }
+}
+
+#[cfg(windows)]
+pub fn kill(pid: u32) -> Result<(), String> {
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .status()
+        .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("taskkill exited with {}", status))
+    }
+}
+
+fn inspect(app: &AppHandle, settings: &GpuHogSettings) -> Result<Vec<ProcessView>, String> {
+    let own = [Some(std::process::id()), managed::running_pid(app)];
+    Ok(app
+        .state::<Gpu>()
+        .processes()?
+        .into_iter()
+        .map(|process| {
+            let (name, command_line) = describe(process.pid);
+            // The name as well, for when the command line cannot be read
+            let agent0 = own.contains(&Some(process.pid))
+                || settings.agent0_patterns.iter().any(|p| {
+                    !p.is_empty()
+                        && (command_line.contains(p.as_str()) || name.contains(p.as_str()))
+                });
+            let allowlisted = settings.allowlist.iter().any(|a| a == &name);
+            ProcessView {
+                pid: process.pid,
+                name,
+                command_line,
+                gpu_indices: process.gpu_indices,
+                used_memory_bytes: process.used_memory_bytes,
+                agent0,
+                allowlisted,
+            }
+        })
+        .collect())
+}
+
+// Run by the training flow before it asks Agent-0 to start a job
+pub fn pre_training(app: &AppHandle) -> Result<HookReport, String> {
+    let settings = load_settings(app)?;
+    if !settings.kill_before_training {
+        return Ok(HookReport::default());
+    }
+    let mut report = HookReport {
+        enabled: true,
+        ..HookReport::default()
+    };
+    for process in inspect(app, &settings)? {
+        // Unknown names are spared: a process we cannot identify is not worth the risk
+        if process.agent0 || process.allowlisted || process.name.is_empty() {
+            report.spared.push(process);
+            continue;
+        }
+        let args = json!({ "pid": process.pid, "name": process.name });
+        let result = kill(process.pid);
+        audit::record(
+            app,
+            "pre_training_hook",
+            "kill_gpu_process",
+            &args,
+            &result.clone().map(|_| Value::Null),
+        );
+        match result {
+            Ok(()) => report.killed.push(process),
+            Err(e) => report
+                .errors
+                .push(format!("{} ({}): {}", process.name, process.pid, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_gpu_processes(app: AppHandle) -> Result<Vec<ProcessView>, String> {
+    install_mode::require_local("GPU process management")?;
+    inspect(&app, &load_settings(&app)?)
+}
+
+#[tauri::command]
+pub fn get_gpu_hog_settings(app: AppHandle) -> Result<GpuHogSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_gpu_hog_settings(app: AppHandle, settings: GpuHogSettings) -> Result<(), String> {
+    journal::write_json(
+        &app,
+        "Edit GPU hog settings",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
+
+#[tauri::command]
+pub fn run_pre_training_hook(app: AppHandle) -> Result<HookReport, String> {
+    install_mode::require_local("GPU process management")?;
+    pre_training(&app)
+}
@@ -0,0 +1,55 @@
+// Heartbeats telling Agent-0 which desktop UIs are attached to it
+use serde::Serialize;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::api::{self, UiSession};
+use crate::{agent0, profiles};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+pub struct AttachedUi {
+    #[serde(flatten)]
+    session: UiSession,
+    // Set locally for this app's own entry
+    current: bool,
+}
+
+#[tauri::command]
+pub async fn register_ui_session(app: AppHandle) -> Result<(), String> {
+    let session = UiSession {
+        session_id: agent0::ui_session_id().to_string(),
+        app_version: Some(app.package_info().version.to_string()),
+        host: Some(gethostname::gethostname().to_string_lossy().into_owned()),
+        os: Some(std::env::consts::OS.to_string()),
+        profile: Some(profiles::active(&app)?.name),
+        last_seen: None,
+    };
+    api::register_ui_session(&agent0::base_url(), &session).await
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = register_ui_session(app.clone()).await {
+                tracing::warn!("UI heartbeat failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_attached_uis() -> Result<Vec<AttachedUi>, String> {
+    let list = api::list_ui_sessions(&agent0::base_url()).await?;
+    Ok(list
+        .sessions
+        .into_iter()
+        .map(|session| AttachedUi {
+            current: session.session_id == agent0::ui_session_id(),
+            session,
+        })
+        .collect())
+}
@@ -0,0 +1,224 @@
+// Health latency aggregated into an hour-of-day by weekday heatmap, kept up to date per sample
+use chrono::{Datelike, Local, TimeZone, Timelike};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::metrics::{now_secs, MetricsStore, Sample, TimeRange};
+use crate::telemetry;
+
+const HOUR_SECS: i64 = 3600;
+const DEFAULT_RANGE_SECS: i64 = 28 * 86400;
+const DEFAULT_PERCENTILE: f64 = 95.0;
+// Log-spaced histogram buckets 5% apart bound the percentile error to about 2.5%
+const BUCKET_GROWTH: f64 = 1.05;
+// Latencies under this share the first bucket
+const MIN_MS: f64 = 0.1;
+
+// One UTC hour of samples
+#[derive(Default, Clone)]
+struct HourCell {
+    count: u64,
+    sum: f64,
+    max: f64,
+    buckets: BTreeMap<u32, u64>,
+}
+
+fn bucket(value: f64) -> u32 {
+    if value <= MIN_MS {
+        0
+    } else {
+        ((value / MIN_MS).ln() / BUCKET_GROWTH.ln()).floor() as u32 + 1
+    }
+}
+
+// The bucket's geometric middle stands in for the samples in it
+fn bucket_value(index: u32) -> f64 {
+    if index == 0 {
+        MIN_MS
+    } else {
+        MIN_MS * BUCKET_GROWTH.powf(index as f64 - 0.5)
+    }
+}
+
+impl HourCell {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+        *self.buckets.entry(bucket(value)).or_insert(0) += 1;
+    }
+
+    fn merge(&mut self, other: &HourCell) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+        for (index, count) in &other.buckets {
+            *self.buckets.entry(*index).or_insert(0) += count;
+        }
+    }
+
+    fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        if p >= 100.0 {
+            return Some(self.max);
+        }
+        let rank = ((p / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_value(*index).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+struct Cells {
+    // Keyed by the hour's start, unix seconds
+    hours: BTreeMap<i64, HourCell>,
+    // Samples up to here came from the store; newer ones arrive through `observe`
+    built_through: i64,
+}
+
+#[derive(Default)]
+pub struct HeatmapState {
+    // Built from the store on first use
+    cells: Mutex<Option<Cells>>,
+}
+
+#[derive(Serialize)]
+pub struct HeatmapCell {
+    // 0 = Monday, local time
+    weekday: u32,
+    hour: u32,
+    samples: u64,
+    mean: Option<f64>,
+    // The requested percentile, in milliseconds
+    value: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct LatencyHeatmap {
+    series: &'static str,
+    range: TimeRange,
+    percentile: f64,
+    // Weekday-major: Monday 00:00 first, Sunday 23:00 last
+    cells: Vec<HeatmapCell>,
+    max: Option<f64>,
+}
+
+fn hour_of(ts: i64) -> i64 {
+    ts - ts.rem_euclid(HOUR_SECS)
+}
+
+fn build(store: &MetricsStore) -> Cells {
+    let now = now_secs();
+    let mut hours: BTreeMap<i64, HourCell> = BTreeMap::new();
+    let samples = store.range(
+        telemetry::LATENCY_SERIES,
+        TimeRange {
+            from: i64::MIN,
+            to: now,
+        },
+    );
+    for sample in &samples {
+        hours
+            .entry(hour_of(sample.ts))
+            .or_default()
+            .add(sample.value);
+    }
+    Cells {
+        hours,
+        built_through: samples.last().map_or(i64::MIN, |s| s.ts),
+    }
+}
+
+// Called with each latency sample as it is stored
+pub fn observe(app: &AppHandle, sample: Sample) {
+    let state = app.state::<HeatmapState>();
+    let mut cells = state.cells.lock().unwrap();
+    if let Some(cells) = cells.as_mut() {
+        if sample.ts > cells.built_through {
+            cells
+                .hours
+                .entry(hour_of(sample.ts))
+                .or_default()
+                .add(sample.value);
+        }
+        // Hours the store no longer keeps drop out too
+        if let Some(oldest) = app
+            .state::<MetricsStore>()
+            .oldest(telemetry::LATENCY_SERIES)
+        {
+            cells.hours = cells.hours.split_off(&hour_of(oldest));
+        }
+    }
+}
+
+// Sub-hour UTC offsets fold each UTC hour into the local hour it starts in
+fn local_slot(hour_start: i64) -> Option<(u32, u32)> {
+    let local = Local.timestamp_opt(hour_start, 0).earliest()?;
+    Some((local.weekday().num_days_from_monday(), local.hour()))
+}
+
+// Defaults to the last four weeks at p95
+#[tauri::command]
+pub fn get_latency_heatmap(
+    app: AppHandle,
+    state: State<'_, HeatmapState>,
+    range: Option<TimeRange>,
+    percentile: Option<f64>,
+) -> Result<LatencyHeatmap, String> {
+    let percentile = percentile.unwrap_or(DEFAULT_PERCENTILE);
+    if !(0.0..=100.0).contains(&percentile) {
+        return Err("Percentile must be between 0 and 100".to_string());
+    }
+    let range = range.unwrap_or_else(|| {
+        let now = now_secs();
+        TimeRange {
+            from: now - DEFAULT_RANGE_SECS,
+            to: now,
+        }
+    });
+    if range.to <= range.from {
+        return Err("Heatmap range must end after it starts".to_string());
+    }
+    let mut slots = vec![HourCell::default(); 7 * 24];
+    {
+        let mut cells = state.cells.lock().unwrap();
+        let cells = cells.get_or_insert_with(|| build(&app.state::<MetricsStore>()));
+        // Whole hours that overlap the range
+        for (start, cell) in cells.hours.range(hour_of(range.from)..=range.to) {
+            if let Some((weekday, hour)) = local_slot(*start) {
+                slots[(weekday * 24 + hour) as usize].merge(cell);
+            }
+        }
+    }
+    let cells: Vec<HeatmapCell> = slots
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| HeatmapCell {
+            weekday: index as u32 / 24,
+            hour: index as u32 % 24,
+            samples: cell.count,
+            mean: (cell.count > 0).then(|| cell.sum / cell.count as f64),
+            value: cell.percentile(percentile),
+        })
+        .collect();
+    let max = cells
+        .iter()
+        .filter_map(|c| c.value)
+        .fold(None, |max, v| Some(max.map_or(v, |m: f64| m.max(v))));
+    Ok(LatencyHeatmap {
+        series: telemetry::LATENCY_SERIES,
+        range,
+        percentile,
+        cells,
+        max,
+    })
+}
@@ -0,0 +1,160 @@
+// Recently run registry commands with their outcomes, so a sequence can be run again
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands;
+use crate::metrics::now_secs;
+
+const CAPACITY: usize = 100;
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Ok { value: Value },
+    Error { message: String },
+}
+
+#[derive(Serialize, Clone)]
+pub struct HistoryEntry {
+    id: u64,
+    command: String,
+    args: Value,
+    mutating: bool,
+    // "webview", "replay", "history" or "alert:<rule>"
+    source: String,
+    started_at: i64,
+    duration_ms: u64,
+    outcome: Outcome,
+}
+
+#[derive(Default)]
+pub struct HistoryState {
+    next_id: Mutex<u64>,
+    entries: Mutex<VecDeque<HistoryEntry>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RerunError {
+    // The UI shows the command and args and calls again with `confirmed`
+    ConfirmationRequired { command: String, args: Value },
+    Failed { message: String },
+}
+
+impl From<String> for RerunError {
+    fn from(message: String) -> Self {
+        RerunError::Failed { message }
+    }
+}
+
+impl fmt::Display for RerunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RerunError::ConfirmationRequired { command, .. } => {
+                write!(f, "'{}' changes state; confirm to run it again", command)
+            }
+            RerunError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+// Runs a registry command by name and records it
+pub async fn run(
+    app: &AppHandle,
+    source: &str,
+    command: &str,
+    args: Value,
+) -> Result<Value, String> {
+    let started_at = now_secs();
+    let started = Instant::now();
+    let result = commands::run(app, command, args.clone()).await;
+    record(app, source, command, args, started_at, started, &result);
+    result
+}
+
+// Also used by registry commands the webview calls directly; read-only ones it polls are noise
+pub fn record(
+    app: &AppHandle,
+    source: &str,
+    command: &str,
+    args: Value,
+    started_at: i64,
+    started: Instant,
+    result: &Result<Value, String>,
+) {
+    let mutating = commands::lookup(command).map_or(false, |spec| spec.mutating);
+    if !mutating && source == "webview" {
+        return;
+    }
+    let state = app.state::<HistoryState>();
+    let id = {
+        let mut next_id = state.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    };
+    let mut entries = state.entries.lock().unwrap();
+    if entries.len() == CAPACITY {
+        entries.pop_front();
+    }
+    entries.push_back(HistoryEntry {
+        id,
+        command: command.to_string(),
+        args,
+        mutating,
+        source: source.to_string(),
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        outcome: match result {
+            Ok(value) => Outcome::Ok {
+                value: value.clone(),
+            },
+            Err(message) => Outcome::Error {
+                message: message.clone(),
+            },
+        },
+    });
+}
+
+// Newest first
+#[tauri::command]
+pub fn get_command_history(
+    state: State<'_, HistoryState>,
+    limit: Option<usize>,
+) -> Vec<HistoryEntry> {
+    let entries = state.entries.lock().unwrap();
+    entries
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(CAPACITY))
+        .cloned()
+        .collect()
+}
+
+#[tauri::command]
+pub async fn rerun_command(
+    app: AppHandle,
+    entry_id: u64,
+    confirmed: Option<bool>,
+) -> Result<Value, RerunError> {
+    let entry = app
+        .state::<HistoryState>()
+        .entries
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|e| e.id == entry_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown history entry {}", entry_id))?;
+    if entry.mutating && !confirmed.unwrap_or(false) {
+        return Err(RerunError::ConfirmationRequired {
+            command: entry.command,
+            args: entry.args,
+        });
+    }
+    Ok(run(&app, "history", &entry.command, entry.args).await?)
+}
@@ -0,0 +1,136 @@
+// Local vs remote-only installs; remote-only never manages a local process or GPU
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+use crate::gpu::Gpu;
+use crate::managed::{self, ManagedState};
+use crate::{audit, journal, storage};
+
+// Subsystems the UI hides in remote-only installs; the commands that read or drive them refuse
+// to run
+const LOCAL_SUBSYSTEMS: [&str; 5] = [
+    "managed_server",
+    "gpu_stats",
+    "gpu_processes",
+    "energy",
+    "device_placement",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallMode {
+    Local,
+    RemoteOnly,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct InstallSettings {
+    // None until onboarding has picked a mode
+    #[serde(default)]
+    mode: Option<InstallMode>,
+}
+
+#[derive(Serialize)]
+pub struct InstallStatus {
+    mode: InstallMode,
+    onboarding_required: bool,
+    // Offered during onboarding: remote-only when no GPU is visible
+    suggested: InstallMode,
+    hidden_subsystems: Vec<&'static str>,
+    // Where the UI lands when it opens
+    home_view: &'static str,
+}
+
+// Checked by background loops every tick, so kept in memory
+static SETTINGS: Lazy<RwLock<InstallSettings>> =
+    Lazy::new(|| RwLock::new(InstallSettings::default()));
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("install_mode.json"))
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    *SETTINGS.write().unwrap() = storage::read_json(&settings_path(app)?)?;
+    Ok(())
+}
+
+// Existing installs predate the choice and keep managing their local server
+pub fn mode() -> InstallMode {
+    SETTINGS.read().unwrap().mode.unwrap_or(InstallMode::Local)
+}
+
+pub fn is_remote_only() -> bool {
+    mode() == InstallMode::RemoteOnly
+}
+
+pub fn require_local(what: &str) -> Result<(), String> {
+    if is_remote_only() {
+        Err(format!(
+            "{} is not available in a remote-only install",
+            what
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn status(app: &AppHandle) -> InstallStatus {
+    let chosen = SETTINGS.read().unwrap().mode;
+    let mode = mode();
+    // NVML is only probed while the install is local
+    let suggested = match mode {
+        InstallMode::RemoteOnly => InstallMode::RemoteOnly,
+        InstallMode::Local => match app.state::<Gpu>().device_count() {
+            Ok(count) if count > 0 => InstallMode::Local,
+            _ => InstallMode::RemoteOnly,
+        },
+    };
+    InstallStatus {
+        mode,
+        onboarding_required: chosen.is_none(),
+        suggested,
+        hidden_subsystems: match mode {
+            InstallMode::Local => Vec::new(),
+            InstallMode::RemoteOnly => LOCAL_SUBSYSTEMS.to_vec(),
+        },
+        home_view: match mode {
+            InstallMode::Local => "dashboard",
+            InstallMode::RemoteOnly => "server_registry",
+        },
+    }
+}
+
+#[tauri::command]
+pub fn get_install_mode(app: AppHandle) -> InstallStatus {
+    status(&app)
+}
+
+#[tauri::command]
+pub fn set_install_mode(app: AppHandle, mode: InstallMode) -> Result<InstallStatus, String> {
+    // A server this app launched would keep running unmanaged otherwise
+    if mode == InstallMode::RemoteOnly {
+        managed::stop_managed_server(app.clone(), app.state::<ManagedState>())?;
+    }
+    let settings = InstallSettings { mode: Some(mode) };
+    journal::write_json(
+        &app,
+        "Change install mode",
+        &settings_path(&app)?,
+        &settings,
+    )?;
+    *SETTINGS.write().unwrap() = settings;
+    let status = status(&app);
+    audit::record(
+        &app,
+        "install_mode",
+        "set_install_mode",
+        &json!({ "mode": mode }),
+        &Ok(Value::Null),
+    );
+    let _ = app.emit_all("install-mode-changed", &status);
+    Ok(status)
+}
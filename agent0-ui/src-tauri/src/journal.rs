@@ -0,0 +1,190 @@
+// Undo/redo journal for settings, profile, automation and server config edits
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::now_secs;
+use crate::{api, install_mode, language, lighting, log_levels, memory, profiles, storage};
+
+const MAX_DEPTH: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalTarget {
+    // JSON file directly under the data dir
+    File { name: String },
+    ServerConfig { base_url: String },
+}
+
+// A missing file is journaled as null
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    id: String,
+    label: String,
+    target: JournalTarget,
+    before: Value,
+    after: Value,
+    ts: i64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Journal {
+    undo: Vec<JournalEntry>,
+    redo: Vec<JournalEntry>,
+}
+
+fn journal_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("journal.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Journal, String> {
+    storage::read_json(&journal_path(app)?)
+}
+
+fn save(app: &AppHandle, journal: &Journal) -> Result<(), String> {
+    storage::write_json(&journal_path(app)?, journal)
+}
+
+pub fn record(app: &AppHandle, label: &str, target: JournalTarget, before: Value, after: Value) {
+    if before == after {
+        return;
+    }
+    let recorded = load(app).and_then(|mut journal| {
+        journal.undo.push(JournalEntry {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            label: label.to_string(),
+            target,
+            before,
+            after,
+            ts: now_secs(),
+        });
+        if journal.undo.len() > MAX_DEPTH {
+            let excess = journal.undo.len() - MAX_DEPTH;
+            journal.undo.drain(..excess);
+        }
+        // A fresh edit forks history, so what was undone can no longer be redone
+        journal.redo.clear();
+        save(app, &journal)
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record change journal: {}", e);
+    }
+}
+
+// What the app last saved to `name` through the journal, for repairing a corrupt file
+pub fn last_saved(app: &AppHandle, name: &str) -> Option<Value> {
+    let journal = load(app).ok()?;
+    journal
+        .undo
+        .iter()
+        .rev()
+        .find(|entry| {
+            entry.target
+                == JournalTarget::File {
+                    name: name.to_string(),
+                }
+        })
+        .map(|entry| entry.after.clone())
+        .filter(|value| !value.is_null())
+}
+
+// storage::write_json for user edits: the previous content is journaled so it can be undone
+pub fn write_json<T: Serialize>(
+    app: &AppHandle,
+    label: &str,
+    path: &Path,
+    value: &T,
+) -> Result<(), String> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Cannot journal {}", path.display()))?
+        .to_string();
+    let before: Value = storage::read_json(path).unwrap_or(Value::Null);
+    let after = serde_json::to_value(value)
+        .map_err(|e| format!("Failed to encode {}: {}", path.display(), e))?;
+    storage::write_json(path, value)?;
+    record(app, label, JournalTarget::File { name }, before, after);
+    Ok(())
+}
+
+// Puts `value` back, refusing if the target no longer holds `expected` so newer edits
+// made outside the journal are never silently discarded
+async fn restore(
+    app: &AppHandle,
+    target: &JournalTarget,
+    expected: &Value,
+    value: &Value,
+) -> Result<(), String> {
+    match target {
+        JournalTarget::File { name } => {
+            let path = storage::data_dir(app)?.join(name);
+            let current: Value = storage::read_json(&path)?;
+            if current != *expected {
+                return Err(format!("{} was changed since; not overwriting it", name));
+            }
+            if value.is_null() {
+                if path.exists() {
+                    fs::remove_file(&path)
+                        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+                }
+            } else {
+                storage::write_json(&path, value)?;
+            }
+            match name.as_str() {
+                "profiles.json" => profiles::init(app)?,
+                "memory.json" => memory::init(app)?,
+                "install_mode.json" => install_mode::init(app)?,
+                "lighting.json" => lighting::init(app)?,
+                "language.json" => language::init(app)?,
+                "log_levels.json" => log_levels::init(app)?,
+                _ => {}
+            }
+        }
+        JournalTarget::ServerConfig { base_url } => {
+            let current = api::get_config(base_url).await?;
+            if current.value != *expected {
+                return Err("The server config was changed since; not overwriting it".to_string());
+            }
+            api::set_config(base_url, value, current.etag.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_change_journal(app: AppHandle) -> Result<Journal, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub async fn undo_last_change(app: AppHandle) -> Result<JournalEntry, String> {
+    let mut journal = load(&app)?;
+    let entry = journal
+        .undo
+        .pop()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+    restore(&app, &entry.target, &entry.after, &entry.before).await?;
+    journal.redo.push(entry.clone());
+    save(&app, &journal)?;
+    let _ = app.emit_all("change-undone", &entry);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn redo_change(app: AppHandle) -> Result<JournalEntry, String> {
+    let mut journal = load(&app)?;
+    let entry = journal
+        .redo
+        .pop()
+        .ok_or_else(|| "Nothing to redo".to_string())?;
+    restore(&app, &entry.target, &entry.before, &entry.after).await?;
+    journal.undo.push(entry.clone());
+    save(&app, &journal)?;
+    let _ = app.emit_all("change-redone", &entry);
+    Ok(entry)
+}
@@ -0,0 +1,36 @@
+// Secrets kept in the OS keychain under the app's service name
+use keyring::{Entry, Error};
+
+const SERVICE: &str = "com.agent0.desktop";
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, name).map_err(|e| format!("Failed to open keychain entry {}: {}", name, e))
+}
+
+// None when no secret is stored under that name
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Failed to read secret {} from keychain: {}",
+            name, e
+        )),
+    }
+}
+
+pub fn set(name: &str, secret: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store secret {} in keychain: {}", name, e))
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_password() {
+        Ok(()) | Err(Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!(
+            "Failed to delete secret {} from keychain: {}",
+            name, e
+        )),
+    }
+}
@@ -0,0 +1,166 @@
+// Local language detection for outgoing prompts, sent to Agent-0 as routing hints
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use tauri::AppHandle;
+
+use crate::metrics::now_secs;
+use crate::{journal, storage};
+
+pub const LANGUAGE_HEADER: &str = "X-Agent0-Language";
+pub const PERSONA_HEADER: &str = "X-Agent0-Persona";
+// Shorter prompts are mostly greetings and names; detection on them is a coin toss
+const MIN_PROMPT_CHARS: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LanguageSettings {
+    enabled: bool,
+    // Detections below this are sent without a hint
+    min_confidence: f64,
+    // ISO 639-3 code (e.g. "deu") to the persona Agent-0 should answer with
+    personas: HashMap<String, String>,
+}
+
+impl Default for LanguageSettings {
+    fn default() -> Self {
+        LanguageSettings {
+            enabled: true,
+            min_confidence: 0.5,
+            personas: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Detection {
+    code: String,
+    name: String,
+    confidence: f64,
+    pub persona: Option<String>,
+    // Headers to attach to the request carrying the prompt
+    pub hints: HashMap<String, String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LanguageStats {
+    since: i64,
+    // Prompts per language code, since launch
+    languages: BTreeMap<String, u64>,
+    undetected: u64,
+}
+
+// Consulted for every proxied chat request, so kept in memory
+static SETTINGS: Lazy<RwLock<LanguageSettings>> =
+    Lazy::new(|| RwLock::new(LanguageSettings::default()));
+
+static STATS: Lazy<Mutex<LanguageStats>> = Lazy::new(|| {
+    Mutex::new(LanguageStats {
+        since: now_secs(),
+        languages: BTreeMap::new(),
+        undetected: 0,
+    })
+});
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("language.json"))
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    *SETTINGS.write().unwrap() = storage::read_json(&settings_path(app)?)?;
+    Ok(())
+}
+
+// Counts toward the stats; None when disabled, too short or not confident enough
+pub fn detect(text: &str) -> Option<Detection> {
+    let settings = SETTINGS.read().unwrap().clone();
+    if !settings.enabled {
+        return None;
+    }
+    let info = if text.trim().chars().count() >= MIN_PROMPT_CHARS {
+        whatlang::detect(text).filter(|i| i.confidence() >= settings.min_confidence)
+    } else {
+        None
+    };
+    let mut stats = STATS.lock().unwrap();
+    let info = match info {
+        Some(info) => info,
+        None => {
+            stats.undetected += 1;
+            return None;
+        }
+    };
+    let code = info.lang().code().to_string();
+    *stats.languages.entry(code.clone()).or_insert(0) += 1;
+    let persona = settings.personas.get(&code).cloned();
+    let mut hints = HashMap::new();
+    hints.insert(LANGUAGE_HEADER.to_string(), code.clone());
+    if let Some(persona) = &persona {
+        hints.insert(PERSONA_HEADER.to_string(), persona.clone());
+    }
+    Some(Detection {
+        code,
+        name: info.lang().eng_name().to_string(),
+        confidence: info.confidence(),
+        persona,
+        hints,
+    })
+}
+
+// The newest user turn of a chat body: {"prompt"}, {"message"} or {"messages": [...]}
+pub fn prompt_text(body: &Value) -> Option<&str> {
+    body["prompt"]
+        .as_str()
+        .or_else(|| body["message"].as_str())
+        .or_else(|| {
+            body["messages"]
+                .as_array()?
+                .iter()
+                .rev()
+                .find(|m| m["role"].as_str().map_or(true, |r| r == "user"))?["content"]
+                .as_str()
+        })
+}
+
+#[tauri::command]
+pub fn detect_prompt_language(text: String) -> Option<Detection> {
+    detect(&text)
+}
+
+#[tauri::command]
+pub fn get_language_stats() -> LanguageStats {
+    STATS.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn get_language_settings() -> LanguageSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_language_settings(app: AppHandle, settings: LanguageSettings) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&settings.min_confidence) {
+        return Err("Minimum confidence must be between 0 and 1".to_string());
+    }
+    if let Some(code) = settings
+        .personas
+        .keys()
+        .find(|c| c.len() != 3 || !c.chars().all(|ch| ch.is_ascii_lowercase()))
+    {
+        return Err(format!(
+            "Invalid language code '{}', expected ISO 639-3 such as \"eng\"",
+            code
+        ));
+    }
+    journal::write_json(
+        &app,
+        "Edit language routing",
+        &settings_path(&app)?,
+        &settings,
+    )?;
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
@@ -0,0 +1,248 @@
+// Launch arguments that script the GUI, forwarded to the running instance when there is one
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::{history, storage};
+
+const INSTANCE_FILE: &str = "instance.json";
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LaunchAction {
+    Pause,
+    Resume,
+    Profile { name: String },
+    Open { view: String },
+    Ask { prompt: String },
+}
+
+impl LaunchAction {
+    // Every action runs through the command registry so it lands in the command history
+    fn command(&self) -> (&'static str, Value) {
+        match self {
+            LaunchAction::Pause => ("pause_service", Value::Null),
+            LaunchAction::Resume => ("resume_service", Value::Null),
+            LaunchAction::Profile { name } => ("set_active_profile", json!({ "name": name })),
+            LaunchAction::Open { view } => ("open_view", json!({ "view": view })),
+            LaunchAction::Ask { prompt } => ("ask", json!({ "prompt": prompt })),
+        }
+    }
+}
+
+// Where a running instance listens; the token keeps other local users' processes out
+#[derive(Serialize, Deserialize, Default)]
+struct InstanceFile {
+    port: u16,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Forwarded {
+    token: String,
+    actions: Vec<LaunchAction>,
+}
+
+// Actions from this process's own command line wait for the webview to load
+#[derive(Default)]
+pub struct LaunchState {
+    pending: Mutex<Vec<LaunchAction>>,
+}
+
+#[derive(Serialize, Clone)]
+struct OpenView {
+    view: String,
+}
+
+#[derive(Serialize, Clone)]
+struct Ask {
+    prompt: String,
+}
+
+fn value<'a>(flag: &str, rest: &mut impl Iterator<Item = &'a String>) -> Result<String, String> {
+    rest.next()
+        .filter(|v| !v.starts_with("--"))
+        .cloned()
+        .ok_or_else(|| format!("{} needs a value", flag))
+}
+
+pub fn parse(args: &[String]) -> Result<Vec<LaunchAction>, String> {
+    let mut actions = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, inline)) => (flag, Some(inline.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut take = |flag: &str| match inline.clone() {
+            Some(inline) => Ok(inline),
+            None => value(flag, &mut rest),
+        };
+        actions.push(match flag {
+            "--pause" => LaunchAction::Pause,
+            "--resume" => LaunchAction::Resume,
+            "--profile" => LaunchAction::Profile { name: take(flag)? },
+            "--open" => LaunchAction::Open { view: take(flag)? },
+            "--ask" => LaunchAction::Ask {
+                prompt: take(flag)?,
+            },
+            // Platform launchers add their own arguments (e.g. macOS -psn_*); leave them be
+            _ => continue,
+        });
+    }
+    Ok(actions)
+}
+
+fn instance_path(dir: &Path) -> PathBuf {
+    dir.join(INSTANCE_FILE)
+}
+
+// True when a running instance took the actions, in which case this process should exit
+pub fn forward(data_dir: Option<PathBuf>, actions: &[LaunchAction]) -> Result<bool, String> {
+    let instance: InstanceFile = match data_dir {
+        Some(dir) => storage::read_json(&instance_path(&dir))?,
+        None => return Ok(false),
+    };
+    if instance.port == 0 {
+        return Ok(false);
+    }
+    // A stale file from a crashed instance refuses the connection
+    let mut stream = match TcpStream::connect(("127.0.0.1", instance.port)) {
+        Ok(stream) => stream,
+        Err(_) => return Ok(false),
+    };
+    let _ = stream.set_read_timeout(Some(FORWARD_TIMEOUT));
+    let message = Forwarded {
+        token: instance.token,
+        actions: actions.to_vec(),
+    };
+    let mut line = serde_json::to_vec(&message)
+        .map_err(|e| format!("Failed to encode launch arguments: {}", e))?;
+    line.push(b'\n');
+    stream
+        .write_all(&line)
+        .map_err(|e| format!("Failed to forward launch arguments: {}", e))?;
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("Failed to read reply from running instance: {}", e))?;
+    match reply.trim() {
+        "ok" => Ok(true),
+        other => Err(format!(
+            "Running instance rejected launch arguments: {}",
+            other
+        )),
+    }
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+pub fn open_view(app: &AppHandle, view: String) -> Result<(), String> {
+    if view.trim().is_empty() {
+        return Err("View name cannot be empty".to_string());
+    }
+    show_main(app);
+    let _ = app.emit_all("open-view", OpenView { view });
+    Ok(())
+}
+
+pub fn ask(app: &AppHandle, prompt: String) -> Result<(), String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt cannot be empty".to_string());
+    }
+    show_main(app);
+    let _ = app.emit_all("ask", Ask { prompt });
+    Ok(())
+}
+
+// In order, so `--profile prod --pause` pauses the prod server
+async fn dispatch(app: &AppHandle, actions: Vec<LaunchAction>) {
+    for action in actions {
+        let (command, args) = action.command();
+        if let Err(e) = history::run(app, "launch", command, args).await {
+            tracing::warn!("Launch action {} failed: {}", command, e);
+        }
+    }
+}
+
+async fn serve(app: AppHandle, listener: TcpListener, token: String) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(_) => continue,
+        };
+        let (reader, mut writer) = stream.into_split();
+        let mut line = String::new();
+        if tokio::io::BufReader::new(reader)
+            .read_line(&mut line)
+            .await
+            .is_err()
+        {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Forwarded>(&line) {
+            Ok(message) if message.token == token => {
+                show_main(&app);
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { dispatch(&app, message.actions).await });
+                "ok".to_string()
+            }
+            Ok(_) => "invalid token".to_string(),
+            Err(e) => format!("invalid message: {}", e),
+        };
+        let _ = writer.write_all(format!("{}\n", reply).as_bytes()).await;
+    }
+}
+
+// Listens for later launches and queues this launch's own actions
+pub fn start(app: AppHandle, actions: Vec<LaunchAction>) -> Result<(), String> {
+    *app.state::<LaunchState>().pending.lock().unwrap() = actions;
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .map_err(|e| format!("Failed to open instance listener: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read instance listener address: {}", e))?
+        .port();
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure instance listener: {}", e))?;
+    let token = hex::encode(rand::random::<[u8; 16]>());
+    storage::write_json(
+        &instance_path(&storage::data_dir(&app)?),
+        &InstanceFile {
+            port,
+            token: token.clone(),
+        },
+    )?;
+    tauri::async_runtime::spawn(async move {
+        match TcpListener::from_std(listener) {
+            Ok(listener) => serve(app, listener, token).await,
+            Err(e) => tracing::warn!("Failed to open instance listener: {}", e),
+        }
+    });
+    Ok(())
+}
+
+// Called on every page load; only the first finds anything queued
+pub fn on_page_load(app: &AppHandle) {
+    let actions = std::mem::take(&mut *app.state::<LaunchState>().pending.lock().unwrap());
+    if actions.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { dispatch(&app, actions).await });
+}
@@ -0,0 +1,326 @@
+// Agent-0 status mirrored onto RGB devices through an OpenRGB server
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::openrgb::{self, Client, Controller};
+use crate::{agent0, api, journal, storage, store};
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+const CLIENT_NAME: &str = "Agent-0 UI";
+const MIN_PULSE_MS: u64 = 200;
+// Darkest point of a pulse, so the device never looks switched off
+const PULSE_FLOOR: f64 = 0.15;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LightStatus {
+    Healthy,
+    Paused,
+    Down,
+    Training,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectStyle {
+    Solid,
+    Pulse,
+    Off,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Effect {
+    style: EffectStyle,
+    // "#rrggbb"
+    color: String,
+    // One full pulse; ignored by the other styles
+    #[serde(default = "default_period_ms")]
+    period_ms: u64,
+}
+
+fn default_period_ms() -> u64 {
+    2000
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LightingEffects {
+    healthy: Effect,
+    paused: Effect,
+    down: Effect,
+    training: Effect,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LightingSettings {
+    // Off until the operator opts in
+    enabled: bool,
+    host: String,
+    port: u16,
+    // Controller indices to drive; empty means every device OpenRGB reports
+    devices: Vec<u32>,
+    effects: LightingEffects,
+}
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        let effect = |style, color: &str| Effect {
+            style,
+            color: color.to_string(),
+            period_ms: default_period_ms(),
+        };
+        LightingSettings {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: openrgb::DEFAULT_PORT,
+            devices: Vec::new(),
+            effects: LightingEffects {
+                healthy: effect(EffectStyle::Solid, "#00c853"),
+                paused: effect(EffectStyle::Solid, "#ff9100"),
+                down: effect(EffectStyle::Solid, "#d50000"),
+                training: effect(EffectStyle::Pulse, "#00c853"),
+            },
+        }
+    }
+}
+
+impl LightingEffects {
+    fn get(&self, status: LightStatus) -> &Effect {
+        match status {
+            LightStatus::Healthy => &self.healthy,
+            LightStatus::Paused => &self.paused,
+            LightStatus::Down => &self.down,
+            LightStatus::Training => &self.training,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct LightingStatus {
+    connected: bool,
+    status: Option<LightStatus>,
+    devices: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct LightingState {
+    status: Mutex<LightingStatus>,
+}
+
+// Read every frame, so kept in memory rather than re-read from disk
+static SETTINGS: Lazy<RwLock<LightingSettings>> =
+    Lazy::new(|| RwLock::new(LightingSettings::default()));
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("lighting.json"))
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    *SETTINGS.write().unwrap() = storage::read_json(&settings_path(app)?)?;
+    Ok(())
+}
+
+fn parse_color(color: &str) -> Result<[u8; 3], String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let bytes = hex::decode(hex).map_err(|_| format!("Invalid color '{}'", color))?;
+    match bytes.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b]),
+        _ => Err(format!("Invalid color '{}', expected #rrggbb", color)),
+    }
+}
+
+// Color for this frame; colors were checked when the settings were saved
+fn frame(effect: &Effect, elapsed: Duration) -> [u8; 3] {
+    let rgb = parse_color(&effect.color).unwrap_or([0, 0, 0]);
+    match effect.style {
+        EffectStyle::Solid => rgb,
+        EffectStyle::Off => [0, 0, 0],
+        EffectStyle::Pulse => {
+            // Only saving checks the period, and a hand-edited file may hold 0
+            let period = effect.period_ms.max(MIN_PULSE_MS);
+            let phase = (elapsed.as_millis() as u64 % period) as f64 / period as f64;
+            let wave = 0.5 - 0.5 * (phase * 2.0 * std::f64::consts::PI).cos();
+            let level = PULSE_FLOOR + (1.0 - PULSE_FLOOR) * wave;
+            [
+                (rgb[0] as f64 * level) as u8,
+                (rgb[1] as f64 * level) as u8,
+                (rgb[2] as f64 * level) as u8,
+            ]
+        }
+    }
+}
+
+async fn agent0_status() -> LightStatus {
+    match api::health(&agent0::base_url()).await {
+        Err(_) => LightStatus::Down,
+        Ok(health) if health.status.as_deref() == Some("paused") => LightStatus::Paused,
+        Ok(health) if health.training.unwrap_or(false) => LightStatus::Training,
+        Ok(_) => LightStatus::Healthy,
+    }
+}
+
+struct Link {
+    // Settings the link was opened with; any change reconnects
+    target: (String, u16, Vec<u32>),
+    client: Client,
+    controllers: Vec<Controller>,
+    last: Option<[u8; 3]>,
+}
+
+async fn connect(settings: &LightingSettings) -> Result<Link, String> {
+    let mut client = Client::connect(&settings.host, settings.port, CLIENT_NAME).await?;
+    let mut controllers = client.controllers().await?;
+    controllers.retain(|c| settings.devices.is_empty() || settings.devices.contains(&c.index));
+    if controllers.is_empty() {
+        return Err("OpenRGB reports no matching devices".to_string());
+    }
+    for controller in &controllers {
+        client.take_control(controller).await?;
+    }
+    Ok(Link {
+        target: (
+            settings.host.clone(),
+            settings.port,
+            settings.devices.clone(),
+        ),
+        client,
+        controllers,
+        last: None,
+    })
+}
+
+async fn paint(link: &mut Link, rgb: [u8; 3]) -> Result<(), String> {
+    if link.last == Some(rgb) {
+        return Ok(());
+    }
+    for controller in &link.controllers {
+        link.client.set_color(controller, rgb).await?;
+    }
+    link.last = Some(rgb);
+    Ok(())
+}
+
+fn update(app: &AppHandle, f: impl FnOnce(&mut LightingStatus)) {
+    f(&mut app.state::<LightingState>().status.lock().unwrap());
+}
+
+pub fn start(app: AppHandle) {
+    // Health is polled apart from the frame loop so a slow server cannot stall a pulse
+    let poller = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if SETTINGS.read().unwrap().enabled {
+                let status = agent0_status().await;
+                if status != LightStatus::Down {
+                    store::set_paused(&poller, status == LightStatus::Paused);
+                }
+                update(&poller, |s| s.status = Some(status));
+            }
+        }
+    });
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(FRAME_INTERVAL);
+        let started = Instant::now();
+        let mut link: Option<Link> = None;
+        let mut retry_at = Instant::now();
+        loop {
+            ticker.tick().await;
+            let settings = SETTINGS.read().unwrap().clone();
+            let target = (
+                settings.host.clone(),
+                settings.port,
+                settings.devices.clone(),
+            );
+            if !settings.enabled || link.as_ref().map_or(false, |l| l.target != target) {
+                link = None;
+                update(&app, |s| s.connected = false);
+            }
+            if !settings.enabled {
+                continue;
+            }
+            let polled = app.state::<LightingState>().status.lock().unwrap().status;
+            let status = match polled {
+                Some(status) => status,
+                None => continue,
+            };
+            if link.is_none() && Instant::now() >= retry_at {
+                match connect(&settings).await {
+                    Ok(connected) => {
+                        let devices = connected
+                            .controllers
+                            .iter()
+                            .map(|c| c.name.clone())
+                            .collect();
+                        update(&app, |s| {
+                            s.connected = true;
+                            s.devices = devices;
+                            s.error = None;
+                        });
+                        link = Some(connected);
+                    }
+                    Err(e) => {
+                        retry_at = Instant::now() + RECONNECT_INTERVAL;
+                        update(&app, |s| s.error = Some(e));
+                    }
+                }
+            }
+            if let Some(connected) = link.as_mut() {
+                let rgb = frame(settings.effects.get(status), started.elapsed());
+                if let Err(e) = paint(connected, rgb).await {
+                    link = None;
+                    retry_at = Instant::now() + RECONNECT_INTERVAL;
+                    update(&app, |s| {
+                        s.connected = false;
+                        s.error = Some(e);
+                    });
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_lighting_settings() -> LightingSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_lighting_settings(app: AppHandle, settings: LightingSettings) -> Result<(), String> {
+    let effects = &settings.effects;
+    for effect in [
+        &effects.healthy,
+        &effects.paused,
+        &effects.down,
+        &effects.training,
+    ] {
+        parse_color(&effect.color)?;
+        if effect.period_ms < MIN_PULSE_MS {
+            return Err(format!("Pulse period must be at least {} ms", MIN_PULSE_MS));
+        }
+    }
+    if settings.host.trim().is_empty() {
+        return Err("OpenRGB host cannot be empty".to_string());
+    }
+    journal::write_json(
+        &app,
+        "Edit lighting settings",
+        &settings_path(&app)?,
+        &settings,
+    )?;
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_lighting_status(state: State<'_, LightingState>) -> LightingStatus {
+    state.status.lock().unwrap().clone()
+}
@@ -0,0 +1,100 @@
+// Large Agent-0 lists streamed to the webview page by page
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+
+use crate::agent0;
+use crate::api::{paginated, Paginated};
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ListKind {
+    Conversations,
+    Adapters,
+    RequestLogs,
+}
+
+impl ListKind {
+    fn endpoint(self) -> Paginated {
+        match self {
+            ListKind::Conversations => paginated::LIST_CONVERSATIONS,
+            ListKind::Adapters => paginated::LIST_ADAPTERS,
+            ListKind::RequestLogs => paginated::LIST_REQUEST_LOGS,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct ListPage {
+    stream_id: String,
+    list: ListKind,
+    index: usize,
+    items: Vec<Value>,
+    done: bool,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ListStreams {
+    active: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+async fn stream(app: &AppHandle, stream_id: &str, list: ListKind) {
+    let endpoint = list.endpoint();
+    let mut pages = agent0::paginate(&agent0::base_url(), endpoint.path, endpoint.key);
+    let mut index = 0;
+    let emit = |index, items, done, error| {
+        let _ = app.emit_all(
+            "list-page",
+            ListPage {
+                stream_id: stream_id.to_string(),
+                list,
+                index,
+                items,
+                done,
+                error,
+            },
+        );
+    };
+    while let Some(page) = pages.recv().await {
+        match page {
+            Ok(page) => emit(index, page.items, false, None),
+            Err(e) => return emit(index, Vec::new(), true, Some(e)),
+        }
+        index += 1;
+    }
+    emit(index, Vec::new(), true, None);
+}
+
+// Returns at once; pages follow as "list-page" events tagged with the returned id
+#[tauri::command]
+pub fn stream_list(app: AppHandle, state: State<'_, ListStreams>, list: ListKind) -> String {
+    let stream_id = hex::encode(rand::random::<[u8; 8]>());
+    let mut active = state.active.lock().unwrap();
+    let id = stream_id.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        stream(&app, &id, list).await;
+        // Waits for the insert below if the list was tiny
+        app.state::<ListStreams>()
+            .active
+            .lock()
+            .unwrap()
+            .remove(&id);
+    });
+    active.insert(stream_id.clone(), handle);
+    stream_id
+}
+
+#[tauri::command]
+pub fn cancel_list_stream(state: State<'_, ListStreams>, stream_id: String) -> Result<(), String> {
+    match state.active.lock().unwrap().remove(&stream_id) {
+        Some(handle) => {
+            handle.abort();
+            Ok(())
+        }
+        None => Err(format!("No list stream '{}'", stream_id)),
+    }
+}
@@ -0,0 +1,149 @@
+// Per-module log levels, changed at runtime and kept per server profile
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::{journal, profiles, storage};
+
+// Modules not named in the active profile's levels log at this
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+// Backend module name (e.g. "agent0", "metrics") to its level
+pub type ModuleLevels = BTreeMap<String, LogLevel>;
+
+static FILTER: OnceCell<reload::Handle<Targets, Registry>> = OnceCell::new();
+
+fn levels_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("log_levels.json"))
+}
+
+// Keyed by profile name
+fn load(app: &AppHandle) -> Result<BTreeMap<String, ModuleLevels>, String> {
+    storage::read_json(&levels_path(app)?)
+}
+
+fn targets(levels: &ModuleLevels) -> Targets {
+    Targets::new().with_default(DEFAULT_LEVEL).with_targets(
+        levels
+            .iter()
+            .map(|(module, level)| (module_target(module), level.filter())),
+    )
+}
+
+// Events carry their module path as the target, e.g. agent0_ui::metrics
+fn module_target(module: &str) -> String {
+    format!("{}::{}", env!("CARGO_CRATE_NAME"), module)
+}
+
+fn apply(levels: &ModuleLevels) -> Result<(), String> {
+    match FILTER.get() {
+        Some(handle) => handle
+            .reload(targets(levels))
+            .map_err(|e| format!("Failed to change log levels: {}", e)),
+        None => Ok(()),
+    }
+}
+
+// Installed before anything logs; levels start at the default until `init`
+pub fn install() {
+    let (filter, handle) = reload::Layer::new(targets(&ModuleLevels::new()));
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .try_init();
+    if installed.is_ok() {
+        let _ = FILTER.set(handle);
+    }
+}
+
+// Applies the active profile's levels; called at startup and on profile switches
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let profile = profiles::active(app)?.name;
+    apply(&load(app)?.remove(&profile).unwrap_or_default())
+}
+
+fn validate(levels: &ModuleLevels) -> Result<(), String> {
+    match levels.keys().find(|module| {
+        module.is_empty()
+            || !module.split("::").all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+    }) {
+        Some(module) => Err(format!("Invalid module name '{}'", module)),
+        None => Ok(()),
+    }
+}
+
+// Defaults to the active profile
+#[tauri::command]
+pub fn get_module_log_levels(
+    app: AppHandle,
+    profile: Option<String>,
+) -> Result<ModuleLevels, String> {
+    let profile = match profile {
+        Some(name) => name,
+        None => profiles::active(&app)?.name,
+    };
+    Ok(load(&app)?.remove(&profile).unwrap_or_default())
+}
+
+// Replaces the profile's levels; they take effect at once when it is the active one
+#[tauri::command]
+pub fn set_module_log_levels(
+    app: AppHandle,
+    levels: ModuleLevels,
+    profile: Option<String>,
+) -> Result<(), String> {
+    validate(&levels)?;
+    let active = profiles::active(&app)?.name;
+    let profile = match profile {
+        Some(name) => profiles::find(&app, &name)?.name,
+        None => active.clone(),
+    };
+    let mut all = load(&app)?;
+    if levels.is_empty() {
+        all.remove(&profile);
+    } else {
+        all.insert(profile.clone(), levels.clone());
+    }
+    journal::write_json(
+        &app,
+        &format!("Edit log levels for {}", profile),
+        &levels_path(&app)?,
+        &all,
+    )?;
+    if profile == active {
+        apply(&levels)?;
+    }
+    Ok(())
+}
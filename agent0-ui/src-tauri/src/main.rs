@@ -1,34 +1,106 @@
 // Tauri main application with system tray
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agent0;
+mod alert_routing;
+mod alerts;
+mod api;
+mod audit;
+mod badge;
+mod bulk;
+mod certs;
+mod commands;
+mod compare;
+mod compat;
+mod completion;
+mod config;
+mod config_import;
+mod costs;
+mod data_dir;
+mod digest;
+mod energy;
+mod events;
+mod explorer;
+mod faults;
+mod firewall;
+mod formatting;
+mod gpu;
+mod gpu_processes;
+mod heartbeat;
+mod heatmap;
+mod history;
+mod install_mode;
+mod journal;
+mod language;
+mod launch;
+mod keychain;
+mod lighting;
+mod lists;
+mod log_levels;
+mod managed;
+mod memory;
+mod metrics;
+mod migrations;
+mod model_diff;
+mod notify;
+mod offline_echo;
+mod openrgb;
+mod panels;
+mod pdf;
+mod placement;
+mod ports;
+mod power_plan;
+mod preflight;
+mod profiles;
+mod progress;
+mod proxy;
+mod release_notes;
+mod remote_store;
+mod remote_sync;
+mod report;
+mod runtime_config;
+mod safe_mode;
+mod schedule;
+mod selftest;
+mod session;
+mod share;
+mod smtp;
+mod snapshot;
+mod spec;
+mod startup;
+mod storage;
+mod store;
+mod sync;
+mod tags;
+mod telemetry;
+mod trash;
+mod uninstall;
+mod vcr;
+
 use tauri::{CustomMenuItem, SystemTray, SystemTrayMenu, Manager, AppHandle, SystemTrayEvent};
 use std::process::Command;
 
 // Custom Tauri commands
 #[tauri::command]
-async fn pause_service() -> Result<String, String> {
+async fn pause_service(app: AppHandle) -> Result<String, String> {
     // Call Agent-0 pause endpoint
-    let client = reqwest::Client::new();
-    match client
-        .post("http://localhost:8000/admin/pause")
-        .send()
-        .await
-    {
-        Ok(_) => Ok("Service paused".to_string()),
+    match api::pause(&agent0::base_url()).await {
+        Ok(_) => {
+            store::set_paused(&app, true);
+            Ok("Service paused".to_string())
+        }
         Err(e) => Err(format!("Failed to pause service: {}", e)),
     }
 }
 
 #[tauri::command]
-async fn resume_service() -> Result<String, String> {
+async fn resume_service(app: AppHandle) -> Result<String, String> {
     // Call Agent-0 resume endpoint
-    let client = reqwest::Client::new();
-    match client
-        .post("http://localhost:8000/admin/resume")
-        .send()
-        .await
-    {
-        Ok(_) => Ok("Service resumed".to_string()),
+    match api::resume(&agent0::base_url()).await {
+        Ok(_) => {
+            store::set_paused(&app, false);
+            Ok("Service resumed".to_string())
+        }
         Err(e) => Err(format!("Failed to resume service: {}", e)),
     }
 }
@@ -36,7 +108,7 @@ async fn resume_service() -> Result<String, String> {
 #[tauri::command]
 async fn open_dashboard() -> Result<String, String> {
     // Open browser to monitoring dashboard
-    if let Err(e) = webbrowser::open("http://localhost:8000/monitor") {
+    if let Err(e) = webbrowser::open(&format!("{}{}", agent0::base_url(), api::paths::MONITOR)) {
         Err(format!("Failed to open dashboard: {}", e))
     } else {
         Ok("Dashboard opened".to_string())
@@ -47,6 +119,7 @@ fn create_system_tray() -> SystemTray {
     let pause = CustomMenuItem::new("pause".to_string(), "Pause Agent-0");
     let resume = CustomMenuItem::new("resume".to_string(), "Resume Agent-0");
     let dashboard = CustomMenuItem::new("dashboard".to_string(), "Open Dashboard");
+    let whats_new = CustomMenuItem::new("whats_new".to_string(), "What's New");
     let separator = CustomMenuItem::new("separator".to_string(), "").disabled();
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
 
@@ -55,6 +128,7 @@ fn create_system_tray() -> SystemTray {
         .add_item(resume)
         .add_item(separator)
         .add_item(dashboard)
+        .add_item(whats_new)
         .add_item(separator)
         .add_item(quit);
 
@@ -74,17 +148,19 @@ fn handle_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
             match id.as_str() {
                 "pause" => {
                     // Call pause command
-                    tauri::async_runtime::spawn(async {
-                        if let Err(e) = pause_service().await {
-                            eprintln!("Failed to pause service: {}", e);
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = pause_service(app).await {
+                            tracing::warn!("Failed to pause service: {}", e);
                         }
                     });
                 }
                 "resume" => {
                     // Call resume command
-                    tauri::async_runtime::spawn(async {
-                        if let Err(e) = resume_service().await {
-                            eprintln!("Failed to resume service: {}", e);
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = resume_service(app).await {
+                            tracing::warn!("Failed to resume service: {}", e);
                         }
                     });
                 }
@@ -92,10 +168,18 @@ fn handle_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
                     // Open dashboard
                     tauri::async_runtime::spawn(async {
                         if let Err(e) = open_dashboard().await {
-                            eprintln!("Failed to open dashboard: {}", e);
+                            tracing::warn!("Failed to open dashboard: {}", e);
                         }
                     });
                 }
+                "whats_new" => {
+                    // Show main window on the release notes view
+                    if let Some(window) = app.get_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                    let _ = app.emit_all("open-release-notes", ());
+                }
                 "quit" => {
                     std::process::exit(0);
                 }
@@ -107,14 +191,294 @@ fn handle_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
 }
 
 fn main() {
+    let startup = startup::StartupProfile::new();
+    log_levels::install();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_actions = match launch::parse(&args) {
+        Ok(actions) => actions,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let context = tauri::generate_context!();
+    // A second launch hands its arguments to the running instance and exits
+    match launch::forward(
+        tauri::api::path::app_data_dir(context.config()).map(|dir| storage::locate(&dir)),
+        &launch_actions,
+    ) {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+    let handler: Box<dyn Fn(tauri::Invoke) + Send + Sync> = Box::new(tauri::generate_handler![
+        pause_service,
+        resume_service,
+        open_dashboard,
+        sync::start_sync_listener,
+        sync::stop_sync_listener,
+        sync::sync_with_peer,
+        sync::sync_with_folder,
+        proxy::start_lan_proxy,
+        proxy::stop_lan_proxy,
+        proxy::get_proxy_log,
+        certs::get_certificate_fingerprint,
+        certs::rotate_certificate,
+        certs::export_certificate_qr,
+        energy::get_energy_report,
+        energy::get_energy_settings,
+        energy::set_energy_settings,
+        power_plan::get_power_plan,
+        power_plan::set_power_plan,
+        power_plan::set_power_override,
+        power_plan::get_power_plan_status,
+        profiles::list_server_profiles,
+        profiles::save_server_profile,
+        profiles::delete_server_profile,
+        profiles::set_active_profile,
+        session::start_session_recording,
+        session::stop_session_recording,
+        session::list_recorded_sessions,
+        session::get_recorded_session,
+        session::start_session_replay,
+        session::advance_session_replay,
+        session::cancel_session_replay,
+        spec::apply_spec,
+        config::get_server_config,
+        config::set_server_config,
+        config::merge_config,
+        heartbeat::register_ui_session,
+        heartbeat::list_attached_uis,
+        badge::get_badge_state,
+        badge::mark_unread,
+        badge::clear_badge,
+        completion::notify_response_complete,
+        completion::get_completion_settings,
+        completion::set_completion_settings,
+        completion::set_conversation_muted,
+        share::share_conversation,
+        share::list_shares,
+        share::revoke_share,
+        model_diff::diff_model_configs,
+        selftest::run_self_test,
+        alerts::get_alert_rules,
+        alerts::set_alert_rules,
+        alerts::get_alert_history,
+        alerts::acknowledge_alerts,
+        alerts::run_remediation,
+        audit::get_audit_log,
+        runtime_config::get_server_flags,
+        runtime_config::set_server_flag,
+        runtime_config::set_server_log_level,
+        gpu_processes::get_gpu_processes,
+        gpu_processes::get_gpu_hog_settings,
+        gpu_processes::set_gpu_hog_settings,
+        gpu_processes::run_pre_training_hook,
+        compat::check_model_compatibility,
+        compat::switch_model,
+        compat::load_adapter,
+        gpu::get_gpu_stats,
+        placement::get_model_device_map,
+        placement::set_model_device_map,
+        compare::compare_metric_windows,
+        trash::move_to_trash,
+        trash::list_trash,
+        trash::restore_from_trash,
+        trash::empty_trash,
+        journal::get_change_journal,
+        journal::undo_last_change,
+        journal::redo_change,
+        config_import::import_server_config,
+        managed::get_launch_config,
+        managed::set_launch_config,
+        managed::set_environment_secret,
+        managed::get_effective_environment,
+        managed::get_managed_server_status,
+        managed::start_managed_server,
+        managed::stop_managed_server,
+        ports::check_port,
+        firewall::check_firewall_status,
+        firewall::add_firewall_rule,
+        firewall::remove_firewall_rule,
+        formatting::get_format_prefs,
+        formatting::set_format_prefs,
+        formatting::format_values,
+        release_notes::get_release_notes,
+        release_notes::get_unseen_release_notes,
+        release_notes::mark_release_notes_seen,
+        release_notes::get_release_notes_settings,
+        release_notes::set_release_notes_settings,
+        startup::get_startup_profile,
+        memory::get_memory_budgets,
+        memory::set_memory_budgets,
+        memory::get_memory_breakdown,
+        panels::get_dynamic_panels,
+        panels::get_panel_settings,
+        panels::set_panel_settings,
+        history::get_command_history,
+        history::rerun_command,
+        faults::inject_failure,
+        faults::list_injected_failures,
+        faults::clear_injected_failures,
+        lists::stream_list,
+        lists::cancel_list_stream,
+        install_mode::get_install_mode,
+        install_mode::set_install_mode,
+        lighting::get_lighting_settings,
+        lighting::set_lighting_settings,
+        lighting::get_lighting_status,
+        smtp::get_smtp_settings,
+        smtp::set_smtp_settings,
+        smtp::send_test_email,
+        report::generate_report,
+        report::get_report_settings,
+        report::set_report_settings,
+        schedule::get_next_fire_times,
+        preflight::run_preflight_checklist,
+        preflight::start_training_run,
+        preflight::get_preflight_settings,
+        preflight::set_preflight_settings,
+        store::get_ui_state,
+        vcr::get_vcr_status,
+        vcr::start_recording,
+        vcr::start_replay,
+        vcr::stop_vcr,
+        language::detect_prompt_language,
+        language::get_language_stats,
+        language::get_language_settings,
+        language::set_language_settings,
+        data_dir::get_data_dir,
+        data_dir::set_data_dir,
+        remote_sync::get_remote_sync_status,
+        remote_sync::set_remote_sync_settings,
+        remote_sync::sync_remote_now,
+        progress::list_operations,
+        progress::cancel_operation,
+        log_levels::get_module_log_levels,
+        log_levels::set_module_log_levels,
+        offline_echo::send_chat,
+        costs::get_conversation_cost,
+        costs::get_persona_costs,
+        costs::get_cost_quotas,
+        costs::set_cost_quotas,
+        safe_mode::get_safe_mode_report,
+        safe_mode::repair_safe_mode_item,
+        safe_mode::restart_after_repair,
+        uninstall::uninstall_cleanup,
+        alert_routing::get_alert_routing,
+        alert_routing::set_alert_routing,
+        heatmap::get_latency_heatmap,
+        tags::get_tags,
+        tags::list_tags,
+        tags::set_user_tags,
+        tags::search_by_tags,
+        bulk::bulk_delete_conversations,
+        bulk::bulk_tag,
+        bulk::bulk_export,
+        explorer::list_api_endpoints,
+        explorer::try_endpoint,
+        snapshot::capture_panel_snapshot,
+        events::get_transport_status,
+        migrations::get_migration_history
+    ]);
+
+    let tray = startup.time("tray_menu", create_system_tray);
+
     tauri::Builder::default()
-        .system_tray(create_system_tray())
+        .manage(startup)
+        .manage(sync::SyncState::default())
+        .manage(proxy::ProxyState::default())
+        .manage(gpu::Gpu::new())
+        .manage(power_plan::PowerPlanState::default())
+        .manage(session::SessionState::default())
+        .manage(badge::BadgeStore::default())
+        .manage(alerts::AlertState::default())
+        .manage(managed::ManagedState::default())
+        .manage(history::HistoryState::default())
+        .manage(faults::FaultState::default())
+        .manage(lists::ListStreams::default())
+        .manage(lighting::LightingState::default())
+        .manage(launch::LaunchState::default())
+        .manage(store::UiStore::default())
+        .manage(progress::Operations::default())
+        .manage(heatmap::HeatmapState::default())
+        .manage(events::EventState::default())
+        .setup(|app| {
+            let profile = app.state::<startup::StartupProfile>();
+            let data_dir = profile.time("data_dir", || storage::data_dir(&app.handle()))?;
+            safe_mode::check(&data_dir);
+            let version = app.package_info().version.to_string();
+            profile.time("migrations", || migrations::run(&data_dir, &version));
+            profile.time("config_load", || {
+                safe_mode::boot(|| {
+                    profiles::init(&app.handle())?;
+                    memory::init(&app.handle())?;
+                    install_mode::init(&app.handle())?;
+                    lighting::init(&app.handle())?;
+                    language::init(&app.handle())
+                })
+            })?;
+            app.manage(metrics::MetricsStore::load(&data_dir));
+            profile.time("background_tasks", || {
+                metrics::start(app.handle());
+                energy::start(app.handle());
+                digest::start(app.handle());
+                power_plan::start(app.handle());
+                heartbeat::start(app.handle());
+                events::start(app.handle());
+                telemetry::start(app.handle());
+                alerts::start(app.handle());
+                runtime_config::start(app.handle());
+                trash::start(app.handle());
+                release_notes::start(app.handle());
+                lighting::start(app.handle());
+                report::start(app.handle());
+                store::start(app.handle());
+                remote_sync::start(app.handle());
+                tags::start();
+            });
+            launch::start(app.handle(), launch_actions)?;
+            startup::start(app.handle());
+            safe_mode::announce(&app.handle());
+            Ok(())
+        })
+        .system_tray(tray)
         .on_system_tray_event(handle_system_tray_event)
-        .invoke_handler(tauri::generate_handler![
-            pause_service,
-            resume_service,
-            open_dashboard
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_page_load(|window, _| launch::on_page_load(&window.app_handle()))
+        .on_window_event(|event| {
+            if let tauri::WindowEvent::Focused(true) = event.event() {
+                badge::on_focus(&event.window().app_handle(), event.window().label());
+            }
+        })
+        .invoke_handler(move |invoke| {
+            let message = &invoke.message;
+            let app = message.window().app_handle();
+            session::observe(&app, message.command(), message.payload());
+            // Registry commands run by name so their outcome lands in the command history;
+            // set_server_config and start_managed_server keep their structured conflict errors
+            if commands::lookup(message.command()).is_some()
+                && !matches!(
+                    message.command(),
+                    "set_server_config" | "start_managed_server"
+                )
+            {
+                let command = message.command().to_string();
+                let args = message.payload().clone();
+                invoke.resolver.respond_async(async move {
+                    history::run(&app, "webview", &command, args)
+                        .await
+                        .map_err(tauri::InvokeError::from)
+                });
+                return;
+            }
+            handler(invoke)
+        })
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // The tray exists once the event loop is running
+            if let tauri::RunEvent::Ready = event {
+                app.state::<startup::StartupProfile>().tray_ready();
+            }
+        });
 } 
\ No newline at end of file
@@ -0,0 +1,529 @@
+// Agent-0 server process launched and owned by the app, configured per profile
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::metrics::now_secs;
+use crate::ports::{self, PortStatus};
+use crate::profiles::{self, ServerProfile};
+use crate::{audit, gpu_processes, history, install_mode, keychain, remote_store, smtp, storage};
+
+// Variable names that are redacted even when given as plain values; also checked for every key
+// of every recorded VCR body, so compiled once
+static SENSITIVE_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(token|secret|password|passwd|api_?key|credential|private)").unwrap()
+});
+static VALID_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+pub const REDACTED: &str = "<redacted>";
+// Launch secrets live under their own keychain prefix, apart from the app's own credentials
+const SECRET_PREFIX: &str = "env/";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum EnvValue {
+    // Resolved from the keychain at spawn time so it never touches the app's files
+    Secret { secret: String },
+    Plain(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct LaunchConfig {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    working_dir: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, EnvValue>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvSource {
+    Inherited,
+    Profile,
+    Secret,
+}
+
+#[derive(Serialize, Clone)]
+pub struct EnvVar {
+    name: String,
+    // Redacted for secrets and sensitive-looking names
+    value: Option<String>,
+    source: EnvSource,
+    // Set when a secret reference could not be resolved
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ManagedStatus {
+    profile: String,
+    pid: u32,
+    port: u16,
+    started_at: i64,
+}
+
+#[derive(Default)]
+pub struct ManagedState {
+    running: Mutex<Option<(ManagedStatus, Child)>>,
+}
+
+fn launch_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("launch.json"))
+}
+
+fn load_launch(app: &AppHandle) -> Result<BTreeMap<String, LaunchConfig>, String> {
+    storage::read_json(&launch_path(app)?)
+}
+
+pub fn sensitive(name: &str) -> bool {
+    SENSITIVE_NAME.is_match(name)
+}
+
+// Arguments as logged: values of sensitive-looking flags, given as "--flag=value" or as the
+// argument after "--flag", are redacted
+fn redact_args(args: &[String]) -> Vec<String> {
+    let mut shown = Vec::with_capacity(args.len());
+    let mut hide_next = false;
+    for arg in args {
+        if hide_next && !arg.starts_with('-') {
+            shown.push(REDACTED.to_string());
+            hide_next = false;
+            continue;
+        }
+        hide_next = false;
+        match arg.split_once('=') {
+            Some((flag, _)) if sensitive(flag) => shown.push(format!("{}={}", flag, REDACTED)),
+            Some(_) => shown.push(arg.clone()),
+            None => {
+                hide_next = arg.starts_with('-') && sensitive(arg);
+                shown.push(arg.clone());
+            }
+        }
+    }
+    shown
+}
+
+// The keychain entry behind a launch secret name; the app's own secret names are refused so
+// they cannot be read into a child process or overwritten from the webview
+fn secret_entry(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Secret names cannot be empty".to_string());
+    }
+    if name == smtp::PASSWORD_SECRET || name == remote_store::SECRET_NAME {
+        return Err(format!("'{}' is reserved for the app's own settings", name));
+    }
+    Ok(format!("{}{}", SECRET_PREFIX, name))
+}
+
+fn validate(config: &LaunchConfig) -> Result<(), String> {
+    if config.program.trim().is_empty() {
+        return Err("The launch command cannot be empty".to_string());
+    }
+    for (name, value) in &config.env {
+        if !VALID_NAME.is_match(name) {
+            return Err(format!("Invalid environment variable name '{}'", name));
+        }
+        match value {
+            EnvValue::Plain(value) if value.contains('\0') => {
+                return Err(format!("{} contains a NUL character", name));
+            }
+            EnvValue::Secret { secret } => {
+                secret_entry(secret).map_err(|e| format!("{}: {}", name, e))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Profile variables with secrets resolved; the redacted view goes to logs and the UI
+fn resolve(config: &LaunchConfig) -> (BTreeMap<String, String>, Vec<EnvVar>) {
+    let mut values = BTreeMap::new();
+    let mut view = Vec::new();
+    for (name, value) in &config.env {
+        let (resolved, source) = match value {
+            EnvValue::Plain(value) => (Ok(value.clone()), EnvSource::Profile),
+            EnvValue::Secret { secret } => (
+                secret_entry(secret)
+                    .and_then(|entry| keychain::get(&entry))
+                    .and_then(|found| {
+                        found.ok_or_else(|| format!("No secret named '{}' in the keychain", secret))
+                    }),
+                EnvSource::Secret,
+            ),
+        };
+        let redact = source == EnvSource::Secret || sensitive(name);
+        view.push(match resolved {
+            Ok(value) => {
+                let shown = if redact {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                values.insert(name.clone(), value);
+                EnvVar {
+                    name: name.clone(),
+                    value: Some(shown),
+                    source,
+                    error: None,
+                }
+            }
+            Err(e) => EnvVar {
+                name: name.clone(),
+                value: None,
+                source,
+                error: Some(e),
+            },
+        });
+    }
+    (values, view)
+}
+
+// Keychain entries referenced by any profile's launch environment
+pub fn secret_names(app: &AppHandle) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = load_launch(app)?
+        .values()
+        .flat_map(|config| config.env.values())
+        .filter_map(|value| match value {
+            EnvValue::Secret { secret } => secret_entry(secret).ok(),
+            EnvValue::Plain(_) => None,
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+fn profile_config(app: &AppHandle, profile: &str) -> Result<LaunchConfig, String> {
+    load_launch(app)?
+        .remove(profile)
+        .ok_or_else(|| format!("Profile '{}' has no launch command", profile))
+}
+
+#[tauri::command]
+pub fn get_launch_config(app: AppHandle, profile: String) -> Result<Option<LaunchConfig>, String> {
+    Ok(load_launch(&app)?.remove(&profile))
+}
+
+#[tauri::command]
+pub fn set_launch_config(
+    app: AppHandle,
+    profile: String,
+    config: Option<LaunchConfig>,
+) -> Result<(), String> {
+    profiles::find(&app, &profile)?;
+    let mut all = load_launch(&app)?;
+    match config {
+        Some(config) => {
+            validate(&config)?;
+            all.insert(profile, config);
+        }
+        None => {
+            all.remove(&profile);
+        }
+    }
+    storage::write_json(&launch_path(&app)?, &all)
+}
+
+#[tauri::command]
+pub fn set_environment_secret(name: String, value: Option<String>) -> Result<(), String> {
+    let entry = secret_entry(&name)?;
+    match value {
+        Some(value) => keychain::set(&entry, &value),
+        None => keychain::delete(&entry),
+    }
+}
+
+// What the process would see if started now: inherited variables overlaid by the profile's
+#[tauri::command]
+pub fn get_effective_environment(
+    app: AppHandle,
+    profile: Option<String>,
+) -> Result<Vec<EnvVar>, String> {
+    let profile = match profile {
+        Some(name) => name,
+        None => profiles::active(&app)?.name,
+    };
+    let (_, overrides) = resolve(&profile_config(&app, &profile)?);
+    let mut vars: Vec<EnvVar> = std::env::vars()
+        .filter(|(name, _)| !overrides.iter().any(|o| o.name == *name))
+        .map(|(name, value)| EnvVar {
+            value: Some(if sensitive(&name) {
+                REDACTED.to_string()
+            } else {
+                value
+            }),
+            name,
+            source: EnvSource::Inherited,
+            error: None,
+        })
+        .collect();
+    vars.extend(overrides);
+    vars.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(vars)
+}
+
+#[tauri::command]
+pub fn get_managed_server_status(state: State<'_, ManagedState>) -> Option<ManagedStatus> {
+    let mut running = state.running.lock().unwrap();
+    // Forget a process that exited on its own
+    let exited = match running.as_mut() {
+        Some((_, child)) => child.try_wait().map_or(true, |status| status.is_some()),
+        None => false,
+    };
+    if exited {
+        *running = None;
+    }
+    running.as_ref().map(|(status, _)| status.clone())
+}
+
+// The process this app started, so cleanup hooks never mistake it for a stranger
+pub fn running_pid(app: &AppHandle) -> Option<u32> {
+    get_managed_server_status(app.state::<ManagedState>()).map(|status| status.pid)
+}
+
+// What to do when the profile's port is already taken
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PortConflict {
+    // Stop the process holding the port, then start as usual
+    Kill,
+    // Treat the listener as this profile's Agent-0 and start nothing
+    Reuse,
+    // Start on the next free port and point the profile at it
+    NextFree,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum StartError {
+    PortInUse {
+        status: PortStatus,
+        suggested_port: Option<u16>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for StartError {
+    fn from(message: String) -> Self {
+        StartError::Failed { message }
+    }
+}
+
+// For the command history and callers outside the webview, which have no conflict dialog
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::PortInUse {
+                status,
+                suggested_port: Some(next),
+            } => write!(f, "Port {} is in use; {} is free", status.port(), next),
+            StartError::PortInUse { status, .. } => write!(f, "Port {} is in use", status.port()),
+            StartError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+fn profile_port(profile: &ServerProfile) -> Result<(reqwest::Url, u16), String> {
+    let url = reqwest::Url::parse(&profile.base_url)
+        .map_err(|e| format!("Invalid base URL '{}': {}", profile.base_url, e))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("No port in {}", profile.base_url))?;
+    Ok((url, port))
+}
+
+// Async, so the wait for a killed owner to let go does not block a runtime thread
+async fn wait_until_free(port: u16) -> bool {
+    for _ in 0..30 {
+        if ports::is_free(port) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    false
+}
+
+// Returns the port to launch on, or None when an existing listener is reused
+async fn negotiate_port(
+    app: &AppHandle,
+    profile: &ServerProfile,
+    on_conflict: Option<PortConflict>,
+) -> Result<Option<u16>, StartError> {
+    let (mut url, port) = profile_port(profile)?;
+    let status = ports::status(port);
+    if status.is_free() {
+        return Ok(Some(port));
+    }
+    match on_conflict {
+        None => Err(StartError::PortInUse {
+            suggested_port: ports::next_free(port),
+            status,
+        }),
+        Some(PortConflict::Reuse) => Ok(None),
+        Some(PortConflict::Kill) => {
+            let owner = status.owner().ok_or_else(|| {
+                format!("Port {} is taken by a process this user cannot see", port)
+            })?;
+            let result = gpu_processes::kill(owner.pid());
+            audit::record(
+                app,
+                "port_conflict",
+                "kill_port_owner",
+                &json!({ "pid": owner.pid(), "port": port }),
+                &result.clone().map(|_| Value::Null),
+            );
+            result?;
+            if !wait_until_free(port).await {
+                return Err(
+                    format!("Port {} is still in use after stopping its owner", port).into(),
+                );
+            }
+            Ok(Some(port))
+        }
+        Some(PortConflict::NextFree) => {
+            let free = ports::next_free(port)
+                .ok_or_else(|| format!("No free port found above {}", port))?;
+            url.set_port(Some(free))
+                .map_err(|_| format!("Cannot set a port on {}", profile.base_url))?;
+            profiles::save_server_profile(
+                app.clone(),
+                ServerProfile {
+                    name: profile.name.clone(),
+                    base_url: url.as_str().trim_end_matches('/').to_string(),
+                },
+            )?;
+            Ok(Some(free))
+        }
+    }
+}
+
+// "{port}" in arguments and plain variables is replaced with the negotiated port
+fn with_port(value: &str, port: u16) -> String {
+    value.replace("{port}", &port.to_string())
+}
+
+// Ok(None) means an already-running server was adopted instead of starting a new one
+pub async fn start(
+    app: &AppHandle,
+    on_conflict: Option<PortConflict>,
+) -> Result<Option<ManagedStatus>, StartError> {
+    install_mode::require_local("Launching a local Agent-0")?;
+    if let Some(status) = get_managed_server_status(app.state::<ManagedState>()) {
+        return Err(format!("Agent-0 is already running (pid {})", status.pid).into());
+    }
+    let profile = profiles::active(app)?;
+    let config = profile_config(app, &profile.name)?;
+    validate(&config)?;
+    let (mut env, view) = resolve(&config);
+    let unresolved: Vec<String> = view.iter().filter_map(|v| v.error.clone()).collect();
+    if !unresolved.is_empty() {
+        return Err(format!("Cannot start Agent-0: {}", unresolved.join("; ")).into());
+    }
+    let port = match negotiate_port(app, &profile, on_conflict).await? {
+        Some(port) => port,
+        None => return Ok(None),
+    };
+    for (name, value) in env.iter_mut() {
+        if let Some(EnvValue::Plain(_)) = config.env.get(name) {
+            *value = with_port(value, port);
+        }
+    }
+    env.insert("AGENT0_PORT".to_string(), port.to_string());
+    let args: Vec<String> = config.args.iter().map(|a| with_port(a, port)).collect();
+    let summary: Vec<String> = view
+        .iter()
+        .map(|v| format!("{}={}", v.name, v.value.as_deref().unwrap_or("")))
+        .collect();
+    tracing::info!(
+        "Starting Agent-0 for profile {} on port {}: {} {} [{}]",
+        profile.name,
+        port,
+        config.program,
+        redact_args(&args).join(" "),
+        summary.join(", ")
+    );
+
+    let log_path = storage::data_dir(app)?.join("agent0-server.log");
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .map_err(|e| format!("Failed to open {}: {}", log_path.display(), e))?;
+    let log_err = log
+        .try_clone()
+        .map_err(|e| format!("Failed to open {}: {}", log_path.display(), e))?;
+    let mut command = Command::new(&config.program);
+    command
+        .args(&args)
+        .envs(&env)
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err);
+    if let Some(dir) = &config.working_dir {
+        command.current_dir(dir);
+    }
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", config.program, e))?;
+    let status = ManagedStatus {
+        profile: profile.name,
+        pid: child.id(),
+        port,
+        started_at: now_secs(),
+    };
+    *app.state::<ManagedState>().running.lock().unwrap() = Some((status.clone(), child));
+    let _ = app.emit_all("managed-server-started", &status);
+    Ok(Some(status))
+}
+
+// Called directly rather than through the registry so the webview keeps the structured port
+// conflict, and recorded in the command history all the same
+#[tauri::command]
+pub async fn start_managed_server(
+    app: AppHandle,
+    on_conflict: Option<PortConflict>,
+) -> Result<Option<ManagedStatus>, StartError> {
+    let started_at = now_secs();
+    let started = Instant::now();
+    let result = start(&app, on_conflict).await;
+    history::record(
+        &app,
+        "webview",
+        "start_managed_server",
+        json!({ "onConflict": on_conflict }),
+        started_at,
+        started,
+        &result
+            .as_ref()
+            .map(|status| json!(status))
+            .map_err(StartError::to_string),
+    );
+    result
+}
+
+#[tauri::command]
+pub fn stop_managed_server(app: AppHandle, state: State<'_, ManagedState>) -> Result<(), String> {
+    let taken = state.running.lock().unwrap().take();
+    if let Some((status, mut child)) = taken {
+        child
+            .kill()
+            .map_err(|e| format!("Failed to stop Agent-0 (pid {}): {}", status.pid, e))?;
+        let _ = child.wait();
+        let _ = app.emit_all("managed-server-stopped", &status);
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+// Memory budgets for in-memory caches and a breakdown of what each one holds
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+use crate::alerts::AlertState;
+use crate::metrics::MetricsStore;
+use crate::proxy::ProxyState;
+use crate::{journal, storage};
+
+// Smallest budget accepted, so a typo cannot empty a cache on every insert
+const MIN_BUDGET_BYTES: usize = 64 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MemoryBudgets {
+    // Proxy request log
+    pub log_buffer_bytes: usize,
+    // Sampled metric series held between flushes
+    pub metrics_cache_bytes: usize,
+    // Alert history kept for the UI and remediation
+    pub event_queue_bytes: usize,
+}
+
+impl Default for MemoryBudgets {
+    fn default() -> Self {
+        MemoryBudgets {
+            log_buffer_bytes: 2 * 1024 * 1024,
+            metrics_cache_bytes: 32 * 1024 * 1024,
+            event_queue_bytes: 1024 * 1024,
+        }
+    }
+}
+
+// Read on every insert, so kept in memory rather than re-read from disk
+static BUDGETS: Lazy<RwLock<MemoryBudgets>> = Lazy::new(|| RwLock::new(MemoryBudgets::default()));
+
+#[derive(Serialize)]
+pub struct CacheUsage {
+    name: &'static str,
+    entries: usize,
+    approx_bytes: usize,
+    budget_bytes: usize,
+}
+
+#[derive(Serialize)]
+pub struct MemoryBreakdown {
+    caches: Vec<CacheUsage>,
+    // Whole-process figure from the OS; None where it is not exposed
+    resident_bytes: Option<u64>,
+}
+
+pub fn budgets() -> MemoryBudgets {
+    *BUDGETS.read().unwrap()
+}
+
+// Drops the oldest entries until the estimated size fits
+pub fn trim_to_budget<T>(queue: &mut VecDeque<T>, budget: usize, size: impl Fn(&T) -> usize) {
+    let mut total: usize = queue.iter().map(&size).sum();
+    while total > budget {
+        match queue.pop_front() {
+            Some(evicted) => total -= size(&evicted),
+            None => break,
+        }
+    }
+}
+
+fn budgets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("memory.json"))
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    *BUDGETS.write().unwrap() = storage::read_json(&budgets_path(app)?)?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_bytes() -> Option<u64> {
+    None
+}
+
+#[tauri::command]
+pub fn get_memory_budgets() -> MemoryBudgets {
+    budgets()
+}
+
+#[tauri::command]
+pub fn set_memory_budgets(app: AppHandle, budgets: MemoryBudgets) -> Result<(), String> {
+    let smallest = budgets
+        .log_buffer_bytes
+        .min(budgets.metrics_cache_bytes)
+        .min(budgets.event_queue_bytes);
+    if smallest < MIN_BUDGET_BYTES {
+        return Err(format!(
+            "Memory budgets must be at least {} bytes",
+            MIN_BUDGET_BYTES
+        ));
+    }
+    journal::write_json(&app, "Edit memory budgets", &budgets_path(&app)?, &budgets)?;
+    *BUDGETS.write().unwrap() = budgets;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_memory_breakdown(app: AppHandle) -> MemoryBreakdown {
+    let budgets = budgets();
+    let (log_entries, log_bytes) = app.state::<ProxyState>().log_usage();
+    let (metric_samples, metric_bytes) = app.state::<MetricsStore>().usage();
+    let (alert_events, alert_bytes) = app.state::<AlertState>().history_usage();
+    MemoryBreakdown {
+        caches: vec![
+            CacheUsage {
+                name: "log_buffer",
+                entries: log_entries,
+                approx_bytes: log_bytes,
+                budget_bytes: budgets.log_buffer_bytes,
+            },
+            CacheUsage {
+                name: "metrics_cache",
+                entries: metric_samples,
+                approx_bytes: metric_bytes,
+                budget_bytes: budgets.metrics_cache_bytes,
+            },
+            CacheUsage {
+                name: "event_queue",
+                entries: alert_events,
+                approx_bytes: alert_bytes,
+                budget_bytes: budgets.event_queue_bytes,
+            },
+        ],
+        resident_bytes: resident_bytes(),
+    }
+}
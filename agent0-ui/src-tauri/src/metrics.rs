@@ -0,0 +1,224 @@
+// Local time-series store for sampled metrics
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::{memory, safe_mode, storage};
+
+const RETENTION_SECS: i64 = 35 * 86400;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Sample {
+    pub ts: i64,
+    pub value: f64,
+}
+
+// Inclusive range of unix timestamps in seconds
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct TimeRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+type Series = HashMap<String, VecDeque<Sample>>;
+
+// Injected failures are stored beside the real series, so only alert rules see them
+pub fn synthetic_series(name: &str) -> String {
+    format!("synthetic:{}", name)
+}
+
+const SAMPLE_BYTES: usize = std::mem::size_of::<Sample>();
+
+// Read from disk on first use; a month of samples is too slow to parse before the tray shows
+pub struct MetricsStore {
+    path: RwLock<PathBuf>,
+    series: OnceCell<Mutex<Series>>,
+    // Series never read since startup sort first and are evicted first
+    last_read: Mutex<HashMap<String, Instant>>,
+}
+
+fn series_bytes(series: &Series) -> usize {
+    series
+        .iter()
+        .map(|(name, samples)| name.len() + samples.len() * SAMPLE_BYTES)
+        .sum()
+}
+
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// Ranges and retention rely on each series being in time order
+fn check_order(series: &Series) -> Result<(), String> {
+    match series.iter().find(|(_, samples)| {
+        samples
+            .iter()
+            .zip(samples.iter().skip(1))
+            .any(|(a, b)| b.ts < a.ts)
+    }) {
+        Some((name, _)) => Err(format!("Series {} is out of time order", name)),
+        None => Ok(()),
+    }
+}
+
+impl MetricsStore {
+    pub fn load(dir: &Path) -> Self {
+        MetricsStore {
+            path: RwLock::new(dir.join("metrics.json")),
+            series: OnceCell::new(),
+            last_read: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn series(&self) -> &Mutex<Series> {
+        self.series.get_or_init(|| {
+            let path = self.path();
+            let loaded = storage::read_json(&path).and_then(|series: Series| {
+                check_order(&series)?;
+                Ok(series)
+            });
+            // Safe mode keeps the file from being overwritten until it is repaired
+            Mutex::new(loaded.unwrap_or_else(|e| {
+                safe_mode::report_corrupt(&path, &e);
+                HashMap::new()
+            }))
+        })
+    }
+
+    pub fn preload(&self) {
+        self.series();
+    }
+
+    pub fn record(&self, name: &str, value: f64) {
+        self.record_sample(name, value);
+    }
+
+    // Same as `record`, returning the stored sample for callers that also aggregate it
+    pub fn record_sample(&self, name: &str, value: f64) -> Sample {
+        let sample = Sample {
+            ts: now_secs(),
+            value,
+        };
+        let mut series = self.series().lock().unwrap();
+        let samples = series.entry(name.to_string()).or_default();
+        samples.push_back(sample);
+        let cutoff = sample.ts - RETENTION_SECS;
+        while samples.front().map_or(false, |s| s.ts < cutoff) {
+            samples.pop_front();
+        }
+        self.evict(&mut series, memory::budgets().metrics_cache_bytes);
+        sample
+    }
+
+    // Oldest samples of the least recently read series go first
+    fn evict(&self, series: &mut Series, budget: usize) {
+        let mut total = series_bytes(series);
+        if total <= budget {
+            return;
+        }
+        let last_read = self.last_read.lock().unwrap();
+        let mut order: Vec<String> = series.keys().cloned().collect();
+        order.sort_by_key(|name| last_read.get(name).copied());
+        for name in order {
+            if let Some(samples) = series.get_mut(&name) {
+                while total > budget && samples.pop_front().is_some() {
+                    total -= SAMPLE_BYTES;
+                }
+                if samples.is_empty() {
+                    series.remove(&name);
+                    total -= name.len();
+                }
+            }
+            if total <= budget {
+                break;
+            }
+        }
+    }
+
+    // Sample count and approximate bytes, without forcing a load
+    pub fn usage(&self) -> (usize, usize) {
+        match self.series.get() {
+            Some(series) => {
+                let series = series.lock().unwrap();
+                (
+                    series.values().map(|s| s.len()).sum(),
+                    series_bytes(&series),
+                )
+            }
+            None => (0, 0),
+        }
+    }
+
+    pub fn range(&self, name: &str, range: TimeRange) -> Vec<Sample> {
+        self.last_read
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), Instant::now());
+        let series = self.series().lock().unwrap();
+        series
+            .get(name)
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|s| s.ts >= range.from && s.ts <= range.to)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Real and injected samples in time order, for rehearsing alert rules
+    pub fn range_with_synthetic(&self, name: &str, range: TimeRange) -> Vec<Sample> {
+        let mut samples = self.range(name, range);
+        samples.extend(self.range(&synthetic_series(name), range));
+        samples.sort_by_key(|s| s.ts);
+        samples
+    }
+
+    // Timestamp of the oldest retained sample, after retention and eviction
+    pub fn oldest(&self, name: &str) -> Option<i64> {
+        let series = self.series().lock().unwrap();
+        series.get(name)?.front().map(|s| s.ts)
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        // Nothing was loaded, so there is nothing newer than the file
+        let series = match self.series.get() {
+            Some(series) => series,
+            None => return Ok(()),
+        };
+        let snapshot = series.lock().unwrap().clone();
+        storage::write_json(&self.path(), &snapshot)
+    }
+
+    fn path(&self) -> PathBuf {
+        self.path.read().unwrap().clone()
+    }
+
+    // After the data directory moved; the copy there is already up to date
+    pub fn relocate(&self, dir: &Path) {
+        *self.path.write().unwrap() = dir.join("metrics.json");
+    }
+}
+
+// Persist periodically; samples recorded since the last flush are lost on a crash
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = app.state::<MetricsStore>().flush() {
+                tracing::warn!("Failed to persist metrics: {}", e);
+            }
+        }
+    });
+}
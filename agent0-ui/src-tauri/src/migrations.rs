@@ -0,0 +1,235 @@
+// Versioned migrations of the data directory, run at startup behind a backup; data from a newer
+// build is left untouched in safe mode
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::audit::{self, AUDIT_FILE};
+use crate::data_dir;
+use crate::metrics::now_secs;
+use crate::{safe_mode, storage};
+
+const SCHEMA_FILE: &str = "schema.json";
+const BACKUP_DIR: &str = "backups";
+// Older backups are removed once a migration succeeds
+const MAX_BACKUPS: usize = 5;
+
+struct Migration {
+    // The schema version the data is at once this has run
+    version: u32,
+    name: &'static str,
+    run: fn(&Path) -> Result<(), String>,
+}
+
+// Append only; a released migration is never edited or reordered
+static MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "audit_entry_ids",
+    run: audit_entry_ids,
+}];
+
+fn current_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MigrationRecord {
+    version: u32,
+    name: String,
+    at: i64,
+    app_version: String,
+    // Relative to the data directory
+    backup: Option<String>,
+    // Set when it failed and the backup was put back
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SchemaState {
+    // 0 for data written before migrations existed
+    version: u32,
+    history: Vec<MigrationRecord>,
+}
+
+#[derive(Serialize)]
+pub struct MigrationStatus {
+    version: u32,
+    // Highest version this build knows
+    supported: u32,
+    // The data is newer than this build, so nothing is being saved
+    downgraded: bool,
+    history: Vec<MigrationRecord>,
+}
+
+// Audit entries from before tagging have no id, so they could not be tagged
+fn audit_entry_ids(dir: &Path) -> Result<(), String> {
+    let path = dir.join(AUDIT_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let mut lines = Vec::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        // A line torn by a crash is kept as it is; the audit log reader already skips it
+        let mut entry: Value = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(_) => {
+                lines.push(line.to_string());
+                continue;
+            }
+        };
+        if entry["id"].as_str().map_or(true, str::is_empty) {
+            entry["id"] = Value::from(audit::new_entry_id(entry["ts"].as_i64().unwrap_or(0)));
+        }
+        lines.push(entry.to_string());
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+// Everything but earlier backups, relative to `dir`
+fn data_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(data_dir::files(dir)?
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| !path.starts_with(BACKUP_DIR))
+        .collect())
+}
+
+fn copy_all(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), String> {
+    for relative in files {
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(from.join(relative), &target)
+            .map_err(|e| format!("Failed to copy {}: {}", relative.display(), e))?;
+    }
+    Ok(())
+}
+
+// Named "<unix secs>-v<version>" so they sort oldest first
+fn back_up(dir: &Path, version: u32) -> Result<PathBuf, String> {
+    let relative = Path::new(BACKUP_DIR).join(format!("{}-v{}", now_secs(), version));
+    copy_all(dir, &dir.join(&relative), &data_files(dir)?)?;
+    Ok(relative)
+}
+
+// Files a failed migration created are left, since the version it targets never sees them
+fn restore(dir: &Path, backup: &Path) -> Result<(), String> {
+    let source = dir.join(backup);
+    let files = data_dir::files(&source)?
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    copy_all(&source, dir, &files)
+}
+
+fn prune_backups(dir: &Path) {
+    let mut backups: Vec<PathBuf> = match fs::read_dir(dir.join(BACKUP_DIR)) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(_) => return,
+    };
+    backups.sort();
+    let excess = backups.len().saturating_sub(MAX_BACKUPS);
+    for old in &backups[..excess] {
+        if let Err(e) = fs::remove_dir_all(old) {
+            tracing::warn!("Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+}
+
+fn migrate(dir: &Path, app_version: &str) -> Result<(), String> {
+    let path = dir.join(SCHEMA_FILE);
+    let mut state: SchemaState = storage::read_json(&path)?;
+    if state.version > current_version() {
+        return Err(format!(
+            "The data directory is at schema version {}, newer than the {} this version of \
+             Agent-0 understands; nothing will be saved so a newer version can still read it",
+            state.version,
+            current_version()
+        ));
+    }
+    if state.version == current_version() {
+        return Ok(());
+    }
+    // A new data directory starts at the current version with nothing to convert
+    if !path.exists() && data_files(dir)?.is_empty() {
+        state.version = current_version();
+        return storage::write_json(&path, &state);
+    }
+
+    let backup = back_up(dir, state.version)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > state.version) {
+        tracing::info!(
+            "Running migration {} ({})",
+            migration.version,
+            migration.name
+        );
+        let result = (migration.run)(dir);
+        let mut record = MigrationRecord {
+            version: migration.version,
+            name: migration.name.to_string(),
+            at: now_secs(),
+            app_version: app_version.to_string(),
+            backup: Some(backup.display().to_string()),
+            error: None,
+        };
+        if let Err(e) = result {
+            restore(dir, &backup)?;
+            // Data from before migrations has no schema file to bring back, so drop the one
+            // the earlier steps of this run wrote
+            if !dir.join(&backup).join(SCHEMA_FILE).exists() {
+                let _ = fs::remove_file(&path);
+            }
+            let mut restored: SchemaState = storage::read_json(&path)?;
+            record.error = Some(e.clone());
+            restored.history.push(record);
+            storage::write_json(&path, &restored)?;
+            return Err(format!(
+                "Migration {} ({}) failed and the data directory was restored from {}: {}",
+                migration.version,
+                migration.name,
+                backup.display(),
+                e
+            ));
+        }
+        state.version = migration.version;
+        state.history.push(record);
+        // Saved after each step, so an interrupted run resumes where it stopped
+        storage::write_json(&path, &state)?;
+    }
+    prune_backups(dir);
+    Ok(())
+}
+
+// Before any settings are read; a failure starts the app in safe mode
+pub fn run(dir: &Path, app_version: &str) {
+    if safe_mode::active() {
+        tracing::warn!("Migrations skipped until the files safe mode lists are repaired");
+        return;
+    }
+    if let Err(e) = migrate(dir, app_version) {
+        tracing::warn!("{}", e);
+        safe_mode::enter(e);
+    }
+}
+
+#[tauri::command]
+pub fn get_migration_history(app: AppHandle) -> Result<MigrationStatus, String> {
+    let state: SchemaState = storage::read_json(&storage::data_dir(&app)?.join(SCHEMA_FILE))?;
+    Ok(MigrationStatus {
+        version: state.version,
+        supported: current_version(),
+        downgraded: state.version > current_version(),
+        history: state.history,
+    })
+}
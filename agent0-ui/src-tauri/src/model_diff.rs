@@ -0,0 +1,151 @@
+// Structured diff of model and adapter configs shown before switching
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use tauri::AppHandle;
+
+use crate::{agent0, api, storage};
+
+const ADAPTER_PREFIX: &str = "adapter:";
+
+// Keys whose change users most need to see, keyed by leaf name
+const HIGHLIGHTED: [(&str, &str); 12] = [
+    ("context_length", "capability"),
+    ("max_position_embeddings", "capability"),
+    ("quantization", "capability"),
+    ("dtype", "capability"),
+    ("modalities", "capability"),
+    ("tools", "capability"),
+    ("temperature", "generation"),
+    ("top_p", "generation"),
+    ("top_k", "generation"),
+    ("max_tokens", "generation"),
+    ("repetition_penalty", "generation"),
+    ("stop", "generation"),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize)]
+pub struct ConfigChange {
+    key: String,
+    category: String,
+    highlighted: bool,
+    kind: ChangeKind,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigDiff {
+    a: String,
+    b: String,
+    changes: Vec<ConfigChange>,
+    unchanged: usize,
+}
+
+pub fn read_adapter_config(app: &AppHandle, adapter: &str) -> Result<Value, String> {
+    if adapter.is_empty() || adapter.contains(|c| c == '/' || c == '\\') || adapter.starts_with('.')
+    {
+        return Err(format!("Invalid adapter name '{}'", adapter));
+    }
+    let path = storage::data_dir(app)?
+        .join("adapters")
+        .join(adapter)
+        .join("adapter_config.json");
+    if !path.exists() {
+        return Err(format!("Adapter '{}' has no adapter_config.json", adapter));
+    }
+    storage::read_json(&path)
+}
+
+// "adapter:<name>" reads the local adapter config, anything else is a server model
+async fn load(app: &AppHandle, name: &str) -> Result<Value, String> {
+    if let Some(adapter) = name.strip_prefix(ADAPTER_PREFIX) {
+        return read_adapter_config(app, adapter);
+    }
+    api::get_model_config(&agent0::base_url(), name).await
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, child, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn categorize(key: &str) -> (String, bool) {
+    let leaf = key.rsplit('.').next().unwrap_or(key);
+    match HIGHLIGHTED.iter().find(|(name, _)| *name == leaf) {
+        Some((_, category)) => (category.to_string(), true),
+        None if key.starts_with("generation") => ("generation".to_string(), false),
+        None => ("other".to_string(), false),
+    }
+}
+
+fn diff(a: &Value, b: &Value) -> (Vec<ConfigChange>, usize) {
+    let (mut before, mut after) = (BTreeMap::new(), BTreeMap::new());
+    flatten("", a, &mut before);
+    flatten("", b, &mut after);
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for key in keys {
+        let (old, new) = (before.get(key).cloned(), after.get(key).cloned());
+        let kind = match (&old, &new) {
+            (Some(x), Some(y)) if x == y => {
+                unchanged += 1;
+                continue;
+            }
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (None, Some(_)) => ChangeKind::Added,
+            _ => ChangeKind::Removed,
+        };
+        let (category, highlighted) = categorize(key);
+        changes.push(ConfigChange {
+            key: key.clone(),
+            category,
+            highlighted,
+            kind,
+            before: old,
+            after: new,
+        });
+    }
+    // Highlighted changes first, otherwise alphabetical
+    changes.sort_by(|x, y| y.highlighted.cmp(&x.highlighted).then(x.key.cmp(&y.key)));
+    (changes, unchanged)
+}
+
+#[tauri::command]
+pub async fn diff_model_configs(
+    app: AppHandle,
+    a: String,
+    b: String,
+) -> Result<ConfigDiff, String> {
+    let before = load(&app, &a).await?;
+    let after = load(&app, &b).await?;
+    let (changes, unchanged) = diff(&before, &after);
+    Ok(ConfigDiff {
+        a,
+        b,
+        changes,
+        unchanged,
+    })
+}
@@ -0,0 +1,49 @@
+// OS notifications with clickable actions where the platform reports them back
+use tauri::AppHandle;
+
+// Only the freedesktop backend reports which action was clicked; elsewhere the
+// notification is shown without buttons and the callback never runs
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn show_with_actions<F>(
+    app: &AppHandle,
+    title: String,
+    body: String,
+    actions: Vec<(String, String)>,
+    on_action: F,
+) where
+    F: FnOnce(&str) + Send + 'static,
+{
+    let appname = app.package_info().name.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut notification = notify_rust::Notification::new();
+        notification.appname(&appname).summary(&title).body(&body);
+        for (id, label) in &actions {
+            notification.action(id, label);
+        }
+        match notification.show() {
+            Ok(handle) => handle.wait_for_action(on_action),
+            Err(e) => tracing::warn!("Failed to show notification: {}", e),
+        }
+    });
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn show_with_actions<F>(
+    app: &AppHandle,
+    title: String,
+    body: String,
+    _actions: Vec<(String, String)>,
+    _on_action: F,
+) where
+    F: FnOnce(&str) + Send + 'static,
+{
+    use tauri::api::notification::Notification;
+
+    if let Err(e) = Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(body)
+        .show()
+    {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,130 @@
+// Canned council replies for demo builds when no Agent-0 server is reachable
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::now_secs;
+use crate::{agent0, api, costs, language, tags};
+
+pub const ENABLED: bool = cfg!(feature = "offline-echo");
+// Stands in for real model names so offline answers are never mistaken for them
+const MODEL: &str = "offline-echo";
+
+struct Member {
+    voice: &'static str,
+    // `{}` is replaced with the prompt's topic
+    replies: [&'static str; 3],
+}
+
+const COUNCIL: [Member; 3] = [
+    Member {
+        voice: "analyst",
+        replies: [
+            "Breaking \"{}\" down: start with what you can measure, then decide what to change.",
+            "On \"{}\", the data we would want first is a baseline; everything else builds on it.",
+            "\"{}\" has three parts worth separating: the goal, the constraint, and the cost.",
+        ],
+    },
+    Member {
+        voice: "skeptic",
+        replies: [
+            "Before committing to \"{}\", ask what would have to be true for it to fail.",
+            "I'd push back on \"{}\" until we know the simplest alternative doesn't already work.",
+            "\"{}\" sounds reasonable, but the edge cases are where this usually goes wrong.",
+        ],
+    },
+    Member {
+        voice: "builder",
+        replies: [
+            "For \"{}\", I'd ship the smallest version this week and iterate from real feedback.",
+            "A practical first step on \"{}\": write down the acceptance check, then build to it.",
+            "\"{}\" is doable; the quickest path is to reuse what already exists and fill the gaps.",
+        ],
+    },
+];
+
+#[derive(Serialize, Clone)]
+struct OfflineReply {
+    session_id: String,
+    at: i64,
+}
+
+// The first line of the prompt, shortened to keep the canned sentences readable
+fn topic(prompt: &str) -> String {
+    let line = prompt.trim().lines().next().unwrap_or("").trim();
+    match line.char_indices().nth(60) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+// Same prompt, same answer, so rehearsed demos stay predictable
+fn reply(prompt: &str, session_id: &str) -> Value {
+    let seed = Sha256::digest(prompt.as_bytes());
+    let topic = topic(prompt);
+    let voices: Vec<Value> = COUNCIL
+        .iter()
+        .zip(seed.iter())
+        .map(|(member, byte)| {
+            let text = member.replies[*byte as usize % member.replies.len()].replace("{}", &topic);
+            json!({
+                "voice": member.voice,
+                "reply": text,
+                "tokens": text.split_whitespace().count(),
+                "cost": 0.0,
+                "confidence": 0.0,
+                "model": MODEL,
+            })
+        })
+        .collect();
+    let text = voices
+        .iter()
+        .filter_map(|v| v["reply"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    json!({
+        "text": format!("[Offline demo] {}", text),
+        "voices": voices,
+        "cost_usd": 0.0,
+        "model_chain": [MODEL],
+        "session_id": session_id,
+        "offline": true,
+    })
+}
+
+// Falls back to the local council only when the server itself is down, not on errors it returns
+#[tauri::command]
+pub async fn send_chat(
+    app: AppHandle,
+    prompt: String,
+    session_id: String,
+    persona: Option<String>,
+) -> Result<Value, String> {
+    let detection = language::detect(&prompt);
+    let routed = detection.as_ref().and_then(|d| d.persona.as_deref());
+    let persona = costs::persona(persona.as_deref(), routed);
+    costs::check_quota(&app, &persona)?;
+    let hints = detection.map(|d| d.hints).unwrap_or_default();
+    let base = agent0::base_url();
+    let body = json!({ "prompt": prompt, "session_id": session_id, "persona": persona });
+    let error = match api::chat(&base, &body, &hints).await {
+        Ok(response) => {
+            costs::attribute(&app, Some(&session_id), &persona, &response);
+            tags::tag_chat(&app, Some(&session_id), &persona, &response);
+            return Ok(response);
+        }
+        Err(e) => e,
+    };
+    if !ENABLED || api::health(&base).await.is_ok() {
+        return Err(error);
+    }
+    let _ = app.emit_all(
+        "chat-offline-reply",
+        OfflineReply {
+            session_id: session_id.clone(),
+            at: now_secs(),
+        },
+    );
+    Ok(reply(&prompt, &session_id))
+}
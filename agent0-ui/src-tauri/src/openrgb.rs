@@ -0,0 +1,177 @@
+// Minimal OpenRGB SDK client: enough of protocol version 0 to list devices and set colors
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const DEFAULT_PORT: u16 = 6742;
+
+const MAGIC: &[u8; 4] = b"ORGB";
+const REQUEST_CONTROLLER_COUNT: u32 = 0;
+const REQUEST_CONTROLLER_DATA: u32 = 1;
+const SET_CLIENT_NAME: u32 = 50;
+const UPDATE_LEDS: u32 = 1050;
+const SET_CUSTOM_MODE: u32 = 1100;
+// Guards against a confused server making us allocate gigabytes
+const MAX_PACKET_BYTES: u32 = 16 * 1024 * 1024;
+
+pub struct Controller {
+    pub index: u32,
+    pub name: String,
+    pub leds: u16,
+}
+
+pub struct Client {
+    stream: TcpStream,
+}
+
+// Cursor over a controller data blob; every field is little-endian
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let end = self.at + len;
+        let bytes = self
+            .data
+            .get(self.at..end)
+            .ok_or_else(|| "Truncated OpenRGB controller data".to_string())?;
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.take(len).map(|_| ())
+    }
+
+    // Length-prefixed and NUL-terminated
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        let bytes = self.take(len)?;
+        let text = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+}
+
+fn parse_controller(index: u32, data: &[u8]) -> Result<Controller, String> {
+    let mut r = Reader { data, at: 0 };
+    r.skip(4 + 4)?; // data size, device type
+    let name = r.string()?;
+    for _ in 0..4 {
+        r.string()?; // description, version, serial, location
+    }
+    let modes = r.u16()?;
+    r.skip(4)?; // active mode
+    for _ in 0..modes {
+        r.string()?;
+        r.skip(4 * 9)?; // value, flags, speed and color limits, speed, direction, color mode
+        let colors = r.u16()? as usize;
+        r.skip(colors * 4)?;
+    }
+    let zones = r.u16()?;
+    for _ in 0..zones {
+        r.string()?;
+        r.skip(4 * 4)?; // type, LED minimum, maximum and count
+        let matrix = r.u16()? as usize;
+        r.skip(matrix)?;
+    }
+    let leds = r.u16()?;
+    Ok(Controller { index, name, leds })
+}
+
+impl Client {
+    pub async fn connect(host: &str, port: u16, client_name: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect((host, port))
+            .await
+            .map_err(|e| format!("Failed to connect to OpenRGB at {}:{}: {}", host, port, e))?;
+        let mut client = Client { stream };
+        let mut name = client_name.as_bytes().to_vec();
+        name.push(0);
+        client.send(0, SET_CLIENT_NAME, &name).await?;
+        Ok(client)
+    }
+
+    async fn send(&mut self, device: u32, id: u32, payload: &[u8]) -> Result<(), String> {
+        let mut packet = Vec::with_capacity(16 + payload.len());
+        packet.extend_from_slice(MAGIC);
+        packet.extend_from_slice(&device.to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        packet.extend_from_slice(payload);
+        self.stream
+            .write_all(&packet)
+            .await
+            .map_err(|e| format!("Failed to send to OpenRGB: {}", e))
+    }
+
+    // Skips packets the server pushes on its own, such as device list updates
+    async fn recv(&mut self, id: u32) -> Result<Vec<u8>, String> {
+        loop {
+            let mut header = [0u8; 16];
+            self.stream
+                .read_exact(&mut header)
+                .await
+                .map_err(|e| format!("Failed to read from OpenRGB: {}", e))?;
+            if &header[0..4] != MAGIC {
+                return Err("OpenRGB sent a packet without the ORGB header".to_string());
+            }
+            let received = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+            let size = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+            if size > MAX_PACKET_BYTES {
+                return Err(format!("OpenRGB packet of {} bytes is too large", size));
+            }
+            let mut payload = vec![0u8; size as usize];
+            self.stream
+                .read_exact(&mut payload)
+                .await
+                .map_err(|e| format!("Failed to read from OpenRGB: {}", e))?;
+            if received == id {
+                return Ok(payload);
+            }
+        }
+    }
+
+    pub async fn controllers(&mut self) -> Result<Vec<Controller>, String> {
+        self.send(0, REQUEST_CONTROLLER_COUNT, &[]).await?;
+        let count = self.recv(REQUEST_CONTROLLER_COUNT).await?;
+        let count = Reader {
+            data: &count,
+            at: 0,
+        }
+        .u32()?;
+        let mut controllers = Vec::new();
+        for index in 0..count {
+            self.send(index, REQUEST_CONTROLLER_DATA, &[]).await?;
+            let data = self.recv(REQUEST_CONTROLLER_DATA).await?;
+            controllers.push(parse_controller(index, &data)?);
+        }
+        Ok(controllers)
+    }
+
+    // Direct color control needs the device's custom (a.k.a. direct) mode
+    pub async fn take_control(&mut self, controller: &Controller) -> Result<(), String> {
+        self.send(controller.index, SET_CUSTOM_MODE, &[]).await
+    }
+
+    pub async fn set_color(&mut self, controller: &Controller, rgb: [u8; 3]) -> Result<(), String> {
+        let count = controller.leds as usize;
+        let size = 4 + 2 + count * 4;
+        let mut payload = Vec::with_capacity(size);
+        payload.extend_from_slice(&(size as u32).to_le_bytes());
+        payload.extend_from_slice(&controller.leds.to_le_bytes());
+        for _ in 0..count {
+            payload.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 0]);
+        }
+        self.send(controller.index, UPDATE_LEDS, &payload).await
+    }
+}
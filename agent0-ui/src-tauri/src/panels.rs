@@ -0,0 +1,237 @@
+// Dashboard panels shipped by the Agent-0 server as signed data, verified before use
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::api::{self, SignedPanelBundle};
+use crate::metrics::now_secs;
+use crate::{agent0, journal, storage};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PanelSettings {
+    // Off until the operator opts in
+    #[serde(default)]
+    enabled: bool,
+    // Hex-encoded ed25519 public keys allowed to sign panel bundles
+    #[serde(default)]
+    trusted_keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PanelBundle {
+    // Monotonic; an older bundle than the last accepted one is a rollback
+    version: u64,
+    #[serde(default)]
+    expires_at: Option<i64>,
+    panels: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PanelKind {
+    LineChart,
+    BarChart,
+    Stat,
+    Table,
+}
+
+// Panels are pure data the dashboard renders with its own components; no markup or script
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PanelDefinition {
+    id: String,
+    title: String,
+    kind: PanelKind,
+    // Agent-0 API path the dashboard polls for this panel's data
+    source: String,
+    #[serde(default)]
+    fields: Vec<String>,
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(default = "default_refresh_secs")]
+    refresh_secs: u64,
+}
+
+fn default_refresh_secs() -> u64 {
+    60
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AcceptedBundle {
+    version: u64,
+    key: String,
+    expires_at: Option<i64>,
+    panels: Vec<PanelDefinition>,
+    skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DynamicPanels {
+    version: u64,
+    key: String,
+    panels: Vec<PanelDefinition>,
+    // Panels dropped because this app version cannot render them
+    skipped: Vec<String>,
+    // Set when the server could not be reached and the last verified bundle was served
+    stale: Option<String>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("panels.json"))
+}
+
+// Last accepted bundle per server base URL
+fn cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("panels_cache.json"))
+}
+
+fn decode_hex<const N: usize>(text: &str, what: &str) -> Result<[u8; N], String> {
+    hex::decode(text.trim())
+        .map_err(|e| format!("Invalid {}: {}", what, e))?
+        .try_into()
+        .map_err(|_| format!("Invalid {}: expected {} bytes", what, N))
+}
+
+fn is_trusted(settings: &PanelSettings, key: &str) -> bool {
+    settings
+        .trusted_keys
+        .iter()
+        .any(|k| k.trim().eq_ignore_ascii_case(key))
+}
+
+fn verify(settings: &PanelSettings, bundle: &SignedPanelBundle) -> Result<(), String> {
+    let key = bundle.key.trim().to_lowercase();
+    if !is_trusted(settings, &key) {
+        return Err(format!("Panel bundle is signed by untrusted key {}", key));
+    }
+    let key = VerifyingKey::from_bytes(&decode_hex(&key, "panel signing key")?)
+        .map_err(|e| format!("Invalid panel signing key: {}", e))?;
+    let signature = Signature::from_bytes(&decode_hex(&bundle.signature, "panel signature")?);
+    key.verify(bundle.payload.as_bytes(), &signature)
+        .map_err(|_| "Panel bundle signature does not match".to_string())
+}
+
+fn validate(panel: &PanelDefinition) -> Result<(), String> {
+    if panel.id.is_empty()
+        || !panel
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Panel id '{}' is not a plain identifier", panel.id));
+    }
+    // Data stays on the configured server; panels cannot point the dashboard elsewhere
+    if !panel.source.starts_with('/') || panel.source.starts_with("//") {
+        return Err(format!(
+            "Panel '{}' source must be an Agent-0 API path",
+            panel.id
+        ));
+    }
+    if panel.refresh_secs == 0 {
+        return Err(format!(
+            "Panel '{}' refresh interval must be positive",
+            panel.id
+        ));
+    }
+    Ok(())
+}
+
+fn accept(
+    signed: &SignedPanelBundle,
+    settings: &PanelSettings,
+    previous: Option<&AcceptedBundle>,
+) -> Result<AcceptedBundle, String> {
+    verify(settings, signed)?;
+    let bundle: PanelBundle = serde_json::from_str(&signed.payload)
+        .map_err(|e| format!("Invalid panel bundle: {}", e))?;
+    if let Some(previous) = previous {
+        if bundle.version < previous.version {
+            return Err(format!(
+                "Panel bundle version {} is older than the accepted version {}",
+                bundle.version, previous.version
+            ));
+        }
+    }
+    if bundle.expires_at.map_or(false, |at| at <= now_secs()) {
+        return Err("Panel bundle has expired".to_string());
+    }
+    let mut panels = Vec::new();
+    let mut skipped = Vec::new();
+    for raw in bundle.panels {
+        let id = raw["id"].as_str().unwrap_or("?").to_string();
+        match serde_json::from_value::<PanelDefinition>(raw)
+            .map_err(|e| e.to_string())
+            .and_then(|panel| validate(&panel).map(|_| panel))
+        {
+            Ok(panel) => panels.push(panel),
+            Err(e) => skipped.push(format!("{}: {}", id, e)),
+        }
+    }
+    Ok(AcceptedBundle {
+        version: bundle.version,
+        key: signed.key.trim().to_lowercase(),
+        expires_at: bundle.expires_at,
+        panels,
+        skipped,
+    })
+}
+
+#[tauri::command]
+pub async fn get_dynamic_panels(app: AppHandle) -> Result<DynamicPanels, String> {
+    let settings: PanelSettings = storage::read_json(&settings_path(&app)?)?;
+    if !settings.enabled {
+        return Err("Server-provided panels are disabled".to_string());
+    }
+    if settings.trusted_keys.is_empty() {
+        return Err("No panel signing keys are trusted yet".to_string());
+    }
+    let base = agent0::base_url();
+    let mut cache: BTreeMap<String, AcceptedBundle> = storage::read_json(&cache_path(&app)?)?;
+    // A cached bundle only counts if its key is still trusted
+    let previous = cache
+        .get(&base)
+        .filter(|b| is_trusted(&settings, &b.key))
+        .cloned();
+    let outcome = api::get_ui_panels(&base)
+        .await
+        .and_then(|signed| accept(&signed, &settings, previous.as_ref()));
+    let (bundle, stale) = match (outcome, previous) {
+        (Ok(bundle), _) => {
+            cache.insert(base, bundle.clone());
+            storage::write_json(&cache_path(&app)?, &cache)?;
+            (bundle, None)
+        }
+        (Err(e), Some(previous)) if previous.expires_at.map_or(true, |at| at > now_secs()) => {
+            (previous, Some(e))
+        }
+        (Err(e), _) => return Err(e),
+    };
+    Ok(DynamicPanels {
+        version: bundle.version,
+        key: bundle.key,
+        panels: bundle.panels,
+        skipped: bundle.skipped,
+        stale,
+    })
+}
+
+#[tauri::command]
+pub fn get_panel_settings(app: AppHandle) -> Result<PanelSettings, String> {
+    storage::read_json(&settings_path(&app)?)
+}
+
+#[tauri::command]
+pub fn set_panel_settings(app: AppHandle, settings: PanelSettings) -> Result<(), String> {
+    for key in &settings.trusted_keys {
+        decode_hex::<32>(key, "panel signing key")?;
+    }
+    journal::write_json(
+        &app,
+        "Edit dashboard panel sources",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
@@ -0,0 +1,114 @@
+// Plain-text PDF writer for exported reports; Helvetica on A4, no external renderer
+use std::fmt::Write;
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 50;
+const TITLE_SIZE: u32 = 16;
+const TEXT_SIZE: u32 = 10;
+const LEADING: u32 = 14;
+
+// Literal string in the font's WinAnsi encoding; anything outside Latin-1 becomes '?'
+fn literal(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => out.extend_from_slice(&[b'\\', c as u8]),
+            c if (c as u32) < 0x20 => out.push(b' '),
+            c if (c as u32) <= 0xff => out.push(c as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+fn page_content(title: Option<&str>, lines: &[String]) -> Vec<u8> {
+    let mut content = Vec::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    if let Some(title) = title {
+        content.extend(format!("BT /F1 {} Tf {} {} Td ", TITLE_SIZE, MARGIN, y).bytes());
+        content.extend(literal(title));
+        content.extend(b" Tj ET\n");
+        y -= TITLE_SIZE + LEADING;
+    }
+    content.extend(
+        format!(
+            "BT /F1 {} Tf {} TL {} {} Td\n",
+            TEXT_SIZE, LEADING, MARGIN, y
+        )
+        .bytes(),
+    );
+    for line in lines {
+        content.extend(literal(line));
+        content.extend(b" Tj T*\n");
+    }
+    content.extend(b"ET\n");
+    content
+}
+
+pub fn render(title: &str, lines: &[String]) -> Vec<u8> {
+    let per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+    let first_page = per_page - ((TITLE_SIZE + LEADING) / LEADING) as usize - 1;
+    let mut pages: Vec<Vec<u8>> = Vec::new();
+    let (first, mut rest) = lines.split_at(first_page.min(lines.len()));
+    pages.push(page_content(Some(title), first));
+    while !rest.is_empty() {
+        let (page, remaining) = rest.split_at(per_page.min(rest.len()));
+        pages.push(page_content(None, page));
+        rest = remaining;
+    }
+
+    // Objects 1-3 are the catalog, page tree and font; each page adds a page and its content
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + i * 2))
+        .collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (i, content) in pages.into_iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                5 + i * 2
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"endstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    out.extend(table.bytes());
+    out
+}
@@ -0,0 +1,42 @@
+// Which GPUs Agent-0 places each model on
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::api::{self, DevicePlacement};
+use crate::gpu::Gpu;
+use crate::{agent0, install_mode};
+
+#[tauri::command]
+pub async fn get_model_device_map(model: String) -> Result<Value, String> {
+    install_mode::require_local("Device placement")?;
+    api::get_model_placement(&agent0::base_url(), &model).await
+}
+
+#[tauri::command]
+pub async fn set_model_device_map(
+    app: AppHandle,
+    model: String,
+    devices: Vec<u32>,
+) -> Result<Value, String> {
+    install_mode::require_local("Device placement")?;
+    if devices.is_empty() {
+        return Err("A model needs at least one device".to_string());
+    }
+    let mut seen = Vec::new();
+    for index in &devices {
+        if seen.contains(index) {
+            return Err(format!("GPU {} is listed twice", index));
+        }
+        seen.push(*index);
+    }
+    // Only checked when NVML can see the devices
+    if let Ok(count) = app.state::<Gpu>().device_count() {
+        if let Some(index) = devices.iter().find(|&&index| index >= count) {
+            return Err(format!(
+                "GPU {} does not exist, this machine has {} GPU(s)",
+                index, count
+            ));
+        }
+    }
+    api::set_model_placement(&agent0::base_url(), &model, &DevicePlacement { devices }).await
+}
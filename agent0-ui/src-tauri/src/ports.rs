@@ -0,0 +1,145 @@
+// Port availability checks and owner lookup for the managed Agent-0 listener
+use serde::Serialize;
+use std::net::TcpListener;
+#[cfg(not(target_os = "linux"))]
+use std::process::Command;
+
+use crate::gpu_processes;
+
+// How far above the configured port to look for a free one
+const SEARCH_SPAN: u16 = 100;
+
+#[derive(Serialize, Clone)]
+pub struct PortOwner {
+    pid: u32,
+    name: String,
+    command_line: String,
+}
+
+impl PortOwner {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct PortStatus {
+    port: u16,
+    free: bool,
+    // None when the port is free or the owner is not visible to this user
+    owner: Option<PortOwner>,
+}
+
+impl PortStatus {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.free
+    }
+
+    pub fn owner(&self) -> Option<&PortOwner> {
+        self.owner.as_ref()
+    }
+}
+
+// Binding on all interfaces catches listeners on either loopback or the LAN address
+pub fn is_free(port: u16) -> bool {
+    // Each probe is dropped before the next so they do not collide with each other
+    let any = TcpListener::bind(("0.0.0.0", port)).is_ok();
+    any && TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+pub fn status(port: u16) -> PortStatus {
+    let free = is_free(port);
+    PortStatus {
+        port,
+        free,
+        owner: if free {
+            None
+        } else {
+            owner_pid(port).map(owner)
+        },
+    }
+}
+
+pub fn next_free(port: u16) -> Option<u16> {
+    (1..=SEARCH_SPAN)
+        .filter_map(|offset| port.checked_add(offset))
+        .find(|candidate| is_free(*candidate))
+}
+
+fn owner(pid: u32) -> PortOwner {
+    let (name, command_line) = gpu_processes::describe(pid);
+    PortOwner {
+        pid,
+        name,
+        command_line,
+    }
+}
+
+// Listening socket inode from /proc/net/tcp{,6}, then the process holding that inode
+#[cfg(target_os = "linux")]
+fn owner_pid(port: u16) -> Option<u32> {
+    let wanted = format!("{:04X}", port);
+    let inode = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .find_map(|table| {
+            let text = std::fs::read_to_string(table).ok()?;
+            text.lines().skip(1).find_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let local_port = fields.get(1)?.rsplit(':').next()?;
+                // 0A is TCP_LISTEN
+                (local_port == wanted && fields.get(3) == Some(&"0A"))
+                    .then(|| fields.get(9).map(|s| s.to_string()))
+                    .flatten()
+            })
+        })?;
+    let target = format!("socket:[{}]", inode);
+    std::fs::read_dir("/proc").ok()?.find_map(|entry| {
+        let entry = entry.ok()?;
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        let fds = std::fs::read_dir(entry.path().join("fd")).ok()?;
+        fds.filter_map(|fd| std::fs::read_link(fd.ok()?.path()).ok())
+            .any(|link| link.to_str() == Some(target.as_str()))
+            .then(|| pid)
+    })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn owner_pid(port: u16) -> Option<u32> {
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-t"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(windows)]
+fn owner_pid(port: u16) -> Option<u32> {
+    let output = Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .output()
+        .ok()?;
+    let suffix = format!(":{}", port);
+    // "  TCP    0.0.0.0:8000    0.0.0.0:0    LISTENING    1234"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (fields.len() == 5 && fields[1].ends_with(&suffix) && fields[3] == "LISTENING")
+                .then(|| fields[4].parse().ok())
+                .flatten()
+        })
+}
+
+#[tauri::command]
+pub fn check_port(port: u16) -> PortStatus {
+    status(port)
+}
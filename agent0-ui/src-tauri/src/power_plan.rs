@@ -0,0 +1,268 @@
+// Electricity tariff windows that throttle, defer training or pause Agent-0
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api::{self, ConcurrencyLimit, TrainingDeferral};
+use crate::metrics::now_secs;
+use crate::schedule::TimeWindow;
+use crate::{agent0, journal, storage};
+
+const EVALUATE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PowerAction {
+    LowerConcurrency { max_concurrency: u32 },
+    DeferTraining,
+    Pause,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TariffWindow {
+    name: String,
+    #[serde(flatten)]
+    window: TimeWindow,
+    action: PowerAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PowerPlan {
+    enabled: bool,
+    windows: Vec<TariffWindow>,
+}
+
+impl PowerPlan {
+    pub fn disabled(windows: Vec<TariffWindow>) -> Self {
+        PowerPlan {
+            enabled: false,
+            windows,
+        }
+    }
+
+    pub fn has_windows(&self) -> bool {
+        !self.windows.is_empty()
+    }
+}
+
+// Manual control that beats the plan until it expires
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PowerOverride {
+    Suspend { until: i64 },
+    Force { action: PowerAction, until: i64 },
+}
+
+// Journaled, so plan edits can be undone
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct PowerPlanFile {
+    plan: PowerPlan,
+}
+
+// Kept apart from the plan so overrides do not break undoing a plan edit; what was applied is
+// saved too, so an action still in force when the app quit is reverted on the next start
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct PowerRuntime {
+    #[serde(rename = "override")]
+    active_override: Option<PowerOverride>,
+    applied: Option<AppliedAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AppliedAction {
+    action: PowerAction,
+    source: String,
+    since: i64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PowerPlanStatus {
+    enabled: bool,
+    applied: Option<AppliedAction>,
+    #[serde(rename = "override")]
+    active_override: Option<PowerOverride>,
+}
+
+#[derive(Default)]
+pub struct PowerPlanState {
+    applied: Mutex<Option<AppliedAction>>,
+}
+
+fn plan_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("power_plan.json"))
+}
+
+fn runtime_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("power_state.json"))
+}
+
+fn load(app: &AppHandle) -> Result<PowerPlanFile, String> {
+    storage::read_json(&plan_path(app)?)
+}
+
+fn load_runtime(app: &AppHandle) -> Result<PowerRuntime, String> {
+    storage::read_json(&runtime_path(app)?)
+}
+
+// Memory is updated even when the file cannot be, so a held write does not re-run the action
+fn save_applied(app: &AppHandle, applied: Option<AppliedAction>) {
+    *app.state::<PowerPlanState>().applied.lock().unwrap() = applied.clone();
+    let saved = load_runtime(app).and_then(|mut runtime| {
+        runtime.applied = applied;
+        storage::write_json(&runtime_path(app)?, &runtime)
+    });
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save the applied power action: {}", e);
+    }
+}
+
+async fn set_concurrency(max_concurrency: Option<u32>) -> Result<(), String> {
+    api::set_concurrency(&agent0::base_url(), &ConcurrencyLimit { max_concurrency }).await
+}
+
+async fn defer_training(deferred: bool) -> Result<(), String> {
+    api::defer_training(&agent0::base_url(), &TrainingDeferral { deferred }).await
+}
+
+async fn apply(app: &AppHandle, action: &PowerAction) -> Result<(), String> {
+    match action {
+        PowerAction::LowerConcurrency { max_concurrency } => {
+            set_concurrency(Some(*max_concurrency)).await
+        }
+        PowerAction::DeferTraining => defer_training(true).await,
+        PowerAction::Pause => crate::pause_service(app.clone()).await.map(|_| ()),
+    }
+}
+
+async fn revert(app: &AppHandle, action: &PowerAction) -> Result<(), String> {
+    match action {
+        // A null limit hands concurrency back to the server's own configuration
+        PowerAction::LowerConcurrency { .. } => set_concurrency(None).await,
+        PowerAction::DeferTraining => defer_training(false).await,
+        PowerAction::Pause => crate::resume_service(app.clone()).await.map(|_| ()),
+    }
+}
+
+fn desired(file: &PowerPlanFile, runtime: &PowerRuntime) -> Option<(PowerAction, String)> {
+    let now = now_secs();
+    match &runtime.active_override {
+        Some(PowerOverride::Suspend { until }) if *until > now => return None,
+        Some(PowerOverride::Force { action, until }) if *until > now => {
+            return Some((action.clone(), "override".to_string()))
+        }
+        _ => {}
+    }
+    if !file.plan.enabled {
+        return None;
+    }
+    file.plan
+        .windows
+        .iter()
+        .find(|w| w.window.contains(Utc::now()))
+        .map(|w| (w.action.clone(), w.name.clone()))
+}
+
+// Bring Agent-0 in line with whatever the plan or override wants right now
+async fn evaluate(app: &AppHandle) -> Result<(), String> {
+    let target = desired(&load(app)?, &load_runtime(app)?);
+    let current = app
+        .state::<PowerPlanState>()
+        .applied
+        .lock()
+        .unwrap()
+        .clone();
+
+    if current.as_ref().map(|c| &c.action) == target.as_ref().map(|(a, _)| a) {
+        return Ok(());
+    }
+    if let Some(current) = &current {
+        revert(app, &current.action).await?;
+    }
+    save_applied(app, None);
+    if let Some((action, source)) = target {
+        apply(app, &action).await?;
+        save_applied(
+            app,
+            Some(AppliedAction {
+                action,
+                source,
+                since: now_secs(),
+            }),
+        );
+    }
+
+    let _ = app.emit_all("power-plan-changed", status(app)?);
+    Ok(())
+}
+
+fn status(app: &AppHandle) -> Result<PowerPlanStatus, String> {
+    let file = load(app)?;
+    let runtime = load_runtime(app)?;
+    Ok(PowerPlanStatus {
+        enabled: file.plan.enabled,
+        applied: app
+            .state::<PowerPlanState>()
+            .applied
+            .lock()
+            .unwrap()
+            .clone(),
+        active_override: runtime.active_override,
+    })
+}
+
+pub fn start(app: AppHandle) {
+    // Picked up from the last run, so the first evaluation reverts it if it no longer applies
+    match load_runtime(&app) {
+        Ok(runtime) => *app.state::<PowerPlanState>().applied.lock().unwrap() = runtime.applied,
+        Err(e) => tracing::warn!("Failed to read the applied power action: {}", e),
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(EVALUATE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = evaluate(&app).await {
+                tracing::warn!("Power plan evaluation failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_power_plan(app: AppHandle) -> Result<PowerPlan, String> {
+    Ok(load(&app)?.plan)
+}
+
+#[tauri::command]
+pub async fn set_power_plan(app: AppHandle, plan: PowerPlan) -> Result<PowerPlanStatus, String> {
+    for window in &plan.windows {
+        window
+            .window
+            .validate()
+            .map_err(|e| format!("Tariff window '{}': {}", window.name, e))?;
+    }
+    let mut file = load(&app)?;
+    file.plan = plan;
+    journal::write_json(&app, "Edit power plan", &plan_path(&app)?, &file)?;
+    evaluate(&app).await?;
+    status(&app)
+}
+
+#[tauri::command]
+pub async fn set_power_override(
+    app: AppHandle,
+    power_override: Option<PowerOverride>,
+) -> Result<PowerPlanStatus, String> {
+    let mut runtime = load_runtime(&app)?;
+    runtime.active_override = power_override;
+    storage::write_json(&runtime_path(&app)?, &runtime)?;
+    evaluate(&app).await?;
+    status(&app)
+}
+
+#[tauri::command]
+pub fn get_power_plan_status(app: AppHandle) -> Result<PowerPlanStatus, String> {
+    status(&app)
+}
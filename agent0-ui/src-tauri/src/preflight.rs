@@ -0,0 +1,365 @@
+// Pre-flight checklist gating training runs; each failing item can be overridden per run
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+use crate::alerts::AlertState;
+use crate::formatting::Formatter;
+use crate::gpu::Gpu;
+use crate::gpu_processes::HookReport;
+use crate::metrics::MetricsStore;
+use crate::schedule::TimeWindow;
+use crate::{agent0, api, audit, gpu_processes, install_mode, journal, storage, telemetry};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PreflightSettings {
+    // Each check is skipped while its threshold is unset
+    min_free_disk_bytes: Option<u64>,
+    // Volume the run writes checkpoints to; defaults to the app data dir
+    disk_path: Option<String>,
+    // Free memory required on at least one GPU
+    min_vram_headroom_bytes: Option<u64>,
+    dataset_path: Option<String>,
+    max_dataset_age_hours: Option<u64>,
+    block_on_incidents: bool,
+    // Quiet hours and recurring meetings alike
+    quiet_hours: Vec<TimeWindow>,
+}
+
+impl Default for PreflightSettings {
+    fn default() -> Self {
+        PreflightSettings {
+            min_free_disk_bytes: None,
+            disk_path: None,
+            min_vram_headroom_bytes: None,
+            dataset_path: None,
+            max_dataset_age_hours: None,
+            block_on_incidents: true,
+            quiet_hours: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightCheck {
+    DiskSpace,
+    VramHeadroom,
+    DatasetFreshness,
+    NoActiveIncident,
+    OutsideQuietHours,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckState {
+    Pass,
+    Fail,
+    Skip,
+    // Failed, but the operator chose to start anyway
+    Overridden,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ChecklistItem {
+    check: PreflightCheck,
+    state: CheckState,
+    detail: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Checklist {
+    passed: bool,
+    items: Vec<ChecklistItem>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum TrainingStartError {
+    PreflightFailed { checklist: Checklist },
+    Failed { message: String },
+}
+
+impl From<String> for TrainingStartError {
+    fn from(message: String) -> Self {
+        TrainingStartError::Failed { message }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TrainingStart {
+    checklist: Checklist,
+    hook: HookReport,
+    run: Value,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("preflight.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<PreflightSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+fn item(check: PreflightCheck, state: CheckState, detail: String) -> ChecklistItem {
+    ChecklistItem {
+        check,
+        state,
+        detail,
+    }
+}
+
+fn skipped(check: PreflightCheck, detail: &str) -> ChecklistItem {
+    item(check, CheckState::Skip, detail.to_string())
+}
+
+// A probe that cannot answer fails the item, which can still be overridden
+fn check_disk(app: &AppHandle, settings: &PreflightSettings, fmt: &Formatter) -> ChecklistItem {
+    let check = PreflightCheck::DiskSpace;
+    let min = match settings.min_free_disk_bytes {
+        Some(min) => min,
+        None => return skipped(check, "No minimum configured"),
+    };
+    if install_mode::is_remote_only() {
+        return skipped(check, "Remote-only install");
+    }
+    let path = match &settings.disk_path {
+        Some(path) => PathBuf::from(path),
+        None => match storage::data_dir(app) {
+            Ok(dir) => dir,
+            Err(e) => return item(check, CheckState::Fail, e),
+        },
+    };
+    let free = match fs2::available_space(&path) {
+        Ok(free) => free,
+        Err(e) => {
+            let detail = format!("Failed to read free space on {}: {}", path.display(), e);
+            return item(check, CheckState::Fail, detail);
+        }
+    };
+    let state = if free >= min {
+        CheckState::Pass
+    } else {
+        CheckState::Fail
+    };
+    let detail = format!(
+        "{} free on {}, {} required",
+        fmt.bytes(free as f64),
+        path.display(),
+        fmt.bytes(min as f64)
+    );
+    item(check, state, detail)
+}
+
+fn check_vram(app: &AppHandle, settings: &PreflightSettings, fmt: &Formatter) -> ChecklistItem {
+    let check = PreflightCheck::VramHeadroom;
+    let min = match settings.min_vram_headroom_bytes {
+        Some(min) => min,
+        None => return skipped(check, "No minimum configured"),
+    };
+    if install_mode::is_remote_only() {
+        return skipped(check, "Remote-only install");
+    }
+    let devices = match app.state::<Gpu>().devices() {
+        Ok(devices) => devices,
+        Err(e) => return item(check, CheckState::Fail, e),
+    };
+    let best = devices.iter().max_by_key(|d| d.free_memory_bytes);
+    match best {
+        None => item(check, CheckState::Fail, "No GPUs found".to_string()),
+        Some(device) => {
+            let state = if device.free_memory_bytes >= min {
+                CheckState::Pass
+            } else {
+                CheckState::Fail
+            };
+            let detail = format!(
+                "{} free on GPU {} ({}), {} required",
+                fmt.bytes(device.free_memory_bytes as f64),
+                device.index,
+                device.name,
+                fmt.bytes(min as f64)
+            );
+            item(check, state, detail)
+        }
+    }
+}
+
+fn modified_at(path: &Path) -> Result<SystemTime, String> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+// A directory counts as fresh when anything directly inside it changed recently
+fn newest_change(path: &Path) -> Result<SystemTime, String> {
+    let mut newest = modified_at(path)?;
+    if path.is_dir() {
+        let entries = std::fs::read_dir(path)
+            .map_err(|e| format!("Failed to list {}: {}", path.display(), e))?;
+        for entry in entries.flatten() {
+            if let Ok(modified) = modified_at(&entry.path()) {
+                newest = newest.max(modified);
+            }
+        }
+    }
+    Ok(newest)
+}
+
+fn check_dataset(settings: &PreflightSettings, fmt: &Formatter) -> ChecklistItem {
+    let check = PreflightCheck::DatasetFreshness;
+    let (path, max_hours) = match (&settings.dataset_path, settings.max_dataset_age_hours) {
+        (Some(path), Some(hours)) => (Path::new(path), hours),
+        _ => return skipped(check, "No dataset configured"),
+    };
+    let changed = match newest_change(path) {
+        Ok(changed) => changed,
+        Err(e) => return item(check, CheckState::Fail, e),
+    };
+    let age = SystemTime::now()
+        .duration_since(changed)
+        .unwrap_or_default()
+        .as_secs();
+    let state = if age <= max_hours * 3600 {
+        CheckState::Pass
+    } else {
+        CheckState::Fail
+    };
+    let detail = format!(
+        "{} last changed {} ago, limit {}",
+        path.display(),
+        fmt.duration(age as f64),
+        fmt.duration((max_hours * 3600) as f64)
+    );
+    item(check, state, detail)
+}
+
+fn check_incidents(app: &AppHandle, settings: &PreflightSettings) -> ChecklistItem {
+    let check = PreflightCheck::NoActiveIncident;
+    if !settings.block_on_incidents {
+        return skipped(check, "Incidents do not block training");
+    }
+    let open = app.state::<AlertState>().unacknowledged();
+    match open.first() {
+        None => item(
+            check,
+            CheckState::Pass,
+            "No unacknowledged alerts".to_string(),
+        ),
+        Some(first) => item(
+            check,
+            CheckState::Fail,
+            format!(
+                "{} unacknowledged alert(s), e.g. {}",
+                open.len(),
+                first.message
+            ),
+        ),
+    }
+}
+
+fn check_quiet_hours(settings: &PreflightSettings) -> ChecklistItem {
+    let check = PreflightCheck::OutsideQuietHours;
+    if settings.quiet_hours.is_empty() {
+        return skipped(check, "No quiet hours configured");
+    }
+    let now = Utc::now();
+    match settings.quiet_hours.iter().find(|w| w.contains(now)) {
+        None => item(check, CheckState::Pass, "Outside quiet hours".to_string()),
+        Some(window) => item(
+            check,
+            CheckState::Fail,
+            format!("Inside quiet hours {}-{}", window.start, window.end),
+        ),
+    }
+}
+
+pub fn run(app: &AppHandle, overrides: &[PreflightCheck]) -> Result<Checklist, String> {
+    let settings = load_settings(app)?;
+    let fmt = Formatter::load(app);
+    let mut items = vec![
+        check_disk(app, &settings, &fmt),
+        check_vram(app, &settings, &fmt),
+        check_dataset(&settings, &fmt),
+        check_incidents(app, &settings),
+        check_quiet_hours(&settings),
+    ];
+    for item in &mut items {
+        if item.state == CheckState::Fail && overrides.contains(&item.check) {
+            item.state = CheckState::Overridden;
+        }
+    }
+    let passed = items.iter().all(|i| i.state != CheckState::Fail);
+    Ok(Checklist { passed, items })
+}
+
+#[tauri::command]
+pub fn run_preflight_checklist(
+    app: AppHandle,
+    overrides: Option<Vec<PreflightCheck>>,
+) -> Result<Checklist, String> {
+    run(&app, &overrides.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn start_training_run(
+    app: AppHandle,
+    config: Value,
+    overrides: Option<Vec<PreflightCheck>>,
+) -> Result<TrainingStart, TrainingStartError> {
+    let overrides = overrides.unwrap_or_default();
+    let checklist = run(&app, &overrides)?;
+    if !checklist.passed {
+        return Err(TrainingStartError::PreflightFailed { checklist });
+    }
+    let hook = if install_mode::is_remote_only() {
+        HookReport::default()
+    } else {
+        gpu_processes::pre_training(&app)?
+    };
+    let overridden: Vec<PreflightCheck> = checklist
+        .items
+        .iter()
+        .filter(|i| i.state == CheckState::Overridden)
+        .map(|i| i.check)
+        .collect();
+    let result = api::start_training_run(&agent0::base_url(), &config).await;
+    if result.is_err() {
+        app.state::<MetricsStore>()
+            .record(telemetry::TRAINING_FAILED_SERIES, 1.0);
+    }
+    audit::record(
+        &app,
+        "preflight",
+        "start_training_run",
+        &json!({ "config": config, "overrides": overridden }),
+        &result.clone().map(|_| Value::Null),
+    );
+    Ok(TrainingStart {
+        checklist,
+        hook,
+        run: result?,
+    })
+}
+
+#[tauri::command]
+pub fn get_preflight_settings(app: AppHandle) -> Result<PreflightSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_preflight_settings(app: AppHandle, settings: PreflightSettings) -> Result<(), String> {
+    for window in &settings.quiet_hours {
+        window.validate()?;
+    }
+    journal::write_json(
+        &app,
+        "Edit pre-flight checklist",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
@@ -0,0 +1,120 @@
+// Named Agent-0 server profiles and the active selection
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::{agent0, journal, log_levels, storage, store};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerProfile {
+    pub name: String,
+    pub base_url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProfilesFile {
+    pub active: String,
+    pub profiles: Vec<ServerProfile>,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        ProfilesFile {
+            active: "local".to_string(),
+            profiles: vec![ServerProfile {
+                name: "local".to_string(),
+                base_url: agent0::DEFAULT_BASE_URL.to_string(),
+            }],
+        }
+    }
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("profiles.json"))
+}
+
+pub fn load(app: &AppHandle) -> Result<ProfilesFile, String> {
+    storage::read_json(&profiles_path(app)?)
+}
+
+fn save(app: &AppHandle, file: &ProfilesFile) -> Result<(), String> {
+    storage::write_json(&profiles_path(app)?, file)
+}
+
+// Profile edits are undoable; switching the active profile is not
+fn save_edit(app: &AppHandle, label: &str, file: &ProfilesFile) -> Result<(), String> {
+    journal::write_json(app, label, &profiles_path(app)?, file)
+}
+
+pub fn find(app: &AppHandle, name: &str) -> Result<ServerProfile, String> {
+    load(app)?
+        .profiles
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Unknown server profile '{}'", name))
+}
+
+pub fn active(app: &AppHandle) -> Result<ServerProfile, String> {
+    let file = load(app)?;
+    find(app, &file.active)
+}
+
+pub fn activate(app: &AppHandle, name: &str) -> Result<ServerProfile, String> {
+    let profile = find(app, name)?;
+    let mut file = load(app)?;
+    file.active = profile.name.clone();
+    save(app, &file)?;
+    agent0::set_base_url(&profile.base_url);
+    store::set_active_profile(app, &profile.name);
+    log_levels::init(app)?;
+    let _ = app.emit_all("profile-changed", &profile);
+    Ok(profile)
+}
+
+// Point the shared client and log levels at the persisted active profile
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let profile = active(app)?;
+    agent0::set_base_url(&profile.base_url);
+    store::set_active_profile(app, &profile.name);
+    log_levels::init(app)
+}
+
+#[tauri::command]
+pub fn list_server_profiles(app: AppHandle) -> Result<ProfilesFile, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub fn save_server_profile(app: AppHandle, profile: ServerProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    reqwest::Url::parse(&profile.base_url)
+        .map_err(|e| format!("Invalid base URL '{}': {}", profile.base_url, e))?;
+    let mut file = load(&app)?;
+    let is_active = file.active == profile.name;
+    match file.profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile.clone(),
+        None => file.profiles.push(profile.clone()),
+    }
+    save_edit(&app, &format!("Save profile {}", profile.name), &file)?;
+    if is_active {
+        agent0::set_base_url(&profile.base_url);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_server_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let mut file = load(&app)?;
+    if file.active == name {
+        return Err("Cannot delete the active profile".to_string());
+    }
+    file.profiles.retain(|p| p.name != name);
+    save_edit(&app, &format!("Delete profile {}", name), &file)
+}
+
+#[tauri::command]
+pub fn set_active_profile(app: AppHandle, name: String) -> Result<ServerProfile, String> {
+    activate(&app, &name)
+}
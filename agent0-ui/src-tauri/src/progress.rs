@@ -0,0 +1,220 @@
+// Long-running operations: an id returned at once, progress events, and cancellation
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+
+use crate::metrics::now_secs;
+
+pub const CANCELLED: &str = "Cancelled";
+
+#[derive(Serialize, Clone)]
+pub struct OperationProgress {
+    id: String,
+    kind: String,
+    stage: String,
+    // 0-100; None while the total is unknown
+    pct: Option<f64>,
+    eta_secs: Option<u64>,
+    started_at: i64,
+    done: bool,
+    error: Option<String>,
+    // The operation's return value, on the final event only
+    result: Option<Value>,
+}
+
+struct Running {
+    handle: Option<JoinHandle<()>>,
+    cancel: Arc<AtomicBool>,
+    last: OperationProgress,
+}
+
+#[derive(Default)]
+pub struct Operations {
+    running: Mutex<HashMap<String, Running>>,
+}
+
+// Handed to the work itself; cheap to clone into blocking tasks
+#[derive(Clone)]
+pub struct Operation {
+    // None for work nobody is watching, such as scheduled runs
+    app: Option<AppHandle>,
+    id: String,
+    kind: String,
+    started: Instant,
+    started_at: i64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Operation {
+    pub fn untracked(kind: &str) -> Self {
+        Operation {
+            app: None,
+            id: String::new(),
+            kind: kind.to_string(),
+            started: Instant::now(),
+            started_at: now_secs(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn event(&self, stage: &str, pct: Option<f64>) -> OperationProgress {
+        let pct = pct.map(|p| p.clamp(0.0, 100.0));
+        // Straight-line estimate from the rate so far
+        let eta_secs = pct.filter(|p| *p > 0.0).map(|p| {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            (elapsed * (100.0 - p) / p) as u64
+        });
+        OperationProgress {
+            id: self.id.clone(),
+            kind: self.kind.clone(),
+            stage: stage.to_string(),
+            pct,
+            eta_secs,
+            started_at: self.started_at,
+            done: false,
+            error: None,
+            result: None,
+        }
+    }
+
+    fn publish(&self, event: OperationProgress) {
+        if let Some(app) = &self.app {
+            if let Some(running) = app
+                .state::<Operations>()
+                .running
+                .lock()
+                .unwrap()
+                .get_mut(&self.id)
+            {
+                running.last = event.clone();
+            }
+            let _ = app.emit_all("operation-progress", &event);
+        }
+    }
+
+    pub fn report(&self, stage: &str, pct: Option<f64>) {
+        self.publish(self.event(stage, pct));
+    }
+
+    // Fraction form for loops that count items or bytes
+    pub fn report_ratio(&self, stage: &str, done: u64, total: u64) {
+        let pct = if total == 0 {
+            100.0
+        } else {
+            done as f64 * 100.0 / total as f64
+        };
+        self.report(stage, Some(pct));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    // For blocking work, which task abort cannot interrupt; call between steps
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Runs `work` in the background and returns its id; the last event carries the result
+pub fn spawn<F, Fut>(app: &AppHandle, kind: &str, work: F) -> String
+where
+    F: FnOnce(Operation) -> Fut,
+    Fut: Future<Output = Result<Value, String>> + Send + 'static,
+{
+    let operation = Operation {
+        app: Some(app.clone()),
+        id: hex::encode(rand::random::<[u8; 8]>()),
+        kind: kind.to_string(),
+        started: Instant::now(),
+        started_at: now_secs(),
+        cancel: Arc::new(AtomicBool::new(false)),
+    };
+    let id = operation.id.clone();
+    let ops = app.state::<Operations>();
+    let mut running = ops.running.lock().unwrap();
+    running.insert(
+        id.clone(),
+        Running {
+            handle: None,
+            cancel: operation.cancel.clone(),
+            last: operation.event("queued", None),
+        },
+    );
+    let future = work(operation.clone());
+    let handle = tauri::async_runtime::spawn(async move {
+        let result = future.await;
+        let mut event = operation.event(if result.is_ok() { "done" } else { "failed" }, None);
+        event.done = true;
+        match result {
+            Ok(value) => {
+                event.pct = Some(100.0);
+                event.result = Some(value);
+            }
+            Err(e) => event.error = Some(e),
+        }
+        if let Some(app) = &operation.app {
+            // Waits for the insert below if the work finished at once
+            app.state::<Operations>()
+                .running
+                .lock()
+                .unwrap()
+                .remove(&operation.id);
+            let _ = app.emit_all("operation-progress", &event);
+        }
+    });
+    if let Some(entry) = running.get_mut(&id) {
+        entry.handle = Some(handle);
+    }
+    id
+}
+
+pub fn encode<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to encode operation result: {}", e))
+}
+
+#[tauri::command]
+pub fn list_operations(state: State<'_, Operations>) -> Vec<OperationProgress> {
+    let mut list: Vec<OperationProgress> = state
+        .running
+        .lock()
+        .unwrap()
+        .values()
+        .map(|r| r.last.clone())
+        .collect();
+    list.sort_by_key(|p| p.started_at);
+    list
+}
+
+#[tauri::command]
+pub fn cancel_operation(app: AppHandle, id: String) -> Result<(), String> {
+    let running = app
+        .state::<Operations>()
+        .running
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or_else(|| format!("No running operation '{}'", id))?;
+    running.cancel.store(true, Ordering::SeqCst);
+    // Async work stops at its next await point; blocking work sees the flag
+    if let Some(handle) = running.handle {
+        handle.abort();
+    }
+    let mut event = running.last;
+    event.stage = "cancelled".to_string();
+    event.done = true;
+    event.eta_secs = None;
+    event.error = Some(CANCELLED.to_string());
+    let _ = app.emit_all("operation-progress", &event);
+    Ok(())
+}
@@ -0,0 +1,427 @@
+// Authenticated LAN proxy exposing selected Agent-0 endpoints to other devices
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, State};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_native_tls::TlsAcceptor;
+
+use crate::{agent0, certs, costs, language, memory, tags};
+
+const LOG_CAPACITY: usize = 500;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+// Shared conversation links carry their own signature, checked by Agent-0
+pub const SHARE_PATH: &str = "/shares/";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProxyClient {
+    name: String,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProxyConfig {
+    port: u16,
+    clients: Vec<ProxyClient>,
+    #[serde(default = "default_allowed_paths")]
+    allowed_paths: Vec<String>,
+    #[serde(default = "default_requests_per_minute")]
+    requests_per_minute: u32,
+    #[serde(default)]
+    tls: bool,
+}
+
+fn default_allowed_paths() -> Vec<String> {
+    vec![
+        "/health".to_string(),
+        "/chat".to_string(),
+        "/models".to_string(),
+    ]
+}
+
+fn default_requests_per_minute() -> u32 {
+    60
+}
+
+fn log_entry_bytes(entry: &ProxyLogEntry) -> usize {
+    std::mem::size_of::<ProxyLogEntry>()
+        + entry.client.len()
+        + entry.method.len()
+        + entry.path.len()
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProxyLogEntry {
+    timestamp: u64,
+    client: String,
+    method: String,
+    path: String,
+    status: u16,
+    duration_ms: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// Everything the connection handlers share for the lifetime of one listener
+struct Proxy {
+    app: AppHandle,
+    config: ProxyConfig,
+    http: reqwest::Client,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    log: Arc<Mutex<VecDeque<ProxyLogEntry>>>,
+}
+
+#[derive(Default)]
+pub struct ProxyState {
+    server: Mutex<Option<JoinHandle<()>>>,
+    log: Arc<Mutex<VecDeque<ProxyLogEntry>>>,
+    tls: Arc<RwLock<Option<TlsAcceptor>>>,
+    public_url: Mutex<Option<String>>,
+}
+
+impl ProxyState {
+    // Address other devices use to reach the running proxy
+    pub fn public_url(&self) -> Option<String> {
+        self.public_url.lock().unwrap().clone()
+    }
+
+    // Swap the certificate of a running TLS listener; new connections pick it up
+    pub fn reload_tls(&self, acceptor: TlsAcceptor) {
+        let mut tls = self.tls.write().unwrap();
+        if tls.is_some() {
+            *tls = Some(acceptor);
+        }
+    }
+
+    pub fn log_usage(&self) -> (usize, usize) {
+        let log = self.log.lock().unwrap();
+        (log.len(), log.iter().map(log_entry_bytes).sum())
+    }
+}
+
+impl Proxy {
+    fn identify(&self, req: &Request<Body>) -> Option<&ProxyClient> {
+        let presented = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))?;
+        self.config
+            .clients
+            .iter()
+            .find(|c| tokens_match(&c.token, presented))
+    }
+
+    // Token bucket per client, refilled continuously at the configured rate
+    fn allow(&self, key: &str) -> bool {
+        let capacity = self.config.requests_per_minute.max(1) as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        // A bucket idle for a minute is full again, the same as a fresh one
+        buckets.retain(|_, b| b.updated.elapsed().as_secs() < 60);
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: Instant::now(),
+        });
+        let elapsed = bucket.updated.elapsed().as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * capacity / 60.0).min(capacity);
+        bucket.updated = Instant::now();
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn path_allowed(&self, path: &str) -> bool {
+        self.config
+            .allowed_paths
+            .iter()
+            .any(|p| path == p || path.starts_with(&format!("{}/", p.trim_end_matches('/'))))
+    }
+
+    fn record(&self, entry: ProxyLogEntry) {
+        tracing::info!(
+            "{} {} {} -> {} ({}ms)",
+            entry.client,
+            entry.method,
+            entry.path,
+            entry.status,
+            entry.duration_ms
+        );
+        let mut log = self.log.lock().unwrap();
+        if log.len() == LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(entry);
+        memory::trim_to_budget(
+            &mut log,
+            memory::budgets().log_buffer_bytes,
+            log_entry_bytes,
+        );
+    }
+
+    async fn handle(&self, req: Request<Body>, peer: SocketAddr) -> Response<Body> {
+        let started = Instant::now();
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let client = match self.identify(&req) {
+            Some(c) => c.name.clone(),
+            None => format!("unauthenticated@{}", peer.ip()),
+        };
+        let authenticated = !client.starts_with("unauthenticated@");
+        let shared_link = method == Method::GET && path.starts_with(SHARE_PATH);
+
+        let response = if !self.allow(&client) {
+            status_response(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded")
+        } else if !authenticated && !shared_link {
+            status_response(StatusCode::UNAUTHORIZED, "Missing or invalid token")
+        } else if !shared_link && !self.path_allowed(&path) {
+            status_response(
+                StatusCode::FORBIDDEN,
+                "Endpoint is not exposed by this proxy",
+            )
+        } else {
+            match self.forward(req).await {
+                Ok(resp) => resp,
+                Err(e) => status_response(StatusCode::BAD_GATEWAY, &e),
+            }
+        };
+
+        self.record(ProxyLogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            client,
+            method: method.to_string(),
+            path,
+            status: response.status().as_u16(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        response
+    }
+
+    async fn forward(&self, req: Request<Body>) -> Result<Response<Body>, String> {
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| "/".to_string());
+        let method = match *req.method() {
+            Method::GET => reqwest::Method::GET,
+            Method::POST => reqwest::Method::POST,
+            _ => return Err("Only GET and POST are proxied".to_string()),
+        };
+        let content_type = req.headers().get(hyper::header::CONTENT_TYPE).cloned();
+        let declared_length = req
+            .headers()
+            .get(hyper::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        if declared_length > MAX_BODY_BYTES {
+            return Err("Request body too large".to_string());
+        }
+        let body = hyper::body::to_bytes(req.into_body())
+            .await
+            .map_err(|e| format!("Failed to read request body: {}", e))?;
+        if body.len() > MAX_BODY_BYTES {
+            return Err("Request body too large".to_string());
+        }
+
+        let chat = method == reqwest::Method::POST && path_and_query.starts_with("/chat");
+        let mut upstream = self
+            .http
+            .request(method, format!("{}{}", agent0::base_url(), path_and_query))
+            .body(body.to_vec());
+        if let Some(ct) = content_type.as_ref().and_then(|v| v.to_str().ok()) {
+            upstream = upstream.header(reqwest::header::CONTENT_TYPE, ct);
+        }
+        // Prompts from other devices get the same routing hints and quotas as the desktop's own
+        let mut billing = None;
+        if chat {
+            let request = serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default();
+            let detection = language::prompt_text(&request).and_then(language::detect);
+            let persona = costs::persona(
+                request["persona"].as_str(),
+                detection.as_ref().and_then(|d| d.persona.as_deref()),
+            );
+            if let Err(e) = costs::check_quota(&self.app, &persona) {
+                return Ok(status_response(StatusCode::TOO_MANY_REQUESTS, &e));
+            }
+            for (name, value) in detection.map(|d| d.hints).unwrap_or_default() {
+                upstream = upstream.header(name, value);
+            }
+            let conversation = request["session_id"]
+                .as_str()
+                .or_else(|| request["conversation_id"].as_str())
+                .map(|id| id.to_string());
+            billing = Some((conversation, persona));
+        }
+        let upstream = upstream
+            .send()
+            .await
+            .map_err(|e| format!("Agent-0 unreachable: {}", e))?;
+
+        let status = upstream.status().as_u16();
+        let content_type = upstream
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let bytes = upstream
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read Agent-0 response: {}", e))?;
+        if let Some((conversation, persona)) = billing.filter(|_| status < 300) {
+            if let Ok(reply) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+                costs::attribute(&self.app, conversation.as_deref(), &persona, &reply);
+                tags::tag_chat(&self.app, conversation.as_deref(), &persona, &reply);
+            }
+        }
+
+        let mut response = Response::builder().status(status);
+        if let Some(ct) = content_type {
+            response = response.header(hyper::header::CONTENT_TYPE, ct);
+        }
+        response
+            .body(Body::from(bytes))
+            .map_err(|e| format!("Failed to build response: {}", e))
+    }
+}
+
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn status_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(
+        serde_json::json!({ "error": message }).to_string(),
+    ));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+async fn serve_connection<I>(io: I, proxy: Arc<Proxy>, peer: SocketAddr) -> Result<(), String>
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |req| {
+        let proxy = proxy.clone();
+        async move { Ok::<_, Infallible>(proxy.handle(req, peer).await) }
+    });
+    Http::new()
+        .http1_only(true)
+        .serve_connection(io, service)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn serve(listener: TcpListener, proxy: Arc<Proxy>, tls: Arc<RwLock<Option<TlsAcceptor>>>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("Failed to accept proxy connection: {}", e);
+                continue;
+            }
+        };
+        let proxy = proxy.clone();
+        let acceptor = tls.read().unwrap().clone();
+        tauri::async_runtime::spawn(async move {
+            let result = match acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => serve_connection(stream, proxy, peer).await,
+                    Err(e) => Err(format!("TLS handshake failed: {}", e)),
+                },
+                None => serve_connection(stream, proxy, peer).await,
+            };
+            if let Err(e) = result {
+                tracing::warn!("Proxy connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn start_lan_proxy(
+    app: AppHandle,
+    state: State<'_, ProxyState>,
+    config: ProxyConfig,
+) -> Result<String, String> {
+    if config.clients.is_empty() {
+        return Err("At least one client token is required".to_string());
+    }
+    if config.clients.iter().any(|c| c.token.len() < 16) {
+        return Err("Client tokens must be at least 16 characters".to_string());
+    }
+    let acceptor = if config.tls {
+        Some(certs::acceptor(&certs::ensure_current(&app)?)?)
+    } else {
+        None
+    };
+    let listener = TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .map_err(|e| format!("Failed to start LAN proxy: {}", e))?;
+    let port = config.port;
+    let scheme = if acceptor.is_some() { "https" } else { "http" };
+    *state.tls.write().unwrap() = acceptor;
+    let host = certs::lan_ip().map_or_else(|| "localhost".to_string(), |ip| ip.to_string());
+    *state.public_url.lock().unwrap() = Some(format!("{}://{}:{}", scheme, host, port));
+    let proxy = Arc::new(Proxy {
+        app: app.clone(),
+        config,
+        http: reqwest::Client::new(),
+        buckets: Mutex::new(HashMap::new()),
+        log: state.log.clone(),
+    });
+
+    let handle = tauri::async_runtime::spawn(serve(listener, proxy, state.tls.clone()));
+    if let Some(previous) = state.server.lock().unwrap().replace(handle) {
+        previous.abort();
+    }
+    Ok(format!(
+        "LAN proxy listening on {}://0.0.0.0:{}",
+        scheme, port
+    ))
+}
+
+#[tauri::command]
+pub fn stop_lan_proxy(state: State<'_, ProxyState>) -> Result<String, String> {
+    match state.server.lock().unwrap().take() {
+        Some(handle) => {
+            handle.abort();
+            *state.tls.write().unwrap() = None;
+            *state.public_url.lock().unwrap() = None;
+            Ok("LAN proxy stopped".to_string())
+        }
+        None => Err("LAN proxy is not running".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_proxy_log(state: State<'_, ProxyState>, limit: Option<usize>) -> Vec<ProxyLogEntry> {
+    let log = state.log.lock().unwrap();
+    let limit = limit.unwrap_or(LOG_CAPACITY);
+    log.iter().rev().take(limit).cloned().collect()
+}
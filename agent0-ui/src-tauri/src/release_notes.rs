@@ -0,0 +1,293 @@
+// Release notes for the desktop app and the Agent-0 server, cached for offline reading
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::now_secs;
+use crate::{agent0, api, journal, storage};
+
+const CACHE_TTL_SECS: i64 = 6 * 3600;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    App,
+    Server,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ReleaseNotesSettings {
+    // JSON feed of releases; GitHub's releases API format is accepted too
+    #[serde(default)]
+    app_url: Option<String>,
+    #[serde(default)]
+    server_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Release {
+    #[serde(alias = "tag_name")]
+    version: String,
+    #[serde(default, alias = "name")]
+    title: Option<String>,
+    #[serde(default, alias = "published_at")]
+    date: Option<String>,
+    #[serde(default, alias = "body")]
+    notes: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct CachedFeed {
+    url: String,
+    fetched_at: i64,
+    releases: Vec<Release>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct NotesState {
+    cache: BTreeMap<Component, CachedFeed>,
+    // Newest version the operator has seen notes for
+    seen: BTreeMap<Component, String>,
+}
+
+#[derive(Serialize)]
+pub struct ReleaseNotes {
+    component: Component,
+    releases: Vec<Release>,
+    // Set when the feed could not be refreshed and cached notes were served
+    stale: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct UnseenNotes {
+    component: Component,
+    from: Option<String>,
+    to: String,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("release_notes.json"))
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("release_notes_cache.json"))
+}
+
+fn load_state(app: &AppHandle) -> Result<NotesState, String> {
+    storage::read_json(&state_path(app)?)
+}
+
+// "v1.4.0-rc1" compares as [1, 4, 0]; pre-release suffixes are ignored
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches(|c| c == 'v' || c == 'V')
+        .split(|c| c == '-' || c == '+')
+        .next()
+        .unwrap_or("")
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+// "1.2.0..1.4.0", "1.2.0.." or "..1.4.0"; the lower bound is exclusive so
+// "<installed before>..<installed now>" lists exactly what changed
+fn in_range(version: &str, range: Option<&str>) -> Result<bool, String> {
+    let range = match range {
+        Some(range) if !range.trim().is_empty() => range,
+        _ => return Ok(true),
+    };
+    let (from, to) = range
+        .split_once("..")
+        .ok_or_else(|| format!("Invalid version range '{}', expected from..to", range))?;
+    let key = version_key(version);
+    let above = from.trim().is_empty() || key > version_key(from.trim());
+    let below = to.trim().is_empty() || key <= version_key(to.trim());
+    Ok(above && below)
+}
+
+fn feed_url(app: &AppHandle, component: Component) -> Result<String, String> {
+    let settings: ReleaseNotesSettings = storage::read_json(&settings_path(app)?)?;
+    let configured = match component {
+        Component::App => settings.app_url,
+        Component::Server => settings.server_url,
+    };
+    match (configured, component) {
+        (Some(url), _) => Ok(url),
+        // Serves the running server's own notes when no URL is configured
+        (None, Component::Server) => Ok(format!(
+            "{}{}",
+            agent0::base_url(),
+            api::paths::GET_RELEASE_NOTES
+        )),
+        (None, Component::App) => Err("No release notes URL is configured for the app".to_string()),
+    }
+}
+
+// The running server's own feed goes through the Agent-0 client; other feeds are external
+async fn fetch(url: &str) -> Result<Vec<Release>, String> {
+    let base = agent0::base_url();
+    let value: Value = if url == format!("{}{}", base, api::paths::GET_RELEASE_NOTES) {
+        api::get_release_notes(&base).await?
+    } else {
+        reqwest::Client::new()
+            .get(url)
+            .timeout(FETCH_TIMEOUT)
+            .header(reqwest::header::USER_AGENT, "agent0-desktop")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch release notes: {}", e))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to fetch release notes: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid release notes from {}: {}", url, e))?
+    };
+    let list = match value {
+        Value::Object(mut map) => map.remove("releases").unwrap_or(Value::Null),
+        other => other,
+    };
+    serde_json::from_value(list).map_err(|e| format!("Invalid release notes from {}: {}", url, e))
+}
+
+async fn releases(
+    app: &AppHandle,
+    component: Component,
+) -> Result<(Vec<Release>, Option<String>), String> {
+    let url = feed_url(app, component)?;
+    let mut state = load_state(app)?;
+    let cached = state
+        .cache
+        .get(&component)
+        .filter(|c| c.url == url)
+        .cloned();
+    if let Some(cached) = &cached {
+        if now_secs() - cached.fetched_at < CACHE_TTL_SECS {
+            return Ok((cached.releases.clone(), None));
+        }
+    }
+    match fetch(&url).await {
+        Ok(releases) => {
+            state.cache.insert(
+                component,
+                CachedFeed {
+                    url,
+                    fetched_at: now_secs(),
+                    releases: releases.clone(),
+                },
+            );
+            storage::write_json(&state_path(app)?, &state)?;
+            Ok((releases, None))
+        }
+        Err(e) => match cached {
+            Some(cached) => Ok((cached.releases, Some(e))),
+            None => Err(e),
+        },
+    }
+}
+
+async fn installed_version(app: &AppHandle, component: Component) -> Result<String, String> {
+    match component {
+        Component::App => Ok(app.package_info().version.to_string()),
+        Component::Server => Ok(api::get_version(&agent0::base_url()).await?.version),
+    }
+}
+
+// Versions that changed since notes were last marked seen
+async fn unseen(app: &AppHandle) -> Result<Vec<UnseenNotes>, String> {
+    let state = load_state(app)?;
+    let mut out = Vec::new();
+    for component in [Component::App, Component::Server] {
+        let current = match installed_version(app, component).await {
+            Ok(version) => version,
+            Err(_) => continue,
+        };
+        let seen = state.seen.get(&component).cloned();
+        if seen
+            .as_deref()
+            .map_or(true, |s| version_key(s) < version_key(&current))
+        {
+            out.push(UnseenNotes {
+                component,
+                from: seen,
+                to: current,
+            });
+        }
+    }
+    Ok(out)
+}
+
+// After an update, tell the webview so it can show what changed
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        match unseen(&app).await {
+            Ok(pending) if !pending.is_empty() => {
+                let _ = app.emit_all("release-notes-available", &pending);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to check for release notes: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_release_notes(
+    app: AppHandle,
+    component: Component,
+    version_range: Option<String>,
+) -> Result<ReleaseNotes, String> {
+    let (all, stale) = releases(&app, component).await?;
+    let mut selected = Vec::new();
+    for release in all {
+        if in_range(&release.version, version_range.as_deref())? {
+            selected.push(release);
+        }
+    }
+    selected.sort_by_key(|r| std::cmp::Reverse(version_key(&r.version)));
+    Ok(ReleaseNotes {
+        component,
+        releases: selected,
+        stale,
+    })
+}
+
+#[tauri::command]
+pub async fn get_unseen_release_notes(app: AppHandle) -> Result<Vec<UnseenNotes>, String> {
+    unseen(&app).await
+}
+
+#[tauri::command]
+pub async fn mark_release_notes_seen(app: AppHandle, component: Component) -> Result<(), String> {
+    let version = installed_version(&app, component).await?;
+    let mut state = load_state(&app)?;
+    state.seen.insert(component, version);
+    storage::write_json(&state_path(&app)?, &state)
+}
+
+#[tauri::command]
+pub fn get_release_notes_settings(app: AppHandle) -> Result<ReleaseNotesSettings, String> {
+    storage::read_json(&settings_path(&app)?)
+}
+
+#[tauri::command]
+pub fn set_release_notes_settings(
+    app: AppHandle,
+    settings: ReleaseNotesSettings,
+) -> Result<(), String> {
+    for url in settings.app_url.iter().chain(settings.server_url.iter()) {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| format!("Invalid release notes URL '{}': {}", url, e))?;
+        if parsed.scheme() != "https" && parsed.scheme() != "http" {
+            return Err(format!("Release notes URL '{}' must be http(s)", url));
+        }
+    }
+    journal::write_json(
+        &app,
+        "Edit release notes sources",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
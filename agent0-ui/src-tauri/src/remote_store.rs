@@ -0,0 +1,248 @@
+// Blob storage on a user-provided WebDAV or S3-compatible endpoint, for remote sync
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::keychain;
+
+pub const SECRET_NAME: &str = "remote_sync_secret";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Remote {
+    // A collection URL such as https://dav.example.com/agent0/; the password is in the keychain
+    WebDav {
+        url: String,
+        username: String,
+    },
+    // Path-style addressing so self-hosted servers (MinIO, Garage) need no DNS setup
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+        access_key_id: String,
+    },
+}
+
+pub struct Blob {
+    pub bytes: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+pub enum PutOutcome {
+    Stored,
+    // The If-Match or If-None-Match precondition failed: someone else wrote in between
+    Conflict,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 64;
+    let mut key = if key.len() > BLOCK {
+        Sha256::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK, 0);
+    let inner: Vec<u8> = key.iter().map(|b| b ^ 0x36).collect();
+    let outer: Vec<u8> = key.iter().map(|b| b ^ 0x5c).collect();
+    let mut hasher = Sha256::new();
+    hasher.update(&inner);
+    hasher.update(data);
+    let inner_hash = hasher.finalize();
+    let mut hasher = Sha256::new();
+    hasher.update(&outer);
+    hasher.update(inner_hash);
+    hasher.finalize().to_vec()
+}
+
+// RFC 3986 unreserved characters pass; everything else is percent-encoded, as SigV4 requires
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn secret() -> Result<String, String> {
+    keychain::get(SECRET_NAME)?.ok_or_else(|| "No remote sync secret is stored".to_string())
+}
+
+impl Remote {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = match self {
+            Remote::WebDav { url, .. } => url,
+            Remote::S3 {
+                endpoint,
+                bucket,
+                region,
+                ..
+            } => {
+                if bucket.is_empty() || region.is_empty() {
+                    return Err("S3 bucket and region are required".to_string());
+                }
+                endpoint
+            }
+        };
+        reqwest::Url::parse(url)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid remote URL '{}': {}", url, e))
+    }
+
+    fn url(&self, name: &str) -> (String, String) {
+        match self {
+            Remote::WebDav { url, .. } => {
+                let url = format!("{}/{}", url.trim_end_matches('/'), uri_encode(name));
+                (url, String::new())
+            }
+            Remote::S3 {
+                endpoint,
+                bucket,
+                prefix,
+                ..
+            } => {
+                let key = format!("{}{}", prefix, name);
+                let path = std::iter::once(bucket.as_str())
+                    .chain(key.split('/'))
+                    .map(uri_encode)
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let path = format!("/{}", path);
+                (format!("{}{}", endpoint.trim_end_matches('/'), path), path)
+            }
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        name: &str,
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let (url, canonical_path) = self.url(name);
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let builder = client.request(method.clone(), &url);
+        Ok(match self {
+            Remote::WebDav { username, .. } => builder.basic_auth(username, Some(secret()?)),
+            Remote::S3 {
+                region,
+                access_key_id,
+                ..
+            } => {
+                let host = reqwest::Url::parse(&url)
+                    .ok()
+                    .and_then(|u| {
+                        u.host_str().map(|h| match u.port() {
+                            Some(port) => format!("{}:{}", h, port),
+                            None => h.to_string(),
+                        })
+                    })
+                    .ok_or_else(|| format!("Invalid S3 URL {}", url))?;
+                let now = Utc::now();
+                let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let date = now.format("%Y%m%d").to_string();
+                let payload_hash = sha256_hex(body);
+                let canonical = format!(
+                    "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+                    method, canonical_path, host, payload_hash, amz_date, payload_hash
+                );
+                let scope = format!("{}/{}/s3/aws4_request", date, region);
+                let to_sign = format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                    amz_date,
+                    scope,
+                    sha256_hex(canonical.as_bytes())
+                );
+                let key = [date.as_str(), region.as_str(), "s3", "aws4_request"]
+                    .iter()
+                    .fold(format!("AWS4{}", secret()?).into_bytes(), |key, part| {
+                        hmac(&key, part.as_bytes())
+                    });
+                let signature = hex::encode(hmac(&key, to_sign.as_bytes()));
+                builder
+                    .header("x-amz-date", amz_date)
+                    .header("x-amz-content-sha256", payload_hash)
+                    .header(
+                        reqwest::header::AUTHORIZATION,
+                        format!(
+                            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                            access_key_id, scope, signature
+                        ),
+                    )
+            }
+        })
+    }
+
+    // None when the object does not exist yet
+    pub async fn get(&self, name: &str) -> Result<Option<Blob>, String> {
+        let response = self
+            .request(reqwest::Method::GET, name, &[])?
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach sync remote: {}", e))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!(
+                "Sync remote returned {} for {}",
+                response.status(),
+                name
+            ));
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", name, e))?;
+        Ok(Some(Blob {
+            bytes: bytes.to_vec(),
+            etag,
+        }))
+    }
+
+    // `if_match` of Some(Some(etag)) writes only over that version, Some(None) only if the
+    // object does not exist yet, and None unconditionally
+    pub async fn put(
+        &self,
+        name: &str,
+        bytes: Vec<u8>,
+        if_match: Option<Option<&str>>,
+    ) -> Result<PutOutcome, String> {
+        let mut builder = self.request(reqwest::Method::PUT, name, &bytes)?;
+        match if_match {
+            Some(Some(etag)) => builder = builder.header(reqwest::header::IF_MATCH, etag),
+            Some(None) => builder = builder.header(reqwest::header::IF_NONE_MATCH, "*"),
+            None => {}
+        }
+        let response = builder
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach sync remote: {}", e))?;
+        match response.status() {
+            reqwest::StatusCode::PRECONDITION_FAILED => Ok(PutOutcome::Conflict),
+            status if status.is_success() => Ok(PutOutcome::Stored),
+            status => Err(format!("Sync remote returned {} for {}", status, name)),
+        }
+    }
+}
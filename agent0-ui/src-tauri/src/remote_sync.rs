@@ -0,0 +1,689 @@
+// Differential sync of conversations and templates through a self-hosted remote, merged by vector clock
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::now_secs;
+use crate::progress::{self, Operation};
+use crate::remote_store::{PutOutcome, Remote, SECRET_NAME};
+use crate::{agent0, api, audit, journal, keychain, storage};
+
+const INDEX_OBJECT: &str = "index.json";
+const CONVERSATION_PREFIX: &str = "conversation/";
+const TEMPLATE_PREFIX: &str = "template/";
+const TICK: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RemoteSyncSettings {
+    remote: Option<Remote>,
+    // Background sync period; None syncs only when asked
+    interval_minutes: Option<u64>,
+}
+
+type Clock = BTreeMap<String, u64>;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RemoteRecord {
+    clock: Clock,
+    hash: String,
+    modified_at: i64,
+    device: String,
+}
+
+// The one object every sync reads and rewrites; records live in their own objects
+#[derive(Serialize, Deserialize, Default)]
+struct RemoteIndex {
+    records: BTreeMap<String, RemoteRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Payload {
+    Conversation { value: Value },
+    // Hex so binary templates survive the JSON envelope
+    Template { hex: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct LedgerEntry {
+    hash: String,
+    clock: Clock,
+    // The conversation's listed `updated_at` when it was agreed; 0 when unknown, as after a pull
+    #[serde(default)]
+    modified: i64,
+}
+
+// What this device last agreed with the remote, to tell local edits from remote ones
+#[derive(Serialize, Deserialize, Default)]
+struct Ledger {
+    device_id: String,
+    records: BTreeMap<String, LedgerEntry>,
+    last_synced_at: Option<i64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SyncConflict {
+    key: String,
+    // "local" or "remote"
+    kept: &'static str,
+    // The losing side, saved so nothing is silently lost
+    copy: String,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct RemoteSyncReport {
+    pushed: Vec<String>,
+    pulled: Vec<String>,
+    conflicts: Vec<SyncConflict>,
+    unchanged: usize,
+}
+
+#[derive(Serialize)]
+pub struct RemoteSyncStatus {
+    settings: RemoteSyncSettings,
+    device_id: String,
+    last_synced_at: Option<i64>,
+    has_secret: bool,
+}
+
+enum Order {
+    Same,
+    Before,
+    After,
+    Concurrent,
+}
+
+fn compare(a: &Clock, b: &Clock) -> Order {
+    let devices: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    let (mut less, mut greater) = (false, false);
+    for device in devices {
+        let (x, y) = (
+            a.get(device).copied().unwrap_or(0),
+            b.get(device).copied().unwrap_or(0),
+        );
+        match x.cmp(&y) {
+            Ordering::Less => less = true,
+            Ordering::Greater => greater = true,
+            Ordering::Equal => {}
+        }
+    }
+    match (less, greater) {
+        (false, false) => Order::Same,
+        (true, false) => Order::Before,
+        (false, true) => Order::After,
+        (true, true) => Order::Concurrent,
+    }
+}
+
+fn merge(a: &Clock, b: &Clock) -> Clock {
+    let mut merged = a.clone();
+    for (device, count) in b {
+        let entry = merged.entry(device.clone()).or_insert(0);
+        *entry = (*entry).max(*count);
+    }
+    merged
+}
+
+fn tick(clock: &Clock, device: &str) -> Clock {
+    let mut next = clock.clone();
+    *next.entry(device.to_string()).or_insert(0) += 1;
+    next
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("remote_sync.json"))
+}
+
+fn ledger_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("remote_sync_state.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<RemoteSyncSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+fn load_ledger(app: &AppHandle) -> Result<Ledger, String> {
+    let mut ledger: Ledger = storage::read_json(&ledger_path(app)?)?;
+    if ledger.device_id.is_empty() {
+        ledger.device_id = hex::encode(rand::random::<[u8; 8]>());
+        save_ledger(app, &ledger)?;
+    }
+    Ok(ledger)
+}
+
+fn save_ledger(app: &AppHandle, ledger: &Ledger) -> Result<(), String> {
+    storage::write_json(&ledger_path(app)?, ledger)
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(value).map_err(|e| format!("Failed to encode sync data: {}", e))
+}
+
+fn hash(payload: &Payload) -> Result<String, String> {
+    Ok(hex::encode(Sha256::digest(&encode(payload)?)))
+}
+
+fn object_name(key: &str) -> String {
+    format!(
+        "obj-{}.json",
+        &hex::encode(Sha256::digest(key.as_bytes()))[..32]
+    )
+}
+
+// Objects are named after their content, so uploading one never changes anything another
+// device has published; only the index decides which object a record points at, and superseded
+// objects are left unread
+fn content_object(hash: &str) -> String {
+    format!("blob-{}.json", hash)
+}
+
+fn template_path(templates: &Path, key: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(key.strip_prefix(TEMPLATE_PREFIX).unwrap_or(key));
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(format!(
+            "Refusing to sync template outside templates: {}",
+            key
+        ));
+    }
+    Ok(templates.join(relative))
+}
+
+// A record as this device has it; conversations listed as unchanged since the last sync are not
+// fetched and carry the hash the ledger agreed on
+struct LocalRecord {
+    payload: Option<Payload>,
+    hash: String,
+    // When it was last modified, where that is known (0 otherwise)
+    modified: i64,
+}
+
+type LocalRecords = BTreeMap<String, LocalRecord>;
+
+fn modified_secs(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn collect_templates(root: &Path, dir: &Path, found: &mut LocalRecords) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_templates(root, &path, found)?;
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|_| format!("{} is outside the templates", path.display()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let payload = Payload::Template {
+            hex: hex::encode(bytes),
+        };
+        found.insert(
+            format!("{}{}", TEMPLATE_PREFIX, relative),
+            LocalRecord {
+                hash: hash(&payload)?,
+                payload: Some(payload),
+                modified: entry.metadata().map_or(0, |m| modified_secs(&m)),
+            },
+        );
+    }
+    Ok(())
+}
+
+async fn local_records(app: &AppHandle, ledger: &Ledger) -> Result<LocalRecords, String> {
+    let mut records = BTreeMap::new();
+    let templates = storage::data_dir(app)?.join("templates");
+    collect_templates(&templates, &templates, &mut records)?;
+    let base = agent0::base_url();
+    for item in api::list_conversations(&base).await? {
+        let id = match item["id"].as_str() {
+            Some(id) => id,
+            None => continue,
+        };
+        let key = format!("{}{}", CONVERSATION_PREFIX, id);
+        let modified = item["updated_at"].as_i64().unwrap_or(0);
+        let record = match ledger.records.get(&key) {
+            Some(known) if modified > 0 && known.modified == modified => LocalRecord {
+                payload: None,
+                hash: known.hash.clone(),
+                modified,
+            },
+            _ => {
+                let payload = Payload::Conversation {
+                    value: api::get_conversation(&base, id).await?,
+                };
+                LocalRecord {
+                    hash: hash(&payload)?,
+                    payload: Some(payload),
+                    modified,
+                }
+            }
+        };
+        records.insert(key, record);
+    }
+    Ok(records)
+}
+
+// The record's payload, fetched now if the listing skipped it
+async fn local_payload(key: &str, record: &LocalRecord) -> Result<Payload, String> {
+    if let Some(payload) = &record.payload {
+        return Ok(payload.clone());
+    }
+    let id = key.strip_prefix(CONVERSATION_PREFIX).unwrap_or(key);
+    Ok(Payload::Conversation {
+        value: api::get_conversation(&agent0::base_url(), id).await?,
+    })
+}
+
+async fn apply(app: &AppHandle, key: &str, payload: &Payload) -> Result<(), String> {
+    match payload {
+        // Stored under the id in the key even when new here, so every device keeps the same key
+        Payload::Conversation { value } => {
+            let id = key.strip_prefix(CONVERSATION_PREFIX).unwrap_or(key);
+            api::update_conversation(&agent0::base_url(), id, value).await
+        }
+        Payload::Template { hex } => {
+            let bytes = hex::decode(hex).map_err(|e| format!("Corrupt template {}: {}", key, e))?;
+            let path = template_path(&storage::data_dir(app)?.join("templates"), key)?;
+            storage::check_writable(&path)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", key, e))
+        }
+    }
+}
+
+// The losing side of a conflict goes next to the data dir, never over anything
+fn save_copy(
+    app: &AppHandle,
+    key: &str,
+    device: &str,
+    payload: &Payload,
+) -> Result<String, String> {
+    let path = storage::data_dir(app)?.join("sync_conflicts").join(format!(
+        "{}-{}-{}.json",
+        &object_name(key)[4..20],
+        device,
+        now_secs()
+    ));
+    storage::write_json(&path, &json!({ "key": key, "payload": payload }))?;
+    Ok(path.display().to_string())
+}
+
+async fn download(remote: &Remote, key: &str, record: &RemoteRecord) -> Result<Payload, String> {
+    let blob = remote
+        .get(&content_object(&record.hash))
+        .await?
+        .ok_or_else(|| format!("Sync remote lists {} but has no data for it", key))?;
+    serde_json::from_slice(&blob.bytes).map_err(|e| format!("Corrupt remote record {}: {}", key, e))
+}
+
+async fn sync(app: &AppHandle, operation: &Operation) -> Result<RemoteSyncReport, String> {
+    let remote = load_settings(app)?
+        .remote
+        .ok_or_else(|| "No sync remote is configured".to_string())?;
+    let mut ledger = load_ledger(app)?;
+    let device = ledger.device_id.clone();
+    operation.report("collecting", None);
+    let local = local_records(app, &ledger).await?;
+    operation.report("fetching index", None);
+    let (mut index, etag) = match remote.get(INDEX_OBJECT).await? {
+        Some(blob) => (
+            serde_json::from_slice::<RemoteIndex>(&blob.bytes)
+                .map_err(|e| format!("Corrupt sync index: {}", e))?,
+            Some(blob.etag),
+        ),
+        None => (RemoteIndex::default(), None),
+    };
+
+    let mut report = RemoteSyncReport::default();
+    // Pushed records only count as agreed once the index naming them is published
+    let mut published: Vec<(String, LedgerEntry)> = Vec::new();
+    let keys: BTreeSet<String> = local.keys().chain(index.records.keys()).cloned().collect();
+    let total = keys.len() as u64;
+    for (done, key) in keys.into_iter().enumerate() {
+        operation.report_ratio("syncing", done as u64, total);
+        let known = ledger.records.get(&key).cloned();
+        let remote_record = index.records.get(&key).cloned();
+        let ours = local.get(&key);
+        // An edit since the last sync is a new event on this device's clock
+        let our_clock = match (ours, &known) {
+            (Some(ours), Some(k)) if ours.hash == k.hash => k.clock.clone(),
+            (Some(_), Some(k)) => tick(&k.clock, &device),
+            (Some(_), None) => tick(&Clock::new(), &device),
+            (None, Some(k)) => k.clock.clone(),
+            (None, None) => Clock::new(),
+        };
+        let push = match (ours, &remote_record) {
+            // Deletions are not propagated; a record deleted here stays deleted unless edited elsewhere
+            (None, Some(theirs)) => {
+                if known.is_some() && !matches!(compare(&theirs.clock, &our_clock), Order::After) {
+                    report.unchanged += 1;
+                    continue;
+                }
+                let payload = download(&remote, &key, theirs).await?;
+                apply(app, &key, &payload).await?;
+                report.pulled.push(key.clone());
+                ledger.records.insert(
+                    key.clone(),
+                    LedgerEntry {
+                        hash: theirs.hash.clone(),
+                        clock: theirs.clock.clone(),
+                        modified: 0,
+                    },
+                );
+                // Saved per item so an error later in the run does not re-pull it as new
+                save_ledger(app, &ledger)?;
+                continue;
+            }
+            (Some(_), None) => true,
+            (Some(ours), Some(theirs)) => {
+                match compare(&our_clock, &theirs.clock) {
+                    Order::Same => {
+                        // Remembered so the next listing can skip fetching it
+                        if let Some(k) = ledger.records.get_mut(&key) {
+                            if k.hash == ours.hash {
+                                k.modified = ours.modified;
+                            }
+                        }
+                        report.unchanged += 1;
+                        continue;
+                    }
+                    Order::After => true,
+                    Order::Before => {
+                        let payload = download(&remote, &key, theirs).await?;
+                        apply(app, &key, &payload).await?;
+                        report.pulled.push(key.clone());
+                        ledger.records.insert(
+                            key.clone(),
+                            LedgerEntry {
+                                hash: theirs.hash.clone(),
+                                clock: theirs.clock.clone(),
+                                modified: 0,
+                            },
+                        );
+                        save_ledger(app, &ledger)?;
+                        continue;
+                    }
+                    Order::Concurrent if ours.hash == theirs.hash => true,
+                    // Last writer wins, with the device id breaking ties so both sides agree
+                    Order::Concurrent => {
+                        let local_wins =
+                            (ours.modified, &device) > (theirs.modified_at, &theirs.device);
+                        let theirs_payload = download(&remote, &key, theirs).await?;
+                        if local_wins {
+                            let copy = save_copy(app, &key, &theirs.device, &theirs_payload)?;
+                            report.conflicts.push(SyncConflict {
+                                key: key.clone(),
+                                kept: "local",
+                                copy,
+                            });
+                            true
+                        } else {
+                            let ours = local_payload(&key, ours).await?;
+                            let copy = save_copy(app, &key, &device, &ours)?;
+                            apply(app, &key, &theirs_payload).await?;
+                            report.conflicts.push(SyncConflict {
+                                key: key.clone(),
+                                kept: "remote",
+                                copy,
+                            });
+                            ledger.records.insert(
+                                key.clone(),
+                                LedgerEntry {
+                                    hash: theirs.hash.clone(),
+                                    clock: merge(&our_clock, &theirs.clock),
+                                    modified: 0,
+                                },
+                            );
+                            save_ledger(app, &ledger)?;
+                            // Record the merged clock so the other side sees the conflict as settled
+                            index.records.insert(
+                                key.clone(),
+                                RemoteRecord {
+                                    clock: merge(&our_clock, &theirs.clock),
+                                    ..theirs.clone()
+                                },
+                            );
+                            continue;
+                        }
+                    }
+                }
+            }
+            (None, None) => continue,
+        };
+        if let (true, Some(ours)) = (push, ours) {
+            let clock = match &remote_record {
+                Some(theirs) => merge(&our_clock, &theirs.clock),
+                None => our_clock.clone(),
+            };
+            let clock = match &remote_record {
+                Some(theirs) if !matches!(compare(&our_clock, &theirs.clock), Order::After) => {
+                    tick(&clock, &device)
+                }
+                _ => clock,
+            };
+            // Already there means another device uploaded the same content
+            let payload = local_payload(&key, ours).await?;
+            remote
+                .put(&content_object(&ours.hash), encode(&payload)?, Some(None))
+                .await?;
+            index.records.insert(
+                key.clone(),
+                RemoteRecord {
+                    clock: clock.clone(),
+                    hash: ours.hash.clone(),
+                    modified_at: if ours.modified > 0 {
+                        ours.modified
+                    } else {
+                        now_secs()
+                    },
+                    device: device.clone(),
+                },
+            );
+            published.push((
+                key.clone(),
+                LedgerEntry {
+                    hash: ours.hash.clone(),
+                    clock,
+                    modified: ours.modified,
+                },
+            ));
+            report.pushed.push(key.clone());
+        }
+    }
+
+    // Another workstation rewriting the index in between fails the precondition; the next run merges it
+    if !report.pushed.is_empty() || !report.conflicts.is_empty() {
+        operation.report("publishing index", Some(100.0));
+        let condition = match &etag {
+            None => Some(None),
+            Some(Some(etag)) => Some(Some(etag.as_str())),
+            // Servers that send no ETag cannot be written conditionally
+            Some(None) => None,
+        };
+        if let PutOutcome::Conflict = remote.put(INDEX_OBJECT, encode(&index)?, condition).await? {
+            return Err("The sync remote changed during this sync; try again".to_string());
+        }
+    }
+    ledger.records.extend(published);
+    ledger.last_synced_at = Some(now_secs());
+    save_ledger(app, &ledger)?;
+    Ok(report)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(TICK);
+        loop {
+            ticker.tick().await;
+            let settings = match load_settings(&app) {
+                Ok(settings) => settings,
+                Err(_) => continue,
+            };
+            let minutes = match (settings.remote.as_ref(), settings.interval_minutes) {
+                (Some(_), Some(minutes)) if minutes > 0 => minutes,
+                _ => continue,
+            };
+            let last = load_ledger(&app)
+                .ok()
+                .and_then(|l| l.last_synced_at)
+                .unwrap_or(0);
+            if now_secs() - last < (minutes * 60) as i64 {
+                continue;
+            }
+            match sync(&app, &Operation::untracked("remote_sync")).await {
+                Ok(report) => {
+                    let _ = app.emit_all("remote-sync-finished", &report);
+                }
+                Err(e) => tracing::warn!("Remote sync failed: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_remote_sync_status(app: AppHandle) -> Result<RemoteSyncStatus, String> {
+    let ledger = load_ledger(&app)?;
+    Ok(RemoteSyncStatus {
+        settings: load_settings(&app)?,
+        device_id: ledger.device_id,
+        last_synced_at: ledger.last_synced_at,
+        has_secret: keychain::get(SECRET_NAME)?.is_some(),
+    })
+}
+
+// `secret` replaces the stored password or S3 secret key when given; empty removes it
+#[tauri::command]
+pub fn set_remote_sync_settings(
+    app: AppHandle,
+    settings: RemoteSyncSettings,
+    secret: Option<String>,
+) -> Result<(), String> {
+    if let Some(remote) = &settings.remote {
+        remote.validate()?;
+    }
+    match secret.as_deref() {
+        Some("") => keychain::delete(SECRET_NAME)?,
+        Some(secret) => keychain::set(SECRET_NAME, secret)?,
+        None => {}
+    }
+    journal::write_json(
+        &app,
+        "Edit remote sync settings",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
+
+// Returns an operation id; the report arrives with its final progress event
+#[tauri::command]
+pub fn sync_remote_now(app: AppHandle) -> String {
+    let handle = app.clone();
+    progress::spawn(&app, "remote_sync", move |operation| async move {
+        let app = handle;
+        let result = sync(&app, &operation).await;
+        audit::record(
+            &app,
+            "remote_sync",
+            "sync_remote_now",
+            &Value::Null,
+            &result
+                .as_ref()
+                .map(|r| json!({ "pushed": r.pushed.len(), "pulled": r.pulled.len() }))
+                .map_err(|e| e.clone()),
+        );
+        progress::encode(result?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(counts: &[(&str, u64)]) -> Clock {
+        counts.iter().map(|(d, c)| (d.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn equal_clocks_are_the_same() {
+        let a = clock(&[("a", 2), ("b", 1)]);
+        assert!(matches!(compare(&a, &a.clone()), Order::Same));
+        assert!(matches!(compare(&Clock::new(), &Clock::new()), Order::Same));
+    }
+
+    #[test]
+    fn missing_devices_count_as_zero() {
+        let a = clock(&[("a", 1)]);
+        let b = clock(&[("a", 1), ("b", 0)]);
+        assert!(matches!(compare(&a, &b), Order::Same));
+        assert!(matches!(compare(&Clock::new(), &a), Order::Before));
+        assert!(matches!(compare(&a, &Clock::new()), Order::After));
+    }
+
+    #[test]
+    fn a_clock_behind_on_every_device_is_before() {
+        let a = clock(&[("a", 1), ("b", 2)]);
+        let b = clock(&[("a", 2), ("b", 2)]);
+        assert!(matches!(compare(&a, &b), Order::Before));
+        assert!(matches!(compare(&b, &a), Order::After));
+    }
+
+    #[test]
+    fn edits_on_both_devices_are_concurrent() {
+        let a = clock(&[("a", 2), ("b", 1)]);
+        let b = clock(&[("a", 1), ("b", 2)]);
+        assert!(matches!(compare(&a, &b), Order::Concurrent));
+        assert!(matches!(compare(&b, &a), Order::Concurrent));
+    }
+
+    #[test]
+    fn merge_takes_the_highest_count_per_device() {
+        let a = clock(&[("a", 3), ("b", 1)]);
+        let b = clock(&[("b", 4), ("c", 2)]);
+        assert_eq!(merge(&a, &b), clock(&[("a", 3), ("b", 4), ("c", 2)]));
+        assert!(matches!(compare(&merge(&a, &b), &a), Order::After));
+        assert!(matches!(compare(&merge(&a, &b), &b), Order::After));
+    }
+
+    #[test]
+    fn tick_advances_only_this_device() {
+        let a = clock(&[("a", 1), ("b", 5)]);
+        assert_eq!(tick(&a, "a"), clock(&[("a", 2), ("b", 5)]));
+        assert_eq!(tick(&Clock::new(), "c"), clock(&[("c", 1)]));
+        assert!(matches!(compare(&tick(&a, "b"), &a), Order::After));
+    }
+
+    #[test]
+    fn ticking_a_merged_clock_supersedes_both_sides() {
+        let ours = clock(&[("a", 2), ("b", 1)]);
+        let theirs = clock(&[("a", 1), ("b", 2)]);
+        let settled = tick(&merge(&ours, &theirs), "a");
+        assert!(matches!(compare(&settled, &ours), Order::After));
+        assert!(matches!(compare(&settled, &theirs), Order::After));
+    }
+}
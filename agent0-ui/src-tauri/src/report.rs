@@ -0,0 +1,525 @@
+// Weekly trend report written as HTML or PDF and optionally emailed on a schedule
+use chrono::{Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::alerts::AlertState;
+use crate::compare::{self, WindowSummary};
+use crate::formatting::Formatter;
+use crate::metrics::{now_secs, MetricsStore, TimeRange};
+use crate::progress::{self, Operation};
+use crate::schedule::{self, Zone};
+use crate::smtp::{self, EmailAttachment};
+use crate::{energy, journal, pdf, snapshot, storage, telemetry};
+
+const WEEK_SECS: i64 = 7 * 86400;
+// Drawn beside HTML reports
+const CHART_PANELS: [&str; 3] = ["latency", "qps", "availability"];
+// How often a disabled schedule is re-read
+const IDLE_RECHECK: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Html,
+    Pdf,
+}
+
+impl ReportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReportSettings {
+    enabled: bool,
+    // 0 = Monday, read in `timezone`, as in schedule::TimeWindow
+    weekday: u32,
+    hour: u32,
+    // IANA name; None follows the system clock
+    timezone: Option<String>,
+    format: ReportFormat,
+    // Sent through the SMTP settings when set
+    email: bool,
+    // Reports land in the data directory's "reports" folder when unset
+    export_dir: Option<String>,
+}
+
+impl Default for ReportSettings {
+    fn default() -> Self {
+        ReportSettings {
+            enabled: false,
+            weekday: 0,
+            hour: 8,
+            timezone: None,
+            format: ReportFormat::Html,
+            email: false,
+            export_dir: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct ReportRow {
+    label: String,
+    value: String,
+    // Against the window of the same length just before
+    change: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ReportSection {
+    title: String,
+    rows: Vec<ReportRow>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Incident {
+    fired_at: String,
+    name: String,
+    message: String,
+    acknowledged: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct TrendReport {
+    range: TimeRange,
+    generated_at: i64,
+    title: String,
+    sections: Vec<ReportSection>,
+    incidents: Vec<Incident>,
+}
+
+#[derive(Serialize)]
+pub struct GeneratedReport {
+    path: String,
+    format: ReportFormat,
+    emailed: bool,
+    report: TrendReport,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("report.json"))
+}
+
+fn load_settings(app: &AppHandle) -> Result<ReportSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+fn previous(range: TimeRange) -> TimeRange {
+    let len = range.to - range.from;
+    TimeRange {
+        from: range.from - len,
+        to: range.from,
+    }
+}
+
+fn change(format: &Formatter, before: Option<f64>, after: Option<f64>) -> Option<String> {
+    let percent = compare::delta(before, after)?.percent?;
+    let sign = if percent > 0.0 { "+" } else { "" };
+    Some(format!("{}{} %", sign, format.number(percent, 1)))
+}
+
+fn row(label: &str, value: Option<String>, change: Option<String>) -> ReportRow {
+    ReportRow {
+        label: label.to_string(),
+        value: value.unwrap_or_else(|| "no data".to_string()),
+        change,
+    }
+}
+
+fn compile(app: &AppHandle, range: TimeRange) -> TrendReport {
+    let store = app.state::<MetricsStore>();
+    let format = Formatter::load(app);
+    let window = |series: &str| -> (WindowSummary, WindowSummary) {
+        (
+            compare::summarize(&store.range(series, range)),
+            compare::summarize(&store.range(series, previous(range))),
+        )
+    };
+
+    let (latency, latency_before) = window(telemetry::LATENCY_SERIES);
+    let ms = |v: f64| format.duration(v / 1000.0);
+    let (qps, qps_before) = window(telemetry::QPS_SERIES);
+    let (up, up_before) = window(telemetry::UP_SERIES);
+    let percent = |v: f64| format!("{} %", format.number(v * 100.0, 2));
+    let mut sections = vec![
+        ReportSection {
+            title: "Latency".to_string(),
+            rows: vec![
+                row(
+                    "Mean health latency",
+                    latency.mean.map(ms),
+                    change(&format, latency_before.mean, latency.mean),
+                ),
+                row(
+                    "p95 health latency",
+                    latency.p95.map(ms),
+                    change(&format, latency_before.p95, latency.p95),
+                ),
+            ],
+        },
+        ReportSection {
+            title: "Traffic".to_string(),
+            rows: vec![
+                row(
+                    "Mean QPS",
+                    qps.mean.map(|v| format.number(v, 2)),
+                    change(&format, qps_before.mean, qps.mean),
+                ),
+                row(
+                    "Peak QPS",
+                    qps.max.map(|v| format.number(v, 2)),
+                    change(&format, qps_before.max, qps.max),
+                ),
+                row(
+                    "Availability",
+                    up.mean.map(percent),
+                    change(&format, up_before.mean, up.mean),
+                ),
+            ],
+        },
+    ];
+
+    let cost = energy::report(app, range);
+    let cost_before = energy::report(app, previous(range));
+    if let (Ok(now), Ok(before)) = (cost, cost_before) {
+        let covered = |r: &energy::EnergyReport| (r.covered_seconds > 0).then(|| r.cost);
+        let kwh = |r: &energy::EnergyReport| (r.covered_seconds > 0).then(|| r.kwh);
+        sections.push(ReportSection {
+            title: "Cost".to_string(),
+            rows: vec![
+                row(
+                    "Energy",
+                    kwh(&now).map(|v| format!("{} kWh", format.number(v, 2))),
+                    change(&format, kwh(&before), kwh(&now)),
+                ),
+                row(
+                    "Energy cost",
+                    covered(&now).map(|v| format.currency(v, Some(&now.currency))),
+                    change(&format, covered(&before), covered(&now)),
+                ),
+            ],
+        });
+    }
+
+    // One sample per run that failed to start
+    let failures = |range| {
+        store
+            .range(telemetry::TRAINING_FAILED_SERIES, range)
+            .iter()
+            .filter(|s| s.value > 0.0)
+            .count() as f64
+    };
+    let (failed, failed_before) = (failures(range), failures(previous(range)));
+    sections.push(ReportSection {
+        title: "Training".to_string(),
+        rows: vec![row(
+            "Failed training runs",
+            Some(format.number(failed, 0)),
+            change(&format, Some(failed_before), Some(failed)),
+        )],
+    });
+
+    let incidents: Vec<Incident> = app
+        .state::<AlertState>()
+        .fired_between(range)
+        .into_iter()
+        .map(|e| Incident {
+            fired_at: format.timestamp(e.fired_at, false),
+            name: e.name,
+            message: e.message,
+            acknowledged: e.acknowledged,
+        })
+        .collect();
+    let incidents_before = app
+        .state::<AlertState>()
+        .fired_between(previous(range))
+        .len() as f64;
+    sections.push(ReportSection {
+        title: "Incidents".to_string(),
+        rows: vec![row(
+            "Alerts fired",
+            Some(format.number(incidents.len() as f64, 0)),
+            change(
+                &format,
+                Some(incidents_before),
+                Some(incidents.len() as f64),
+            ),
+        )],
+    });
+
+    TrendReport {
+        range,
+        generated_at: now_secs(),
+        title: format!(
+            "Agent-0 trends {} – {}",
+            format.timestamp(range.from, true),
+            format.timestamp(range.to, true)
+        ),
+        sections,
+        incidents,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// File names of the charts written beside `stem`; a chart that fails is left out
+fn write_charts(app: &AppHandle, range: TimeRange, dir: &Path, stem: &str) -> Vec<String> {
+    CHART_PANELS
+        .iter()
+        .filter_map(|panel| {
+            let filename = format!("{}-{}.png", stem, panel);
+            match snapshot::write(app, panel, range, &dir.join(&filename)) {
+                Ok(_) => Some(filename),
+                Err(e) => {
+                    tracing::warn!("Report chart '{}' failed: {}", panel, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn render_html(report: &TrendReport, charts: &[String]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:1.5em}}\
+         td,th{{border:1px solid #ccc;padding:4px 10px;text-align:left}}</style></head><body><h1>{0}</h1>\n",
+        escape(&report.title)
+    );
+    for section in &report.sections {
+        html.push_str(&format!(
+            "<h2>{}</h2><table><tr><th>Metric</th><th>This period</th><th>Change</th></tr>\n",
+            escape(&section.title)
+        ));
+        for row in &section.rows {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&row.label),
+                escape(&row.value),
+                escape(row.change.as_deref().unwrap_or("–"))
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    if !charts.is_empty() {
+        html.push_str("<h2>Charts</h2>\n");
+        for chart in charts {
+            html.push_str(&format!(
+                "<p><img src=\"{}\" alt=\"\"></p>\n",
+                escape(chart)
+            ));
+        }
+    }
+    if !report.incidents.is_empty() {
+        html.push_str("<h2>Alert log</h2><table><tr><th>Fired</th><th>Alert</th><th>Details</th><th>Acknowledged</th></tr>\n");
+        for incident in &report.incidents {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&incident.fired_at),
+                escape(&incident.name),
+                escape(&incident.message),
+                if incident.acknowledged { "yes" } else { "no" }
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+fn render_pdf(report: &TrendReport) -> Vec<u8> {
+    let mut lines = Vec::new();
+    for section in &report.sections {
+        lines.push(String::new());
+        lines.push(section.title.to_uppercase());
+        for row in &section.rows {
+            let change = row
+                .change
+                .as_ref()
+                .map(|c| format!(" ({})", c))
+                .unwrap_or_default();
+            lines.push(format!("  {}: {}{}", row.label, row.value, change));
+        }
+    }
+    if !report.incidents.is_empty() {
+        lines.push(String::new());
+        lines.push("ALERT LOG".to_string());
+        for incident in &report.incidents {
+            lines.push(format!(
+                "  {}  {}: {}",
+                incident.fired_at, incident.name, incident.message
+            ));
+        }
+    }
+    pdf::render(&report.title, &lines)
+}
+
+fn output_dir(app: &AppHandle, settings: &ReportSettings) -> Result<PathBuf, String> {
+    let dir = match &settings.export_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => storage::data_dir(app)?.join("reports"),
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+async fn generate(
+    app: &AppHandle,
+    range: TimeRange,
+    format: ReportFormat,
+    email: bool,
+    operation: &Operation,
+) -> Result<GeneratedReport, String> {
+    if range.to <= range.from {
+        return Err("Report range must end after it starts".to_string());
+    }
+    let settings = load_settings(app)?;
+    operation.report("compiling", Some(0.0));
+    let report = compile(app, range);
+    operation.check()?;
+    operation.report("rendering", Some(40.0));
+    let dir = output_dir(app, &settings)?;
+    let stem = format!("agent0-report-{}", Local::now().format("%Y-%m-%d-%H%M"));
+    // Email bodies cannot reach files beside the report, so they go without charts
+    let html = render_html(&report, &[]);
+    let bytes = match format {
+        ReportFormat::Html => {
+            render_html(&report, &write_charts(app, range, &dir, &stem)).into_bytes()
+        }
+        ReportFormat::Pdf => render_pdf(&report),
+    };
+    let filename = format!("{}.{}", stem, format.extension());
+    let path = dir.join(&filename);
+    operation.check()?;
+    operation.report("writing", Some(70.0));
+    fs::write(&path, &bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    if email {
+        operation.report("emailing", Some(80.0));
+        let smtp_settings = smtp::load_settings(app)?;
+        let attachment = (format == ReportFormat::Pdf).then(|| EmailAttachment {
+            filename,
+            content_type: "application/pdf".to_string(),
+            bytes,
+        });
+        let subject = report.title.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            smtp::send(&smtp_settings, &subject, html, attachment)
+        })
+        .await
+        .map_err(|e| format!("Email task failed: {}", e))??;
+    }
+    Ok(GeneratedReport {
+        path: path.display().to_string(),
+        format,
+        emailed: email,
+        report,
+    })
+}
+
+fn until_next_report(settings: &ReportSettings) -> Duration {
+    let now = Utc::now();
+    let (zone, time) = match (
+        Zone::parse(settings.timezone.as_deref()),
+        NaiveTime::from_hms_opt(settings.hour, 0, 0),
+    ) {
+        (Ok(zone), Some(time)) => (zone, time),
+        _ => return IDLE_RECHECK,
+    };
+    schedule::next_fire(zone, &[settings.weekday], time, now)
+        .and_then(|next| (next - now).to_std().ok())
+        .unwrap_or(IDLE_RECHECK)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = load_settings(&app).unwrap_or_default();
+            if !settings.enabled {
+                tokio::time::sleep(IDLE_RECHECK).await;
+                continue;
+            }
+            // Woken hourly at most, so a changed schedule takes effect within the hour
+            let wait = until_next_report(&settings);
+            if wait > IDLE_RECHECK {
+                tokio::time::sleep(IDLE_RECHECK).await;
+                continue;
+            }
+            tokio::time::sleep(wait).await;
+            let now = now_secs();
+            let range = TimeRange {
+                from: now - WEEK_SECS,
+                to: now,
+            };
+            let operation = Operation::untracked("report");
+            let generated = generate(&app, range, settings.format, settings.email, &operation);
+            if let Err(e) = generated.await {
+                tracing::warn!("Weekly report failed: {}", e);
+            }
+        }
+    });
+}
+
+// Defaults to the last seven days in the configured format; returns an operation id
+#[tauri::command]
+pub fn generate_report(
+    app: AppHandle,
+    range: Option<TimeRange>,
+    format: Option<ReportFormat>,
+    email: Option<bool>,
+) -> Result<String, String> {
+    let settings = load_settings(&app)?;
+    let range = range.unwrap_or_else(|| {
+        let now = now_secs();
+        TimeRange {
+            from: now - WEEK_SECS,
+            to: now,
+        }
+    });
+    let format = format.unwrap_or(settings.format);
+    let email = email.unwrap_or(false);
+    let handle = app.clone();
+    Ok(progress::spawn(
+        &app,
+        "report",
+        move |operation| async move {
+            progress::encode(generate(&handle, range, format, email, &operation).await?)
+        },
+    ))
+}
+
+#[tauri::command]
+pub fn get_report_settings(app: AppHandle) -> Result<ReportSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_report_settings(app: AppHandle, settings: ReportSettings) -> Result<(), String> {
+    if settings.weekday > 6 || settings.hour > 23 {
+        return Err(
+            "Report schedule needs a weekday from 0 to 6 and an hour from 0 to 23".to_string(),
+        );
+    }
+    Zone::parse(settings.timezone.as_deref())?;
+    journal::write_json(
+        &app,
+        "Edit report schedule",
+        &settings_path(&app)?,
+        &settings,
+    )
+}
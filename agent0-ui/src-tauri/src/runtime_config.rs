@@ -0,0 +1,190 @@
+// Agent-0 feature flags and log levels, with timed automatic revert
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api::{self, LogLevelUpdate, RuntimeFlagUpdate};
+use crate::metrics::now_secs;
+use crate::{agent0, storage};
+
+const REVERT_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const LOG_LEVELS: [&str; 6] = ["trace", "debug", "info", "warning", "error", "critical"];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+    Flag { name: String },
+    LogLevel { logger: String },
+}
+
+// Value to restore once a temporary change expires
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingRevert {
+    target: Target,
+    // Reverts go to the server the change was made on, even after a profile switch
+    base_url: String,
+    previous: Value,
+    revert_at: i64,
+}
+
+#[derive(Serialize)]
+pub struct ServerFlags {
+    flags: Value,
+    pending_reverts: Vec<PendingRevert>,
+}
+
+fn reverts_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("runtime_reverts.json"))
+}
+
+fn load_reverts(app: &AppHandle) -> Result<Vec<PendingRevert>, String> {
+    storage::read_json(&reverts_path(app)?)
+}
+
+fn save_reverts(app: &AppHandle, reverts: &[PendingRevert]) -> Result<(), String> {
+    storage::write_json(&reverts_path(app)?, &reverts)
+}
+
+async fn current(base: &str, target: &Target) -> Result<Value, String> {
+    Ok(match target {
+        Target::Flag { name } => api::get_runtime_flags(base).await?[name].clone(),
+        Target::LogLevel { logger } => api::get_log_levels(base).await?[logger].clone(),
+    })
+}
+
+async fn write(base: &str, target: &Target, value: Value) -> Result<Value, String> {
+    match target {
+        Target::Flag { name } => {
+            let update = RuntimeFlagUpdate {
+                name: name.clone(),
+                value,
+            };
+            api::set_runtime_flag(base, &update).await
+        }
+        Target::LogLevel { logger } => {
+            let update = LogLevelUpdate {
+                logger: logger.clone(),
+                level: value,
+            };
+            api::set_log_level(base, &update).await
+        }
+    }
+}
+
+async fn change(
+    app: &AppHandle,
+    target: Target,
+    value: Value,
+    expires_in_secs: Option<u64>,
+) -> Result<Value, String> {
+    let base = agent0::base_url();
+    let mut reverts = load_reverts(app)?;
+    let existing = reverts
+        .iter()
+        .position(|r| r.target == target && r.base_url == base);
+    let pending = match (expires_in_secs, existing) {
+        // Extending a temporary change keeps the value from before the first one
+        (Some(secs), Some(index)) => Some(PendingRevert {
+            revert_at: now_secs() + secs as i64,
+            ..reverts.remove(index)
+        }),
+        (Some(secs), None) => Some(PendingRevert {
+            target: target.clone(),
+            base_url: base.clone(),
+            previous: current(&base, &target).await?,
+            revert_at: now_secs() + secs as i64,
+        }),
+        (None, Some(index)) => {
+            reverts.remove(index);
+            None
+        }
+        (None, None) => None,
+    };
+    let result = write(&base, &target, value).await?;
+    reverts.extend(pending);
+    save_reverts(app, &reverts)?;
+    Ok(result)
+}
+
+async fn revert_due(app: &AppHandle) -> Result<(), String> {
+    let now = now_secs();
+    let (due, mut remaining): (Vec<PendingRevert>, Vec<PendingRevert>) = load_reverts(app)?
+        .into_iter()
+        .partition(|r| r.revert_at <= now);
+    if due.is_empty() {
+        return Ok(());
+    }
+    for revert in due {
+        match write(&revert.base_url, &revert.target, revert.previous.clone()).await {
+            Ok(_) => {
+                let _ = app.emit_all("runtime-config-reverted", &revert);
+            }
+            // Keep it queued so an unreachable server is reverted once it returns
+            Err(e) => {
+                tracing::warn!("Failed to revert runtime config: {}", e);
+                remaining.push(revert);
+            }
+        }
+    }
+    save_reverts(app, &remaining)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(REVERT_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = revert_due(&app).await {
+                tracing::warn!("Runtime config revert check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_server_flags(app: AppHandle) -> Result<ServerFlags, String> {
+    let flags = api::get_runtime_flags(&agent0::base_url()).await?;
+    Ok(ServerFlags {
+        flags,
+        pending_reverts: load_reverts(&app)?,
+    })
+}
+
+#[tauri::command]
+pub async fn set_server_flag(
+    app: AppHandle,
+    name: String,
+    value: Value,
+    expires_in_secs: Option<u64>,
+) -> Result<Value, String> {
+    if name.trim().is_empty() {
+        return Err("Flag name cannot be empty".to_string());
+    }
+    change(&app, Target::Flag { name }, value, expires_in_secs).await
+}
+
+#[tauri::command]
+pub async fn set_server_log_level(
+    app: AppHandle,
+    logger: String,
+    level: String,
+    expires_in_secs: Option<u64>,
+) -> Result<Value, String> {
+    let level = level.to_lowercase();
+    if !LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!(
+            "Unknown log level '{}', expected one of {}",
+            level,
+            LOG_LEVELS.join(", ")
+        ));
+    }
+    change(
+        &app,
+        Target::LogLevel { logger },
+        Value::from(level),
+        expires_in_secs,
+    )
+    .await
+}
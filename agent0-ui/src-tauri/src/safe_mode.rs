@@ -0,0 +1,242 @@
+// Safe mode: boots on defaults with read-only stores when settings or data files are corrupt
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+use crate::metrics::now_secs;
+use crate::{audit, journal, notify, storage};
+
+// Checked when first read instead, since parsing them before the tray shows is too slow
+const LAZY_FILES: [&str; 1] = ["metrics.json"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairAction {
+    // Moves the file aside so its defaults apply; the broken copy is kept next to it
+    Reset,
+    // Writes back the content last saved through the change journal
+    RestoreLastSaved,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Problem {
+    // Name under the data directory
+    file: String,
+    error: String,
+    // Filled in when the report is read, since the journal may itself be broken
+    actions: Vec<RepairAction>,
+    // Takes effect after a restart
+    repaired: Option<RepairAction>,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct SafeModeReport {
+    active: bool,
+    since: Option<i64>,
+    problems: Vec<Problem>,
+    // Why startup failed when no single file explains it
+    startup_error: Option<String>,
+}
+
+static REPORT: Lazy<RwLock<SafeModeReport>> = Lazy::new(|| RwLock::new(SafeModeReport::default()));
+
+pub fn active() -> bool {
+    REPORT.read().unwrap().active
+}
+
+fn activate(report: &mut SafeModeReport) {
+    if !report.active {
+        report.active = true;
+        report.since = Some(now_secs());
+        tracing::warn!("Entering safe mode: stores are read-only until restart");
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// Lists the file for repair and switches to safe mode if not already in it
+pub fn report_corrupt(path: &Path, error: &str) {
+    let file = file_name(path);
+    let mut report = REPORT.write().unwrap();
+    activate(&mut report);
+    if report.problems.iter().any(|p| p.file == file) {
+        return;
+    }
+    tracing::warn!("Safe mode: {} is corrupt: {}", file, error);
+    report.problems.push(Problem {
+        file,
+        error: error.to_string(),
+        actions: Vec::new(),
+        repaired: None,
+    });
+}
+
+// Refuses writes while in safe mode so nothing overwrites a file still awaiting repair
+pub fn check_writable(path: &Path) -> Result<(), String> {
+    if active() {
+        Err(format!("Safe mode is on; not writing {}", path.display()))
+    } else {
+        Ok(())
+    }
+}
+
+// Parses every settings file under the data directory before anything reads them
+pub fn check(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("Failed to check data directory: {}", e);
+            return;
+        }
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let name = file_name(&path);
+        if !name.ends_with(".json") || LAZY_FILES.contains(&name.as_str()) || !path.is_file() {
+            continue;
+        }
+        let parsed = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                serde_json::from_slice::<Value>(&bytes)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = parsed {
+            report_corrupt(&path, &e);
+        }
+    }
+}
+
+// For startup failures no single file explains, such as data written by a newer version
+pub fn enter(reason: String) {
+    let mut report = REPORT.write().unwrap();
+    activate(&mut report);
+    report.startup_error = Some(reason);
+}
+
+// Runs the startup config load, retrying it on defaults in safe mode when it fails
+pub fn boot<F: Fn() -> Result<(), String>>(load: F) -> Result<(), String> {
+    match load() {
+        Err(e) if !active() => {
+            tracing::warn!("Failed to load settings: {}", e);
+            enter(e);
+            load()
+        }
+        loaded => loaded,
+    }
+}
+
+// Startup used to fail silently, so say so once the tray is up
+pub fn announce(app: &AppHandle) {
+    if !active() {
+        return;
+    }
+    let count = REPORT.read().unwrap().problems.len();
+    let body = match count {
+        0 => "Settings failed to load. Defaults are in use and nothing will be saved.".to_string(),
+        1 => "1 file is corrupt. Defaults are in use and nothing will be saved.".to_string(),
+        n => format!(
+            "{} files are corrupt. Defaults are in use and nothing will be saved.",
+            n
+        ),
+    };
+    let handle = app.clone();
+    notify::show_with_actions(
+        app,
+        "Agent-0 started in safe mode".to_string(),
+        body,
+        vec![("review".to_string(), "Review".to_string())],
+        move |action| {
+            if let ("review" | "default", Some(window)) = (action, handle.get_window("main")) {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+                let _ = handle.emit_all("safe-mode-review", ());
+            }
+        },
+    );
+}
+
+#[tauri::command]
+pub fn get_safe_mode_report(app: AppHandle) -> SafeModeReport {
+    let mut report = REPORT.read().unwrap().clone();
+    for problem in &mut report.problems {
+        problem.actions = vec![RepairAction::Reset];
+        if journal::last_saved(&app, &problem.file).is_some() {
+            problem.actions.push(RepairAction::RestoreLastSaved);
+        }
+    }
+    report
+}
+
+fn repair(app: &AppHandle, file: &str, action: RepairAction) -> Result<Value, String> {
+    let path = storage::data_dir(app)?.join(file);
+    match action {
+        RepairAction::Reset => {
+            let aside = path.with_file_name(format!("{}.corrupt-{}", file, now_secs()));
+            match fs::rename(&path, &aside) {
+                Ok(()) => Ok(json!({ "moved_to": aside })),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(json!({ "moved_to": null })),
+                Err(e) => Err(format!("Failed to move {} aside: {}", path.display(), e)),
+            }
+        }
+        RepairAction::RestoreLastSaved => {
+            let value = journal::last_saved(app, file)
+                .ok_or_else(|| format!("No saved copy of {} in the change journal", file))?;
+            storage::write_json_unguarded(&path, &value)?;
+            Ok(json!({ "restored": file }))
+        }
+    }
+}
+
+// Repairs only touch files listed in the report; the app keeps its defaults until restarted
+#[tauri::command]
+pub fn repair_safe_mode_item(
+    app: AppHandle,
+    file: String,
+    action: RepairAction,
+) -> Result<SafeModeReport, String> {
+    if !REPORT
+        .read()
+        .unwrap()
+        .problems
+        .iter()
+        .any(|p| p.file == file)
+    {
+        return Err(format!("{} is not listed in the safe mode report", file));
+    }
+    let result = repair(&app, &file, action);
+    audit::record(
+        &app,
+        "safe_mode",
+        "repair_safe_mode_item",
+        &json!({ "file": file, "action": action }),
+        &result,
+    );
+    result?;
+    if let Some(problem) = REPORT
+        .write()
+        .unwrap()
+        .problems
+        .iter_mut()
+        .find(|p| p.file == file)
+    {
+        problem.repaired = Some(action);
+    }
+    Ok(get_safe_mode_report(app))
+}
+
+// Everything reloads from disk; safe mode comes back if a file is still broken
+#[tauri::command]
+pub fn restart_after_repair(app: AppHandle) {
+    app.restart();
+}
@@ -0,0 +1,287 @@
+// Recurring weekly time windows shared by the schedulers, read in an IANA timezone
+use chrono::{
+    DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// Longest stretch of wall-clock time a forward change has skipped (Samoa, 2011)
+const LONGEST_GAP_MINUTES: i64 = 24 * 60;
+const MAX_FIRE_TIMES: usize = 50;
+
+#[derive(Clone, Copy)]
+pub enum Zone {
+    // The system clock's zone, for schedules saved without one
+    System,
+    Named(Tz),
+}
+
+fn resolve_in<Z: TimeZone>(
+    zone: &Z,
+    local: NaiveDateTime,
+    earliest: bool,
+) -> Option<DateTime<Utc>> {
+    (0..=LONGEST_GAP_MINUTES).find_map(|minutes| {
+        let instant = match zone.from_local_datetime(&(local + Duration::minutes(minutes))) {
+            LocalResult::Single(instant) => instant,
+            LocalResult::Ambiguous(first, second) => {
+                if earliest {
+                    first
+                } else {
+                    second
+                }
+            }
+            LocalResult::None => return None,
+        };
+        Some(instant.with_timezone(&Utc))
+    })
+}
+
+impl Zone {
+    pub fn parse(name: Option<&str>) -> Result<Self, String> {
+        match name {
+            None => Ok(Zone::System),
+            Some(name) => name.parse::<Tz>().map(Zone::Named).map_err(|_| {
+                format!(
+                    "Unknown timezone '{}', expected an IANA name such as Europe/Berlin",
+                    name
+                )
+            }),
+        }
+    }
+
+    fn date_of(&self, instant: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Zone::System => instant.with_timezone(&Local).date_naive(),
+            Zone::Named(tz) => instant.with_timezone(tz).date_naive(),
+        }
+    }
+
+    // A repeated wall-clock time picks its first or second pass; a skipped one lands where
+    // the clock resumes
+    fn resolve(&self, local: NaiveDateTime, earliest: bool) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::System => resolve_in(&Local, local, earliest),
+            Zone::Named(tz) => resolve_in(tz, local, earliest),
+        }
+    }
+
+    fn wall_clock(&self, instant: DateTime<Utc>) -> String {
+        match self {
+            Zone::System => instant.with_timezone(&Local).to_rfc3339(),
+            Zone::Named(tz) => instant.with_timezone(tz).to_rfc3339(),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+fn runs_on(days: &[u32], date: NaiveDate) -> bool {
+    days.is_empty() || days.contains(&date.weekday().num_days_from_monday())
+}
+
+// First instant after `after` that `time` comes round on one of `days` (empty = every day).
+// Each local day fires once, so a time inside a repeated hour does not fire twice
+pub fn next_fire(
+    zone: Zone,
+    days: &[u32],
+    time: NaiveTime,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let today = zone.date_of(after);
+    (-1..8)
+        .map(|offset| today + Duration::days(offset))
+        .filter(|date| runs_on(days, *date))
+        .filter_map(|date| zone.resolve(date.and_time(time), true))
+        .find(|instant| *instant > after)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeWindow {
+    // Days the window starts on, 0 = Monday; empty means every day
+    #[serde(default)]
+    pub days: Vec<u32>,
+    // "HH:MM" wall-clock time; an end before the start wraps past midnight
+    pub start: String,
+    pub end: String,
+    // IANA name such as "Europe/Berlin"; None follows the system clock
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl TimeWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(day) = self.days.iter().find(|d| **d > 6) {
+            return Err(format!("Invalid weekday {}, expected 0-6", day));
+        }
+        let (start, end) = self.bounds()?;
+        if start == end {
+            return Err("Time window start and end must differ".to_string());
+        }
+        self.zone()?;
+        Ok(())
+    }
+
+    fn bounds(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        Ok((parse_time(&self.start)?, parse_time(&self.end)?))
+    }
+
+    fn zone(&self) -> Result<Zone, String> {
+        Zone::parse(self.timezone.as_deref())
+    }
+
+    // Through a repeated hour the window stays open from the first pass of its start to the
+    // second pass of its end, so it is entered only once
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let (zone, (start, end)) = match (self.zone(), self.bounds()) {
+            (Ok(zone), Ok(bounds)) => (zone, bounds),
+            _ => return false,
+        };
+        let today = zone.date_of(now);
+        [today - Duration::days(1), today]
+            .into_iter()
+            .filter(|date| runs_on(&self.days, *date))
+            .any(|date| {
+                let end_date = if start < end {
+                    date
+                } else {
+                    date + Duration::days(1)
+                };
+                match (
+                    zone.resolve(date.and_time(start), true),
+                    zone.resolve(end_date.and_time(end), false),
+                ) {
+                    (Some(opens), Some(closes)) => opens <= now && now < closes,
+                    _ => false,
+                }
+            })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FireSchedule {
+    // Fires as the window opens, like tariff windows and quiet hours
+    Window(TimeWindow),
+    // Fires once a week, like the weekly report
+    Weekly {
+        weekday: u32,
+        hour: u32,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+}
+
+#[derive(Serialize)]
+pub struct FireTime {
+    // Unix seconds
+    at: i64,
+    // RFC 3339 in the schedule's timezone, offset included
+    local: String,
+}
+
+// Upcoming fire times from `after` (default now), to check a schedule across DST changes
+#[tauri::command]
+pub fn get_next_fire_times(
+    schedule: FireSchedule,
+    count: Option<usize>,
+    after: Option<i64>,
+) -> Result<Vec<FireTime>, String> {
+    let mut cursor = match after {
+        Some(secs) => Utc
+            .timestamp_opt(secs, 0)
+            .single()
+            .ok_or_else(|| format!("Invalid timestamp {}", secs))?,
+        None => Utc::now(),
+    };
+    let (zone, days, time) = match schedule {
+        FireSchedule::Window(window) => {
+            window.validate()?;
+            (window.zone()?, window.days.clone(), window.bounds()?.0)
+        }
+        FireSchedule::Weekly {
+            weekday,
+            hour,
+            timezone,
+        } => {
+            if weekday > 6 || hour > 23 {
+                return Err(
+                    "Weekly schedule needs a weekday from 0 to 6 and an hour from 0 to 23"
+                        .to_string(),
+                );
+            }
+            let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+            (Zone::parse(timezone.as_deref())?, vec![weekday], time)
+        }
+    };
+    let mut times = Vec::new();
+    while times.len() < count.unwrap_or(10).min(MAX_FIRE_TIMES) {
+        match next_fire(zone, &days, time, cursor) {
+            Some(next) => {
+                times.push(FireTime {
+                    at: next.timestamp(),
+                    local: zone.wall_clock(next),
+                });
+                cursor = next;
+            }
+            None => break,
+        }
+    }
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn berlin() -> Zone {
+        Zone::parse(Some("Europe/Berlin")).unwrap()
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn skipped_time_fires_where_the_clock_resumes() {
+        // 02:00 CET jumps to 03:00 CEST on 2024-03-31, so 02:30 never happens that day
+        let fire = next_fire(berlin(), &[], at(2, 30), utc("2024-03-30T23:00:00Z"));
+        assert_eq!(fire, Some(utc("2024-03-31T01:00:00Z")));
+    }
+
+    #[test]
+    fn skipped_time_fires_once_that_day() {
+        let fire = next_fire(berlin(), &[], at(2, 30), utc("2024-03-31T01:00:00Z"));
+        assert_eq!(fire, Some(utc("2024-04-01T00:30:00Z")));
+    }
+
+    #[test]
+    fn repeated_time_fires_on_its_first_pass() {
+        // 03:00 CEST falls back to 02:00 CET on 2024-10-27, so 02:30 happens twice
+        let fire = next_fire(berlin(), &[], at(2, 30), utc("2024-10-26T23:00:00Z"));
+        assert_eq!(fire, Some(utc("2024-10-27T00:30:00Z")));
+    }
+
+    #[test]
+    fn repeated_time_does_not_fire_again_on_its_second_pass() {
+        let fire = next_fire(berlin(), &[], at(2, 30), utc("2024-10-27T00:30:00Z"));
+        assert_eq!(fire, Some(utc("2024-10-28T01:30:00Z")));
+    }
+
+    #[test]
+    fn weekdays_are_read_in_the_schedule_zone() {
+        // 23:30 UTC on a Sunday is already Monday in Berlin
+        let fire = next_fire(berlin(), &[0], at(1, 0), utc("2024-06-09T23:30:00Z"));
+        assert_eq!(fire, Some(utc("2024-06-16T23:00:00Z")));
+    }
+}
@@ -0,0 +1,216 @@
+// On-demand self-test covering the app's local dependencies and the backend
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::api::notification::Notification;
+use tauri::{AppHandle, Manager};
+
+use crate::gpu::Gpu;
+use crate::metrics::now_secs;
+use crate::{agent0, api, install_mode, keychain, storage};
+
+const EVENT_COUNT: usize = 1000;
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Serialize)]
+pub struct CheckResult {
+    name: String,
+    status: CheckStatus,
+    detail: String,
+    duration_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct SelfTestReport {
+    started_at: i64,
+    passed: bool,
+    results: Vec<CheckResult>,
+}
+
+// Ok carries the detail for a pass; None means the check does not apply here
+type CheckOutcome = Result<Option<String>, String>;
+
+fn record(results: &mut Vec<CheckResult>, name: &str, started: Instant, outcome: CheckOutcome) {
+    let (status, detail) = match outcome {
+        Ok(Some(detail)) => (CheckStatus::Pass, detail),
+        Ok(None) => (
+            CheckStatus::Skip,
+            "Not applicable on this system".to_string(),
+        ),
+        Err(e) => (CheckStatus::Fail, e),
+    };
+    results.push(CheckResult {
+        name: name.to_string(),
+        status,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+}
+
+fn check_data_dir(app: &AppHandle) -> CheckOutcome {
+    let dir = storage::data_dir(app)?;
+    let probe = dir.join(".selftest");
+    // Through the guarded writer, so safe mode and a data directory move are respected
+    storage::write_json(&probe, &"ok")?;
+    let read = fs::read(&probe).map_err(|e| format!("Failed to read back probe: {}", e))?;
+    let _ = fs::remove_file(&probe);
+    if serde_json::from_slice::<String>(&read).ok().as_deref() != Some("ok") {
+        return Err("Probe file read back differently".to_string());
+    }
+    Ok(Some(format!("{} is writable", dir.display())))
+}
+
+fn json_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                json_files(&path, out);
+            } else if path.extension().map_or(false, |ext| ext == "json") {
+                out.push(path);
+            }
+        }
+    }
+}
+
+// Every persisted store is a JSON document, so integrity means it still parses
+fn check_stores(app: &AppHandle) -> CheckOutcome {
+    let dir = storage::data_dir(app)?;
+    let mut files = Vec::new();
+    json_files(&dir, &mut files);
+    let corrupt: Vec<String> = files
+        .iter()
+        .filter(|path| {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .is_none()
+        })
+        .map(|path| {
+            path.strip_prefix(&dir)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect();
+    if corrupt.is_empty() {
+        Ok(Some(format!("{} store files parsed", files.len())))
+    } else {
+        Err(format!("Unreadable store files: {}", corrupt.join(", ")))
+    }
+}
+
+fn check_events(app: &AppHandle) -> CheckOutcome {
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = received.clone();
+    let handler = app.listen_global("selftest-ping", move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let started = Instant::now();
+    for _ in 0..EVENT_COUNT {
+        app.trigger_global("selftest-ping", None);
+    }
+    let elapsed = started.elapsed().as_secs_f64().max(1e-6);
+    app.unlisten(handler);
+    let received = received.load(Ordering::SeqCst);
+    if received != EVENT_COUNT {
+        return Err(format!(
+            "Only {} of {} events were delivered",
+            received, EVENT_COUNT
+        ));
+    }
+    Ok(Some(format!(
+        "{:.0} events/s",
+        EVENT_COUNT as f64 / elapsed
+    )))
+}
+
+fn check_tray(app: &AppHandle) -> CheckOutcome {
+    app.tray_handle()
+        .set_tooltip("Agent-0 Desktop")
+        .map_err(|e| format!("Tray icon is unavailable: {}", e))?;
+    Ok(Some("Tray icon responds".to_string()))
+}
+
+fn check_notifications(app: &AppHandle) -> CheckOutcome {
+    Notification::new(&app.config().tauri.bundle.identifier)
+        .title("Agent-0 self-test")
+        .body("Notifications are working")
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))?;
+    Ok(Some("Test notification handed to the OS".to_string()))
+}
+
+fn check_gpu(app: &AppHandle) -> CheckOutcome {
+    if install_mode::is_remote_only() {
+        return Ok(None);
+    }
+    match app.state::<Gpu>().total_power_watts() {
+        Ok(watts) => Ok(Some(format!("NVML reports {:.0} W", watts))),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn check_backend() -> CheckOutcome {
+    let url = format!("{}{}", agent0::base_url(), api::paths::HEALTH);
+    let started = Instant::now();
+    let response =
+        agent0::get_raw(&agent0::base_url(), api::paths::HEALTH, BACKEND_TIMEOUT).await?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    Ok(Some(format!(
+        "{} answered in {} ms",
+        url,
+        started.elapsed().as_millis()
+    )))
+}
+
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> SelfTestReport {
+    let started_at = now_secs();
+    let mut results = Vec::new();
+
+    // Reading a name that is never stored proves the keychain answers without a prompt
+    let started = Instant::now();
+    let keychain =
+        keychain::get("selftest-probe").map(|_| Some("OS keychain reachable".to_string()));
+    record(&mut results, "keychain", started, keychain);
+
+    let started = Instant::now();
+    record(&mut results, "data_dir", started, check_data_dir(&app));
+    let started = Instant::now();
+    record(&mut results, "store_integrity", started, check_stores(&app));
+    let started = Instant::now();
+    record(&mut results, "event_channel", started, check_events(&app));
+    let started = Instant::now();
+    record(&mut results, "tray", started, check_tray(&app));
+    let started = Instant::now();
+    record(
+        &mut results,
+        "notifications",
+        started,
+        check_notifications(&app),
+    );
+    let started = Instant::now();
+    record(&mut results, "gpu", started, check_gpu(&app));
+    let started = Instant::now();
+    record(&mut results, "backend", started, check_backend().await);
+
+    SelfTestReport {
+        started_at,
+        passed: results.iter().all(|r| r.status != CheckStatus::Fail),
+        results,
+    }
+}
@@ -0,0 +1,296 @@
+// Recording of UI-driven admin commands and step-by-step replay on another profile
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+use crate::metrics::now_secs;
+use crate::{commands, history, profiles, storage};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedStep {
+    offset_ms: u64,
+    command: String,
+    args: Value,
+    mutating: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RecordedSession {
+    id: String,
+    name: String,
+    started_at: i64,
+    profile: String,
+    steps: Vec<RecordedStep>,
+}
+
+#[derive(Serialize)]
+pub struct SessionSummary {
+    id: String,
+    name: String,
+    started_at: i64,
+    profile: String,
+    steps: usize,
+}
+
+impl From<&RecordedSession> for SessionSummary {
+    fn from(session: &RecordedSession) -> Self {
+        SessionSummary {
+            id: session.id.clone(),
+            name: session.name.clone(),
+            started_at: session.started_at,
+            profile: session.profile.clone(),
+            steps: session.steps.len(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct StepResult {
+    index: usize,
+    command: String,
+    outcome: String,
+    detail: Value,
+}
+
+#[derive(Serialize)]
+pub struct ReplayStatus {
+    session_id: String,
+    profile: String,
+    total: usize,
+    // Mutating step waiting for confirm or skip
+    pending: Option<RecordedStep>,
+    finished: bool,
+    results: Vec<StepResult>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayDecision {
+    Confirm,
+    Skip,
+}
+
+struct Recording {
+    session: RecordedSession,
+    started: Instant,
+}
+
+struct Replay {
+    session: RecordedSession,
+    profile: String,
+    next: usize,
+    results: Vec<StepResult>,
+}
+
+impl Replay {
+    fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            session_id: self.session.id.clone(),
+            profile: self.profile.clone(),
+            total: self.session.steps.len(),
+            pending: self.session.steps.get(self.next).cloned(),
+            finished: self.next >= self.session.steps.len(),
+            results: self.results.clone(),
+        }
+    }
+
+    async fn execute(&mut self, app: &AppHandle) {
+        let step = self.session.steps[self.next].clone();
+        let (outcome, detail) = match history::run(app, "replay", &step.command, step.args).await {
+            Ok(value) => ("ok", value),
+            Err(e) => ("error", Value::from(e)),
+        };
+        self.results.push(StepResult {
+            index: self.next,
+            command: step.command,
+            outcome: outcome.to_string(),
+            detail,
+        });
+        self.next += 1;
+    }
+
+    // Run read-only steps freely and stop in front of the next mutating one
+    async fn advance(&mut self, app: &AppHandle, decision: Option<ReplayDecision>) {
+        if let Some(decision) = decision {
+            if self.next >= self.session.steps.len() {
+                return;
+            }
+            match decision {
+                ReplayDecision::Confirm => self.execute(app).await,
+                ReplayDecision::Skip => {
+                    self.results.push(StepResult {
+                        index: self.next,
+                        command: self.session.steps[self.next].command.clone(),
+                        outcome: "skipped".to_string(),
+                        detail: Value::Null,
+                    });
+                    self.next += 1;
+                }
+            }
+        }
+        while self.next < self.session.steps.len() && !self.session.steps[self.next].mutating {
+            self.execute(app).await;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SessionState {
+    recording: Mutex<Option<Recording>>,
+    replay: tokio::sync::Mutex<Option<Replay>>,
+}
+
+fn sessions_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("sessions"))
+}
+
+fn load_session(app: &AppHandle, id: &str) -> Result<RecordedSession, String> {
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid session id '{}'", id));
+    }
+    let path = sessions_dir(app)?.join(format!("{}.json", id));
+    if !path.exists() {
+        return Err(format!("Recorded session '{}' not found", id));
+    }
+    storage::read_json(&path)
+}
+
+// Invoked for every command the webview calls; only registry commands are replayable
+pub fn observe(app: &AppHandle, command: &str, args: &Value) {
+    let spec = match commands::lookup(command) {
+        Some(spec) => spec,
+        None => return,
+    };
+    let state = app.state::<SessionState>();
+    let mut recording = state.recording.lock().unwrap();
+    if let Some(recording) = recording.as_mut() {
+        recording.session.steps.push(RecordedStep {
+            offset_ms: recording.started.elapsed().as_millis() as u64,
+            command: command.to_string(),
+            args: args.clone(),
+            mutating: spec.mutating,
+        });
+    }
+}
+
+#[tauri::command]
+pub fn start_session_recording(
+    app: AppHandle,
+    state: State<'_, SessionState>,
+    name: String,
+) -> Result<String, String> {
+    let mut recording = state.recording.lock().unwrap();
+    if recording.is_some() {
+        return Err("A session is already being recorded".to_string());
+    }
+    let started_at = now_secs();
+    let id = format!("{}-{}", started_at, hex::encode(rand::random::<[u8; 4]>()));
+    *recording = Some(Recording {
+        session: RecordedSession {
+            id: id.clone(),
+            name,
+            started_at,
+            profile: profiles::active(&app)?.name,
+            steps: Vec::new(),
+        },
+        started: Instant::now(),
+    });
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn stop_session_recording(
+    app: AppHandle,
+    state: State<'_, SessionState>,
+) -> Result<SessionSummary, String> {
+    let recording = state
+        .recording
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No session is being recorded".to_string())?;
+    let session = recording.session;
+    storage::write_json(
+        &sessions_dir(&app)?.join(format!("{}.json", session.id)),
+        &session,
+    )?;
+    Ok(SessionSummary::from(&session))
+}
+
+#[tauri::command]
+pub fn list_recorded_sessions(app: AppHandle) -> Result<Vec<SessionSummary>, String> {
+    let dir = sessions_dir(&app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to list sessions: {}", e))?;
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            let session: RecordedSession = storage::read_json(&path)?;
+            sessions.push(SessionSummary::from(&session));
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+    Ok(sessions)
+}
+
+#[tauri::command]
+pub fn get_recorded_session(app: AppHandle, id: String) -> Result<RecordedSession, String> {
+    load_session(&app, &id)
+}
+
+#[tauri::command]
+pub async fn start_session_replay(
+    app: AppHandle,
+    state: State<'_, SessionState>,
+    id: String,
+    profile: String,
+) -> Result<ReplayStatus, String> {
+    let session = load_session(&app, &id)?;
+    let mut replay = state.replay.lock().await;
+    if replay
+        .as_ref()
+        .map_or(false, |r| r.next < r.session.steps.len())
+    {
+        return Err("Another replay is still in progress".to_string());
+    }
+    profiles::activate(&app, &profile)?;
+    let mut next = Replay {
+        session,
+        profile,
+        next: 0,
+        results: Vec::new(),
+    };
+    next.advance(&app, None).await;
+    let status = next.status();
+    *replay = Some(next);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn advance_session_replay(
+    app: AppHandle,
+    state: State<'_, SessionState>,
+    decision: ReplayDecision,
+) -> Result<ReplayStatus, String> {
+    let mut replay = state.replay.lock().await;
+    let replay = replay
+        .as_mut()
+        .ok_or_else(|| "No replay in progress".to_string())?;
+    replay.advance(&app, Some(decision)).await;
+    Ok(replay.status())
+}
+
+#[tauri::command]
+pub async fn cancel_session_replay(state: State<'_, SessionState>) -> Result<(), String> {
+    match state.replay.lock().await.take() {
+        Some(_) => Ok(()),
+        None => Err("No replay in progress".to_string()),
+    }
+}
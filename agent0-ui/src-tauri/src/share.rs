@@ -0,0 +1,138 @@
+// Redacted conversation sharing through expiring signed links
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use crate::api::{self, ShareRequest, ShareRevocation, TranscriptMessage};
+use crate::metrics::now_secs;
+use crate::proxy::{ProxyState, SHARE_PATH};
+use crate::{agent0, storage};
+
+const MAX_TTL_SECS: u64 = 30 * 24 * 3600;
+
+// Applied in order, so specific secrets are masked before the broader patterns run
+static REDACTIONS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (r"(?i)bearer\s+[A-Za-z0-9._~+/-]+=*", "Bearer [secret]"),
+        (
+            r"\b(?:sk|pk|rk|ghp|gho|xox[abp])[-_][A-Za-z0-9_-]{16,}\b",
+            "[secret]",
+        ),
+        (r"\b[0-9a-fA-F]{32,}\b", "[secret]"),
+        (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[email]"),
+        (r"\b(?:\d{1,3}\.){3}\d{1,3}\b", "[ip]"),
+        (r"(?:/home|/Users|[A-Za-z]:\\Users)[/\\][^/\\\s]+", "[home]"),
+    ]
+    .iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), *replacement))
+    .collect()
+});
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ActiveShare {
+    share_id: String,
+    conversation_id: String,
+    url: String,
+    created_at: i64,
+    expires_at: i64,
+}
+
+fn shares_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("shares.json"))
+}
+
+// Expired links are dropped whenever the list is read
+fn load_shares(app: &AppHandle) -> Result<Vec<ActiveShare>, String> {
+    let mut shares: Vec<ActiveShare> = storage::read_json(&shares_path(app)?)?;
+    let now = now_secs();
+    shares.retain(|s| s.expires_at > now);
+    Ok(shares)
+}
+
+fn redact(text: &str) -> String {
+    REDACTIONS
+        .iter()
+        .fold(text.to_string(), |text, (pattern, replacement)| {
+            pattern.replace_all(&text, *replacement).into_owned()
+        })
+}
+
+#[tauri::command]
+pub async fn share_conversation(
+    app: AppHandle,
+    proxy: State<'_, ProxyState>,
+    id: String,
+    ttl: u64,
+    via_proxy: Option<bool>,
+) -> Result<ActiveShare, String> {
+    if ttl == 0 || ttl > MAX_TTL_SECS {
+        return Err(format!(
+            "Share lifetime must be between 1 and {} seconds",
+            MAX_TTL_SECS
+        ));
+    }
+    if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid conversation id '{}'", id));
+    }
+    let public_base = if via_proxy.unwrap_or(false) {
+        proxy
+            .public_url()
+            .ok_or_else(|| "LAN proxy is not running".to_string())?
+    } else {
+        agent0::base_url()
+    };
+
+    let conversation = api::get_conversation(&agent0::base_url(), &id).await?;
+    let mut messages: Vec<TranscriptMessage> =
+        serde_json::from_value(conversation["messages"].clone())
+            .map_err(|e| format!("Invalid conversation transcript: {}", e))?;
+    for message in &mut messages {
+        message.text = redact(&message.text);
+    }
+
+    let created_at = now_secs();
+    let expires_at = created_at + ttl as i64;
+    let request = ShareRequest {
+        conversation_id: id.clone(),
+        transcript: messages,
+        expires_at,
+    };
+    let created = api::create_share(&agent0::base_url(), &request).await?;
+    // A signed path below SHARE_PATH, including expiry and signature query
+    if !created.path.starts_with(SHARE_PATH) {
+        return Err(format!("Unexpected share path '{}'", created.path));
+    }
+
+    let share = ActiveShare {
+        share_id: created.share_id,
+        conversation_id: id,
+        url: format!("{}{}", public_base, created.path),
+        created_at,
+        expires_at,
+    };
+    let mut shares = load_shares(&app)?;
+    shares.push(share.clone());
+    storage::write_json(&shares_path(&app)?, &shares)?;
+    Ok(share)
+}
+
+#[tauri::command]
+pub fn list_shares(app: AppHandle) -> Result<Vec<ActiveShare>, String> {
+    load_shares(&app)
+}
+
+#[tauri::command]
+pub async fn revoke_share(app: AppHandle, share_id: String) -> Result<(), String> {
+    let revocation = ShareRevocation {
+        share_id: share_id.clone(),
+    };
+    api::revoke_share(&agent0::base_url(), &revocation).await?;
+    let mut shares = load_shares(&app)?;
+    shares.retain(|s| s.share_id != share_id);
+    storage::write_json(&shares_path(&app)?, &shares)
+}
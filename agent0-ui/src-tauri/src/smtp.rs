@@ -0,0 +1,156 @@
+// Outgoing email through an operator-configured SMTP server; the password lives in the keychain
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::{journal, keychain, storage};
+
+pub const PASSWORD_SECRET: &str = "smtp_password";
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    // Implicit TLS, usually port 465
+    Tls,
+    // Upgraded plaintext connection, usually port 587
+    StartTls,
+    // Only for relays on localhost or a trusted network
+    None,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SmtpSettings {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    // Empty sends without authenticating
+    username: String,
+    from: String,
+    to: Vec<String>,
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        SmtpSettings {
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+        }
+    }
+}
+
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("smtp.json"))
+}
+
+pub fn load_settings(app: &AppHandle) -> Result<SmtpSettings, String> {
+    storage::read_json(&settings_path(app)?)
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, String> {
+    address
+        .parse()
+        .map_err(|e| format!("Invalid email address '{}': {}", address, e))
+}
+
+fn transport(settings: &SmtpSettings) -> Result<SmtpTransport, String> {
+    let builder = match settings.security {
+        SmtpSecurity::Tls => SmtpTransport::relay(&settings.host),
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&settings.host),
+        SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(settings.host.as_str())),
+    }
+    .map_err(|e| format!("Invalid SMTP server {}: {}", settings.host, e))?;
+    let mut builder = builder.port(settings.port).timeout(Some(SEND_TIMEOUT));
+    if !settings.username.is_empty() {
+        let password = keychain::get(PASSWORD_SECRET)?.unwrap_or_default();
+        builder = builder.credentials(Credentials::new(settings.username.clone(), password));
+    }
+    Ok(builder.build())
+}
+
+// Blocking; callers on the async runtime go through spawn_blocking
+pub fn send(
+    settings: &SmtpSettings,
+    subject: &str,
+    html: String,
+    attachment: Option<EmailAttachment>,
+) -> Result<(), String> {
+    if settings.host.is_empty() || settings.to.is_empty() {
+        return Err("SMTP is not configured".to_string());
+    }
+    let mut builder = Message::builder()
+        .from(mailbox(&settings.from)?)
+        .subject(subject);
+    for to in &settings.to {
+        builder = builder.to(mailbox(to)?);
+    }
+    let mut body = MultiPart::mixed().singlepart(SinglePart::html(html));
+    if let Some(attachment) = attachment {
+        let content_type = ContentType::parse(&attachment.content_type)
+            .map_err(|e| format!("Invalid attachment type: {}", e))?;
+        body = body
+            .singlepart(Attachment::new(attachment.filename).body(attachment.bytes, content_type));
+    }
+    let message = builder
+        .multipart(body)
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+    transport(settings)?
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to send email via {}: {}", settings.host, e))
+}
+
+#[tauri::command]
+pub fn get_smtp_settings(app: AppHandle) -> Result<SmtpSettings, String> {
+    load_settings(&app)
+}
+
+// `password` replaces the stored one when given; an empty string removes it
+#[tauri::command]
+pub fn set_smtp_settings(
+    app: AppHandle,
+    settings: SmtpSettings,
+    password: Option<String>,
+) -> Result<(), String> {
+    mailbox(&settings.from)?;
+    for to in &settings.to {
+        mailbox(to)?;
+    }
+    match password.as_deref() {
+        Some("") => keychain::delete(PASSWORD_SECRET)?,
+        Some(password) => keychain::set(PASSWORD_SECRET, password)?,
+        None => {}
+    }
+    journal::write_json(&app, "Edit SMTP settings", &settings_path(&app)?, &settings)
+}
+
+#[tauri::command]
+pub async fn send_test_email(app: AppHandle) -> Result<(), String> {
+    let settings = load_settings(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        send(
+            &settings,
+            "Agent-0 test email",
+            "<p>SMTP delivery from the Agent-0 desktop app works.</p>".to_string(),
+            None,
+        )
+    })
+    .await
+    .map_err(|e| format!("Email task failed: {}", e))?
+}
@@ -0,0 +1,234 @@
+// Metric panels drawn to PNG in the backend, so reports and the CLI get charts without a webview
+use chrono::Local;
+use once_cell::sync::Lazy;
+use plotters::prelude::*;
+use plotters::style::register_font;
+use serde::Serialize;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::energy::POWER_SERIES;
+use crate::formatting::Formatter;
+use crate::metrics::{now_secs, MetricsStore, TimeRange};
+use crate::{storage, telemetry};
+
+const WIDTH: u32 = 960;
+const HEIGHT: u32 = 360;
+// About two samples' worth of pixels per point
+const POINTS: i64 = 480;
+// Shorter gaps than this are sampling jitter and stay joined
+const MIN_GAP_SECS: i64 = 300;
+const DEFAULT_RANGE_SECS: i64 = 86400;
+
+// Embedded, since headless machines often have no system fonts to find
+static FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+static FONT_LOADED: Lazy<Result<(), String>> = Lazy::new(|| {
+    register_font("sans-serif", FontStyle::Normal, FONT)
+        .map_err(|e| format!("Failed to load the chart font: {}", e))
+});
+
+struct Panel {
+    title: String,
+    series: String,
+    // Applied to each sample before plotting
+    scale: f64,
+    unit: &'static str,
+    decimals: usize,
+}
+
+// The dashboard's built-in panels by name; anything else is read as a raw series name
+fn panel(name: &str) -> Result<Panel, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(format!("Invalid panel name '{}'", name));
+    }
+    let builtin = |title: &str, series: &str, scale, unit, decimals| Panel {
+        title: title.to_string(),
+        series: series.to_string(),
+        scale,
+        unit,
+        decimals,
+    };
+    Ok(match name {
+        "latency" => builtin("Health latency", telemetry::LATENCY_SERIES, 1.0, "ms", 0),
+        "qps" => builtin(
+            "Requests per second",
+            telemetry::QPS_SERIES,
+            1.0,
+            "req/s",
+            1,
+        ),
+        "vram" => builtin(
+            "VRAM used",
+            telemetry::VRAM_SERIES,
+            1.0 / (1u64 << 30) as f64,
+            "GiB",
+            1,
+        ),
+        "availability" => builtin("Availability", telemetry::UP_SERIES, 100.0, "%", 0),
+        "power" => builtin("GPU power", POWER_SERIES, 1.0, "W", 0),
+        series => builtin(series, series, 1.0, "", 2),
+    })
+}
+
+fn draw_error(e: impl Display) -> String {
+    format!("Failed to draw chart: {}", e)
+}
+
+fn encode_error(e: png::EncodingError) -> String {
+    format!("Failed to encode chart: {}", e)
+}
+
+// Samples averaged into fixed buckets, split into runs wherever sampling stopped
+fn runs(app: &AppHandle, panel: &Panel, range: TimeRange) -> (Vec<Vec<(i64, f64)>>, usize) {
+    let samples = app.state::<MetricsStore>().range(&panel.series, range);
+    let width = ((range.to - range.from) / POINTS).max(1);
+    let mut buckets: Vec<(i64, f64, u32)> = Vec::new();
+    for sample in &samples {
+        let start = range.from + (sample.ts - range.from) / width * width;
+        match buckets.last_mut() {
+            Some((ts, sum, count)) if *ts == start => {
+                *sum += sample.value;
+                *count += 1;
+            }
+            _ => buckets.push((start, sample.value, 1)),
+        }
+    }
+    let gap = (2 * width).max(MIN_GAP_SECS);
+    let mut runs: Vec<Vec<(i64, f64)>> = Vec::new();
+    let mut last = None;
+    for (start, sum, count) in buckets {
+        let point = (start + width / 2, sum / count as f64 * panel.scale);
+        match (runs.last_mut(), last) {
+            (Some(run), Some(previous)) if start - previous <= gap => run.push(point),
+            _ => runs.push(vec![point]),
+        }
+        last = Some(start);
+    }
+    (runs, samples.len())
+}
+
+// PNG bytes of `panel` over `range`, with the sample count behind it
+pub fn render(app: &AppHandle, name: &str, range: TimeRange) -> Result<(Vec<u8>, usize), String> {
+    if range.to <= range.from {
+        return Err("Snapshot range must end after it starts".to_string());
+    }
+    let panel = panel(name)?;
+    FONT_LOADED.clone()?;
+    let (runs, samples) = runs(app, &panel, range);
+    let format = Formatter::load(app);
+
+    let values = runs.iter().flatten().map(|(_, v)| *v);
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| {
+        (low.min(v), high.max(v))
+    });
+    // Flat or missing data still needs a span to lay out the axis
+    let (low, high) = if !low.is_finite() {
+        (0.0, 1.0)
+    } else if high - low < f64::EPSILON {
+        (low - 1.0, high + 1.0)
+    } else {
+        let pad = (high - low) * 0.05;
+        (low - pad, high + pad)
+    };
+    let caption = match (samples, panel.unit) {
+        (0, _) => format!("{} (no data)", panel.title),
+        (_, "") => panel.title.clone(),
+        (_, unit) => format!("{} ({})", panel.title, unit),
+    };
+
+    let mut pixels = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(draw_error)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(caption, ("sans-serif", 20))
+            .margin(12)
+            .x_label_area_size(32)
+            .y_label_area_size(72)
+            .build_cartesian_2d(range.from..range.to, low..high)
+            .map_err(draw_error)?;
+        let date_only = range.to - range.from > 3 * 86400;
+        chart
+            .configure_mesh()
+            .x_labels(6)
+            .y_labels(6)
+            .x_label_formatter(&|ts| format.timestamp(*ts, date_only))
+            .y_label_formatter(&|v| format.number(*v, panel.decimals))
+            .draw()
+            .map_err(draw_error)?;
+        for run in runs {
+            chart
+                .draw_series(LineSeries::new(run, BLUE.stroke_width(2)))
+                .map_err(draw_error)?;
+        }
+        root.present().map_err(draw_error)?;
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(encode_error)?;
+    writer.write_image_data(&pixels).map_err(encode_error)?;
+    writer.finish().map_err(encode_error)?;
+    Ok((png, samples))
+}
+
+pub fn write(app: &AppHandle, name: &str, range: TimeRange, path: &Path) -> Result<usize, String> {
+    let (png, samples) = render(app, name, range)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, png).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(samples)
+}
+
+#[derive(Serialize)]
+pub struct PanelSnapshot {
+    path: String,
+    // Samples in the range; 0 means the chart says "no data"
+    samples: usize,
+}
+
+// Defaults to the last 24 hours, written to the data directory's "snapshots" folder
+#[tauri::command]
+pub async fn capture_panel_snapshot(
+    app: AppHandle,
+    panel: String,
+    range: Option<TimeRange>,
+    path: Option<String>,
+) -> Result<PanelSnapshot, String> {
+    // Checked before the name goes into a file name
+    self::panel(&panel)?;
+    let range = range.unwrap_or_else(|| {
+        let now = now_secs();
+        TimeRange {
+            from: now - DEFAULT_RANGE_SECS,
+            to: now,
+        }
+    });
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => storage::data_dir(&app)?.join("snapshots").join(format!(
+            "{}-{}.png",
+            panel,
+            Local::now().format("%Y-%m-%d-%H%M%S")
+        )),
+    };
+    // Drawing a month of samples takes long enough to stall the event loop
+    let target = path.clone();
+    let samples = tauri::async_runtime::spawn_blocking(move || write(&app, &panel, range, &target))
+        .await
+        .map_err(|e| format!("Snapshot task failed: {}", e))??;
+    Ok(PanelSnapshot {
+        path: path.display().to_string(),
+        samples,
+    })
+}
@@ -0,0 +1,267 @@
+// Declarative desired-state specs diffed and applied against a server profile
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::agent0::{Versioned, WriteError};
+use crate::api::{self, AdapterRef, ModelSelection};
+use crate::compat;
+use crate::power_plan::{self, PowerPlan};
+use crate::profiles;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    // Falls back to the active profile
+    profile: Option<String>,
+    model: Option<String>,
+    adapters: Option<Vec<String>>,
+    limits: Option<Map<String, Value>>,
+    routing: Option<Map<String, Value>>,
+    // Tariff windows live in this app rather than on the server
+    schedules: Option<PowerPlan>,
+}
+
+#[derive(Serialize)]
+pub struct ResourceResult {
+    resource: String,
+    // unchanged, planned, applied, conflict, failed or skipped
+    status: String,
+    current: Value,
+    desired: Value,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ApplyReport {
+    profile: String,
+    dry_run: bool,
+    results: Vec<ResourceResult>,
+}
+
+// Missing sections are left unmanaged
+enum Desired {
+    Model(String),
+    Adapters(Vec<String>),
+    Limits(Map<String, Value>),
+    Routing(Map<String, Value>),
+    Schedules(PowerPlan),
+}
+
+impl Desired {
+    fn resource(&self) -> &'static str {
+        match self {
+            Desired::Model(_) => "model",
+            Desired::Adapters(_) => "adapters",
+            Desired::Limits(_) => "limits",
+            Desired::Routing(_) => "routing",
+            Desired::Schedules(_) => "schedules",
+        }
+    }
+
+    fn value(&self) -> Value {
+        match self {
+            Desired::Model(model) => Value::from(model.as_str()),
+            Desired::Adapters(adapters) => json!(sorted(adapters.clone())),
+            Desired::Limits(map) | Desired::Routing(map) => Value::Object(map.clone()),
+            Desired::Schedules(plan) => serde_json::to_value(plan).unwrap_or(Value::Null),
+        }
+    }
+
+    async fn current(&self, app: &AppHandle, base: &str) -> Result<Versioned, String> {
+        match self {
+            Desired::Model(_) => {
+                let mut current = api::get_model(base).await?;
+                current.value = current.value["model"].clone();
+                Ok(current)
+            }
+            Desired::Adapters(_) => {
+                let adapters = api::list_adapters(base).await?;
+                Ok(Versioned {
+                    etag: None,
+                    value: json!(sorted(adapters)),
+                })
+            }
+            Desired::Limits(_) => api::get_limits(base).await,
+            Desired::Routing(_) => api::get_routing(base).await,
+            Desired::Schedules(_) => Ok(Versioned {
+                etag: None,
+                value: serde_json::to_value(power_plan::get_power_plan(app.clone())?)
+                    .map_err(|e| format!("Failed to encode power plan: {}", e))?,
+            }),
+        }
+    }
+
+    fn matches(&self, current: &Value) -> bool {
+        match self {
+            // Only the keys the spec mentions are managed
+            Desired::Limits(map) | Desired::Routing(map) => map
+                .iter()
+                .all(|(key, value)| current.get(key) == Some(value)),
+            _ => self.value() == *current,
+        }
+    }
+
+    // Config writes carry the ETag they were diffed against
+    async fn apply(
+        self,
+        app: &AppHandle,
+        base: &str,
+        current: &Versioned,
+    ) -> Result<(), WriteError> {
+        let etag = current.etag.as_deref();
+        match self {
+            Desired::Model(model) => {
+                compat::ensure_compatible(app, base, &model)
+                    .await
+                    .map_err(|e| WriteError::from(e.to_string()))?;
+                api::set_model(base, &ModelSelection { model }, etag).await?;
+            }
+            Desired::Adapters(adapters) => {
+                let loaded: Vec<String> =
+                    serde_json::from_value(current.value.clone()).unwrap_or_default();
+                for name in loaded.iter().filter(|name| !adapters.contains(name)) {
+                    let adapter = AdapterRef { name: name.clone() };
+                    api::unload_adapter(base, &adapter).await?;
+                }
+                for name in adapters.iter().filter(|name| !loaded.contains(name)) {
+                    if let Some(base_model) = compat::adapter_base_model(app, name) {
+                        compat::ensure_compatible(app, base, &base_model)
+                            .await
+                            .map_err(|e| WriteError::from(e.to_string()))?;
+                    }
+                    let adapter = AdapterRef { name: name.clone() };
+                    api::load_adapter(base, &adapter).await?;
+                }
+            }
+            Desired::Limits(map) => {
+                api::set_limits(base, &merged(&current.value, map), etag).await?;
+            }
+            Desired::Routing(map) => {
+                api::set_routing(base, &merged(&current.value, map), etag).await?;
+            }
+            Desired::Schedules(plan) => {
+                power_plan::set_power_plan(app.clone(), plan).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The whole document with the spec's keys laid over it, since the server replaces it on write
+// and keys the spec leaves out are unmanaged rather than removed
+fn merged(current: &Value, map: Map<String, Value>) -> Value {
+    let mut document = current.as_object().cloned().unwrap_or_default();
+    document.extend(map);
+    Value::Object(document)
+}
+
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn load_spec(path: &Path) -> Result<Spec, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read spec {}: {}", path.display(), e))?;
+    if path.extension().map_or(false, |ext| ext == "toml") {
+        toml::from_str(&text).map_err(|e| format!("Invalid spec {}: {}", path.display(), e))
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("Invalid spec {}: {}", path.display(), e))
+    }
+}
+
+#[tauri::command]
+pub async fn apply_spec(
+    app: AppHandle,
+    path: String,
+    dry_run: bool,
+) -> Result<ApplyReport, String> {
+    let spec = load_spec(Path::new(&path))?;
+    let profile = match &spec.profile {
+        Some(name) => profiles::find(&app, name)?,
+        None => profiles::active(&app)?,
+    };
+    let base = profile.base_url.trim_end_matches('/').to_string();
+    // The power plan belongs to this machine, which only serves the active profile
+    let is_active = profiles::active(&app)?.name == profile.name;
+
+    // Applied in this order so adapters and limits land on the intended model
+    let resources = vec![
+        spec.model.map(Desired::Model),
+        spec.adapters.map(Desired::Adapters),
+        spec.limits.map(Desired::Limits),
+        spec.routing.map(Desired::Routing),
+        spec.schedules.map(Desired::Schedules),
+    ];
+
+    let mut results = Vec::new();
+    let mut failed = false;
+    for desired in resources.into_iter().flatten() {
+        let mut result = ResourceResult {
+            resource: desired.resource().to_string(),
+            status: String::new(),
+            current: Value::Null,
+            desired: desired.value(),
+            error: None,
+        };
+        if failed {
+            result.status = "skipped".to_string();
+            results.push(result);
+            continue;
+        }
+        if let (Desired::Schedules(_), false) = (&desired, is_active) {
+            result.status = "skipped".to_string();
+            result.error = Some(format!(
+                "Schedules set this machine's power plan, which only applies to the active \
+                 profile, not {}",
+                profile.name
+            ));
+            results.push(result);
+            continue;
+        }
+        let current = match desired.current(&app, &base).await {
+            Ok(current) => current,
+            Err(e) => {
+                result.status = "failed".to_string();
+                result.error = Some(e);
+                failed = true;
+                results.push(result);
+                continue;
+            }
+        };
+        result.current = current.value.clone();
+        result.status = if desired.matches(&current.value) {
+            "unchanged"
+        } else if dry_run {
+            "planned"
+        } else {
+            match desired.apply(&app, &base, &current).await {
+                Ok(()) => "applied",
+                Err(e) => {
+                    let status = match &e {
+                        WriteError::Conflict { current, .. } => {
+                            result.current = current.value.clone();
+                            "conflict"
+                        }
+                        WriteError::Failed { .. } => "failed",
+                    };
+                    result.error = Some(e.to_string());
+                    failed = true;
+                    status
+                }
+            }
+        }
+        .to_string();
+        results.push(result);
+    }
+
+    Ok(ApplyReport {
+        profile: profile.name,
+        dry_run,
+        results,
+    })
+}
@@ -0,0 +1,127 @@
+// Cold-start phase timings, checked against the tray-icon budget
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::gpu::Gpu;
+use crate::metrics::MetricsStore;
+use crate::{agent0, api, install_mode};
+
+// The tray icon should be up within this long of process start
+const TRAY_BUDGET_MS: f64 = 300.0;
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone)]
+pub struct Phase {
+    name: String,
+    // Offset from process start
+    start_ms: f64,
+    duration_ms: f64,
+    // Ran in the background after the tray was up
+    deferred: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StartupReport {
+    phases: Vec<Phase>,
+    // None until the event loop is running
+    tray_ready_ms: Option<f64>,
+    budget_ms: f64,
+    within_budget: Option<bool>,
+}
+
+pub struct StartupProfile {
+    started: Instant,
+    phases: Mutex<Vec<Phase>>,
+    tray_ready_ms: Mutex<Option<f64>>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl StartupProfile {
+    pub fn new() -> Self {
+        StartupProfile {
+            started: Instant::now(),
+            phases: Mutex::new(Vec::new()),
+            tray_ready_ms: Mutex::new(None),
+        }
+    }
+
+    fn record(&self, name: &str, began: Instant, deferred: bool, error: Option<String>) {
+        self.phases.lock().unwrap().push(Phase {
+            name: name.to_string(),
+            start_ms: millis(began.duration_since(self.started)),
+            duration_ms: millis(began.elapsed()),
+            deferred,
+            error,
+        });
+    }
+
+    pub fn time<T>(&self, name: &str, phase: impl FnOnce() -> T) -> T {
+        let began = Instant::now();
+        let out = phase();
+        self.record(name, began, false, None);
+        out
+    }
+
+    pub fn tray_ready(&self) {
+        let elapsed = millis(self.started.elapsed());
+        if elapsed > TRAY_BUDGET_MS {
+            tracing::warn!(
+                "Tray took {:.0} ms to appear, over the {:.0} ms budget",
+                elapsed,
+                TRAY_BUDGET_MS
+            );
+        }
+        *self.tray_ready_ms.lock().unwrap() = Some(elapsed);
+    }
+}
+
+async fn first_health_check() -> Result<(), String> {
+    let url = format!("{}{}", agent0::base_url(), api::paths::HEALTH);
+    let response = agent0::get_raw(&agent0::base_url(), api::paths::HEALTH, HEALTH_TIMEOUT).await?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    Ok(())
+}
+
+// Heavy subsystems load off the main thread so setup returns and the tray can show
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let profile = app.state::<StartupProfile>();
+        let began = Instant::now();
+        app.state::<MetricsStore>().preload();
+        profile.record("metrics_store", began, true, None);
+        // Remote-only installs never touch NVML
+        if !install_mode::is_remote_only() {
+            let began = Instant::now();
+            app.state::<Gpu>().preload();
+            profile.record("gpu_init", began, true, None);
+        }
+        let began = Instant::now();
+        let result = first_health_check().await;
+        profile.record("first_health_check", began, true, result.err());
+    });
+}
+
+#[tauri::command]
+pub fn get_startup_profile(profile: State<'_, StartupProfile>) -> StartupReport {
+    let tray_ready_ms = *profile.tray_ready_ms.lock().unwrap();
+    let mut phases = profile.phases.lock().unwrap().clone();
+    phases.sort_by(|a, b| {
+        a.start_ms
+            .partial_cmp(&b.start_ms)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    StartupReport {
+        phases,
+        tray_ready_ms,
+        budget_ms: TRAY_BUDGET_MS,
+        within_budget: tray_ready_ms.map(|ms| ms <= TRAY_BUDGET_MS),
+    }
+}
@@ -0,0 +1,111 @@
+// Local persistence helpers for the app data directory
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use tauri::AppHandle;
+
+use crate::safe_mode;
+
+// Always in the default directory, pointing at wherever the data was moved to
+pub const LOCATION_FILE: &str = "data_location.json";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DataLocation {
+    pub path: Option<PathBuf>,
+}
+
+// Resolved once; a move replaces it after the files are in place
+static DATA_DIR: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+
+// Set while the data directory is being moved, so nothing lands behind the copy
+static WRITES_HELD: AtomicBool = AtomicBool::new(false);
+
+pub fn hold_writes(held: bool) {
+    WRITES_HELD.store(held, Ordering::SeqCst);
+}
+
+pub fn default_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to resolve app data directory".to_string())
+}
+
+// Also used before the app is built, when only the default directory is known
+pub fn locate(default: &Path) -> PathBuf {
+    read_json::<DataLocation>(&default.join(LOCATION_FILE))
+        .ok()
+        .and_then(|l| l.path)
+        .unwrap_or_else(|| default.to_path_buf())
+}
+
+pub fn set_data_dir(dir: PathBuf) {
+    *DATA_DIR.write().unwrap() = Some(dir);
+}
+
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cached = DATA_DIR.read().unwrap().clone();
+    let dir = match cached {
+        Some(dir) => dir,
+        None => {
+            let dir = locate(&default_data_dir(app)?);
+            set_data_dir(dir.clone());
+            dir
+        }
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    Ok(dir)
+}
+
+// Missing files read as the default value so first runs need no setup
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read(path) {
+        Ok(bytes) => match serde_json::from_slice(&bytes) {
+            Ok(value) => Ok(value),
+            // Safe mode carries on with defaults and lists the file for repair
+            Err(e) if safe_mode::active() => {
+                safe_mode::report_corrupt(path, &e.to_string());
+                Ok(T::default())
+            }
+            Err(e) => Err(format!("Failed to parse {}: {}", path.display(), e)),
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+// The checks `write_json` makes, for files in the data directory written some other way
+pub fn check_writable(path: &Path) -> Result<(), String> {
+    safe_mode::check_writable(path)?;
+    if WRITES_HELD.load(Ordering::SeqCst) {
+        return Err(format!(
+            "The data directory is being moved; not writing {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+// Write through a temp file and rename so a crash never leaves a truncated file
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    check_writable(path)?;
+    write_json_unguarded(path, value)
+}
+
+// Also writes in safe mode and during a move; only for repairing the files safe mode lists
+// and for the move's own location pointer
+pub fn write_json_unguarded<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to encode {}: {}", path.display(), e))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
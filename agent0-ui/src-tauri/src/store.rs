@@ -0,0 +1,88 @@
+// Canonical UI-relevant state shared by every window; mutations publish to all subscribers
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::watch;
+
+use crate::alerts::AlertState;
+
+#[derive(Serialize, Clone, Default, PartialEq)]
+pub struct AlertSummary {
+    unacknowledged: usize,
+    latest_id: Option<String>,
+    latest_message: Option<String>,
+}
+
+#[derive(Serialize, Clone, Default, PartialEq)]
+pub struct UiState {
+    // Bumped on every change so a window can drop snapshots older than the one it holds
+    revision: u64,
+    // None until Agent-0 has been reached or paused/resumed from here
+    paused: Option<bool>,
+    active_profile: Option<String>,
+    alerts: AlertSummary,
+}
+
+pub struct UiStore {
+    tx: watch::Sender<UiState>,
+}
+
+impl Default for UiStore {
+    fn default() -> Self {
+        UiStore {
+            tx: watch::channel(UiState::default()).0,
+        }
+    }
+}
+
+// The single write path; windows only hear about changes that actually changed something
+fn update(app: &AppHandle, f: impl FnOnce(&mut UiState)) {
+    let mut changed = None;
+    app.state::<UiStore>().tx.send_modify(|state| {
+        let before = state.clone();
+        f(state);
+        if *state != before {
+            state.revision = before.revision + 1;
+            changed = Some(state.clone());
+        }
+    });
+    if let Some(state) = changed {
+        let _ = app.emit_all("ui-state-changed", &state);
+    }
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    update(app, |s| s.paused = Some(paused));
+}
+
+pub fn set_active_profile(app: &AppHandle, name: &str) {
+    update(app, |s| s.active_profile = Some(name.to_string()));
+}
+
+pub fn refresh_alerts(app: &AppHandle) {
+    let open = app.state::<AlertState>().unacknowledged();
+    let latest = open.iter().max_by_key(|e| e.fired_at);
+    let alerts = AlertSummary {
+        unacknowledged: open.len(),
+        latest_id: latest.map(|e| e.id.clone()),
+        latest_message: latest.map(|e| e.message.clone()),
+    };
+    update(app, |s| s.alerts = alerts);
+}
+
+// The tray is a subscriber like any window: only the action that applies is enabled
+pub fn start(app: AppHandle) {
+    let mut rx = app.state::<UiStore>().tx.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let paused = rx.borrow().paused;
+            let tray = app.tray_handle();
+            let _ = tray.get_item("pause").set_enabled(paused != Some(true));
+            let _ = tray.get_item("resume").set_enabled(paused != Some(false));
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_ui_state(store: State<'_, UiStore>) -> UiState {
+    store.tx.borrow().clone()
+}
@@ -0,0 +1,603 @@
+// Hash-verified sync of adapters, templates and settings between two app instances
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::progress::{self, Operation};
+use crate::storage;
+
+// Top-level entries of the data directory that take part in sync
+const SYNC_ROOTS: [&str; 3] = ["adapters", "templates", "settings.json"];
+const PARTIAL_SUFFIX: &str = ".partial";
+const MIN_TOKEN_LEN: usize = 16;
+// Read before the peer has proven the token, so an endless line cannot exhaust memory; well above
+// the manifest of a large adapter library
+const MAX_MESSAGE_BYTES: u64 = 16 << 20;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    hash: String,
+    size: u64,
+}
+
+type Manifest = BTreeMap<String, FileEntry>;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncDirection {
+    Push,
+    Pull,
+}
+
+#[derive(Serialize, Default)]
+pub struct SyncReport {
+    transferred: Vec<String>,
+    unchanged: usize,
+    bytes: u64,
+}
+
+#[derive(Default)]
+pub struct SyncState {
+    listener: Mutex<Option<JoinHandle<()>>>,
+}
+
+// Wire protocol: one JSON message per line, `File` headers are followed by `size` raw bytes
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Challenge {
+        nonce: String,
+    },
+    Proof {
+        proof: String,
+        nonce: String,
+    },
+    Accepted {
+        proof: String,
+    },
+    GetManifest,
+    Manifest {
+        files: Manifest,
+    },
+    GetFiles {
+        paths: Vec<String>,
+    },
+    File {
+        path: String,
+        hash: String,
+        size: u64,
+    },
+    Done {
+        count: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+struct Peer {
+    stream: BufReader<TcpStream>,
+}
+
+impl Peer {
+    fn new(stream: TcpStream) -> Self {
+        Peer {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<(), String> {
+        let mut line = serde_json::to_vec(message)
+            .map_err(|e| format!("Failed to encode sync message: {}", e))?;
+        line.push(b'\n');
+        self.stream
+            .write_all(&line)
+            .await
+            .map_err(|e| format!("Failed to send sync message: {}", e))
+    }
+
+    async fn recv(&mut self) -> Result<Option<Message>, String> {
+        let mut line = String::new();
+        let read = (&mut self.stream)
+            .take(MAX_MESSAGE_BYTES)
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read sync message: {}", e))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read as u64 == MAX_MESSAGE_BYTES && !line.ends_with('\n') {
+            return Err(format!(
+                "Sync message is longer than {} bytes",
+                MAX_MESSAGE_BYTES
+            ));
+        }
+        serde_json::from_str(&line)
+            .map(Some)
+            .map_err(|e| format!("Invalid sync message: {}", e))
+    }
+
+    async fn expect(&mut self) -> Result<Message, String> {
+        match self.recv().await? {
+            Some(Message::Error { message }) => Err(format!("Peer reported an error: {}", message)),
+            Some(message) => Ok(message),
+            None => Err("Peer closed the connection".to_string()),
+        }
+    }
+
+    async fn fail(&mut self, message: String) -> Result<(), String> {
+        // Best effort: the peer may already be gone
+        let _ = self
+            .send(&Message::Error {
+                message: message.clone(),
+            })
+            .await;
+        Err(message)
+    }
+
+    async fn send_file(&mut self, root: &Path, key: &str, entry: &FileEntry) -> Result<(), String> {
+        let path = resolve(root, key)?;
+        let file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", key, e))?;
+        self.send(&Message::File {
+            path: key.to_string(),
+            hash: entry.hash.clone(),
+            size: entry.size,
+        })
+        .await?;
+        let copied = tokio::io::copy(&mut file.take(entry.size), self.stream.get_mut())
+            .await
+            .map_err(|e| format!("Failed to send {}: {}", key, e))?;
+        if copied != entry.size {
+            return Err(format!("{} changed while it was being synced", key));
+        }
+        Ok(())
+    }
+
+    async fn recv_file(
+        &mut self,
+        root: &Path,
+        key: &str,
+        hash: &str,
+        size: u64,
+    ) -> Result<(), String> {
+        let target = resolve(root, key)?;
+        // Settings and templates land in the data directory, so safe mode and a move hold them
+        storage::check_writable(&target)?;
+        let partial = partial_path(&target);
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create directory for {}: {}", key, e))?;
+        }
+        let mut file = tokio::fs::File::create(&partial)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", key, e))?;
+
+        let mut body = (&mut self.stream).take(size);
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut received = 0u64;
+        loop {
+            let read = body
+                .read(&mut buf)
+                .await
+                .map_err(|e| format!("Failed to receive {}: {}", key, e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            file.write_all(&buf[..read])
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", key, e))?;
+            received += read as u64;
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", key, e))?;
+        drop(file);
+
+        if received != size || hex::encode(hasher.finalize()) != hash {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(format!("Hash mismatch for {}, file discarded", key));
+        }
+        // A move may have started while the file was arriving
+        if let Err(e) = storage::check_writable(&target) {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+        tokio::fs::rename(&partial, &target)
+            .await
+            .map_err(|e| format!("Failed to replace {}: {}", key, e))
+    }
+}
+
+fn nonce() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+// Each side proves knowledge of the shared token without sending it; the role
+// label stops a peer from reflecting our own challenge back at us
+fn prove(token: &str, nonce: &str, role: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("agent0-sync:{}:{}:{}", role, token, nonce));
+    hex::encode(hasher.finalize())
+}
+
+fn proofs_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+// Map a manifest key back onto a root directory, rejecting anything outside the sync roots
+fn resolve(root: &Path, key: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(key);
+    let inside_roots = match rel.components().next() {
+        Some(Component::Normal(first)) => SYNC_ROOTS.iter().any(|r| first == *r),
+        _ => false,
+    };
+    if !inside_roots || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!(
+            "Refusing to sync path outside the sync roots: {}",
+            key
+        ));
+    }
+    Ok(root.join(rel))
+}
+
+fn hash_file(path: &Path) -> Result<FileEntry, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        size += read as u64;
+    }
+    Ok(FileEntry {
+        hash: hex::encode(hasher.finalize()),
+        size,
+    })
+}
+
+fn collect(root: &Path, path: &Path, manifest: &mut Manifest) -> Result<(), String> {
+    if path.is_dir() {
+        let entries =
+            fs::read_dir(path).map_err(|e| format!("Failed to list {}: {}", path.display(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to list {}: {}", path.display(), e))?;
+            collect(root, &entry.path(), manifest)?;
+        }
+    } else if path.is_file() && !path.to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+        let key = path
+            .strip_prefix(root)
+            .map_err(|_| format!("{} is outside the data directory", path.display()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        manifest.insert(key, hash_file(path)?);
+    }
+    Ok(())
+}
+
+fn build_manifest(root: &Path) -> Result<Manifest, String> {
+    let mut manifest = Manifest::new();
+    for entry in SYNC_ROOTS {
+        collect(root, &root.join(entry), &mut manifest)?;
+    }
+    Ok(manifest)
+}
+
+async fn manifest_for(root: &Path) -> Result<Manifest, String> {
+    let root = root.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || build_manifest(&root))
+        .await
+        .map_err(|e| format!("Failed to build sync manifest: {}", e))?
+}
+
+// Entries of `source` that are missing or different in `target`
+fn changed(source: &Manifest, target: &Manifest) -> Vec<(String, FileEntry)> {
+    source
+        .iter()
+        .filter(|(key, entry)| target.get(*key) != Some(entry))
+        .map(|(key, entry)| (key.clone(), entry.clone()))
+        .collect()
+}
+
+async fn serve_peer(stream: TcpStream, root: &Path, token: &str) -> Result<(), String> {
+    let mut peer = Peer::new(stream);
+    let challenge = nonce();
+    peer.send(&Message::Challenge {
+        nonce: challenge.clone(),
+    })
+    .await?;
+
+    let client_nonce = match peer.expect().await? {
+        Message::Proof { proof, nonce }
+            if proofs_match(&proof, &prove(token, &challenge, "client")) =>
+        {
+            nonce
+        }
+        _ => return peer.fail("Authentication failed".to_string()).await,
+    };
+    peer.send(&Message::Accepted {
+        proof: prove(token, &client_nonce, "server"),
+    })
+    .await?;
+
+    while let Some(message) = peer.recv().await? {
+        match message {
+            Message::GetManifest => {
+                let files = manifest_for(root).await?;
+                peer.send(&Message::Manifest { files }).await?;
+            }
+            Message::GetFiles { paths } => {
+                let manifest = manifest_for(root).await?;
+                let mut count = 0;
+                for key in paths {
+                    if let Some(entry) = manifest.get(&key) {
+                        peer.send_file(root, &key, entry).await?;
+                        count += 1;
+                    }
+                }
+                peer.send(&Message::Done { count }).await?;
+            }
+            Message::File { path, hash, size } => {
+                peer.recv_file(root, &path, &hash, size).await?;
+            }
+            Message::Done { count } => {
+                peer.send(&Message::Done { count }).await?;
+            }
+            _ => return peer.fail("Unexpected sync message".to_string()).await,
+        }
+    }
+    Ok(())
+}
+
+async fn connect(address: &str, token: &str) -> Result<Peer, String> {
+    let stream = TcpStream::connect(address)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+    let mut peer = Peer::new(stream);
+
+    let challenge = match peer.expect().await? {
+        Message::Challenge { nonce } => nonce,
+        _ => return Err("Peer did not send an authentication challenge".to_string()),
+    };
+    let ours = nonce();
+    peer.send(&Message::Proof {
+        proof: prove(token, &challenge, "client"),
+        nonce: ours.clone(),
+    })
+    .await?;
+    match peer.expect().await? {
+        Message::Accepted { proof } if proofs_match(&proof, &prove(token, &ours, "server")) => {
+            Ok(peer)
+        }
+        _ => Err("Peer failed to prove it knows the sync token".to_string()),
+    }
+}
+
+async fn remote_manifest(peer: &mut Peer) -> Result<Manifest, String> {
+    peer.send(&Message::GetManifest).await?;
+    match peer.expect().await? {
+        Message::Manifest { files } => Ok(files),
+        _ => Err("Peer did not return a manifest".to_string()),
+    }
+}
+
+async fn sync_peer(
+    root: &Path,
+    address: &str,
+    token: &str,
+    direction: SyncDirection,
+    operation: &Operation,
+) -> Result<SyncReport, String> {
+    operation.report("connecting", None);
+    let mut peer = connect(address, token).await?;
+    operation.report("comparing", None);
+    let remote = remote_manifest(&mut peer).await?;
+    let local = manifest_for(root).await?;
+    let mut report = SyncReport::default();
+
+    match direction {
+        SyncDirection::Push => {
+            let pending = changed(&local, &remote);
+            report.unchanged = local.len() - pending.len();
+            let total = pending.iter().map(|(_, entry)| entry.size).sum();
+            for (key, entry) in &pending {
+                operation.report_ratio("uploading", report.bytes, total);
+                peer.send_file(root, key, entry).await?;
+                report.bytes += entry.size;
+                report.transferred.push(key.clone());
+            }
+            peer.send(&Message::Done {
+                count: pending.len(),
+            })
+            .await?;
+            peer.expect().await?;
+        }
+        SyncDirection::Pull => {
+            let pending = changed(&remote, &local);
+            report.unchanged = remote.len() - pending.len();
+            let total = pending.iter().map(|(_, entry)| entry.size).sum();
+            operation.report_ratio("downloading", 0, total);
+            peer.send(&Message::GetFiles {
+                paths: pending.iter().map(|(key, _)| key.clone()).collect(),
+            })
+            .await?;
+            loop {
+                match peer.expect().await? {
+                    Message::File { path, hash, size } => {
+                        if !pending.iter().any(|(key, _)| *key == path) {
+                            return Err(format!(
+                                "Peer sent a file that was not requested: {}",
+                                path
+                            ));
+                        }
+                        peer.recv_file(root, &path, &hash, size).await?;
+                        report.bytes += size;
+                        report.transferred.push(path);
+                        operation.report_ratio("downloading", report.bytes, total);
+                    }
+                    Message::Done { .. } => break,
+                    _ => return Err("Unexpected sync message".to_string()),
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn sync_folder(
+    local: &Path,
+    folder: &Path,
+    direction: SyncDirection,
+    operation: &Operation,
+) -> Result<SyncReport, String> {
+    let (from, to) = match direction {
+        SyncDirection::Push => (local, folder),
+        SyncDirection::Pull => (folder, local),
+    };
+    operation.report("comparing", None);
+    let source = build_manifest(from)?;
+    let target = build_manifest(to)?;
+    let pending = changed(&source, &target);
+    let mut report = SyncReport {
+        unchanged: source.len() - pending.len(),
+        ..SyncReport::default()
+    };
+
+    let total = pending.iter().map(|(_, entry)| entry.size).sum();
+    for (key, entry) in pending {
+        // Leaves no partial file behind; finished files stay
+        operation.check()?;
+        operation.report_ratio("copying", report.bytes, total);
+        let src = resolve(from, &key)?;
+        let dst = resolve(to, &key)?;
+        storage::check_writable(&dst)?;
+        let partial = partial_path(&dst);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory for {}: {}", key, e))?;
+        }
+        fs::copy(&src, &partial).map_err(|e| format!("Failed to copy {}: {}", key, e))?;
+        if hash_file(&partial)? != entry {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("{} changed while it was being synced", key));
+        }
+        fs::rename(&partial, &dst).map_err(|e| format!("Failed to replace {}: {}", key, e))?;
+        report.bytes += entry.size;
+        report.transferred.push(key);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn start_sync_listener(
+    app: AppHandle,
+    state: State<'_, SyncState>,
+    port: u16,
+    token: String,
+) -> Result<String, String> {
+    if token.len() < MIN_TOKEN_LEN {
+        return Err(format!(
+            "Sync token must be at least {} characters",
+            MIN_TOKEN_LEN
+        ));
+    }
+    let root = storage::data_dir(&app)?;
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| format!("Failed to start sync listener: {}", e))?;
+
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, addr)) => {
+                    let root = root.clone();
+                    let token = token.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = serve_peer(stream, &root, &token).await {
+                            tracing::warn!("Sync session with {} failed: {}", addr, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept sync connection: {}", e),
+            }
+        }
+    });
+    if let Some(previous) = state.listener.lock().unwrap().replace(handle) {
+        previous.abort();
+    }
+    Ok(format!("Sync listener started on port {}", port))
+}
+
+#[tauri::command]
+pub fn stop_sync_listener(state: State<'_, SyncState>) -> Result<String, String> {
+    match state.listener.lock().unwrap().take() {
+        Some(handle) => {
+            handle.abort();
+            Ok("Sync listener stopped".to_string())
+        }
+        None => Err("Sync listener is not running".to_string()),
+    }
+}
+
+// Both return an operation id; the sync report arrives with its final progress event
+#[tauri::command]
+pub fn sync_with_peer(
+    app: AppHandle,
+    address: String,
+    token: String,
+    direction: SyncDirection,
+) -> Result<String, String> {
+    let root = storage::data_dir(&app)?;
+    Ok(progress::spawn(&app, "sync", move |operation| async move {
+        progress::encode(sync_peer(&root, &address, &token, direction, &operation).await?)
+    }))
+}
+
+#[tauri::command]
+pub fn sync_with_folder(
+    app: AppHandle,
+    path: String,
+    direction: SyncDirection,
+) -> Result<String, String> {
+    let root = storage::data_dir(&app)?;
+    let folder = PathBuf::from(path);
+    Ok(progress::spawn(&app, "sync", move |operation| async move {
+        let report = tauri::async_runtime::spawn_blocking(move || {
+            sync_folder(&root, &folder, direction, &operation)
+        })
+        .await
+        .map_err(|e| format!("Failed to sync with folder: {}", e))??;
+        progress::encode(report)
+    }))
+}
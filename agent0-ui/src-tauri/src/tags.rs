@@ -0,0 +1,412 @@
+// Shared user and auto tags on conversations, templates, alerts and audit entries, kept with an
+// inverted index so filters do not scan the flat lists
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::{profiles, storage};
+
+const MAX_TAG_LEN: usize = 40;
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(15);
+
+struct Cached {
+    path: PathBuf,
+    index: TagIndex,
+    // Auto tags recorded since the last write
+    dirty: bool,
+}
+
+// Loaded on first use; auto tags from chats, alerts and audit entries only change the copy in
+// memory, which `start` writes back
+static INDEX: Lazy<Mutex<Option<Cached>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TagKind {
+    Conversation,
+    Template,
+    Alert,
+    Audit,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemRef {
+    kind: TagKind,
+    id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ItemTags {
+    user: BTreeSet<String>,
+    // Derived from the item itself, e.g. "model:llama3", "server:home", "persona:analyst"
+    auto: BTreeSet<String>,
+}
+
+impl ItemTags {
+    fn all(&self) -> BTreeSet<String> {
+        self.user.union(&self.auto).cloned().collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagIndex {
+    items: BTreeMap<TagKind, BTreeMap<String, ItemTags>>,
+    // Tag to every item carrying it, kept in step with `items`
+    by_tag: BTreeMap<String, BTreeSet<ItemRef>>,
+}
+
+#[derive(Serialize)]
+pub struct TagMatch {
+    kind: TagKind,
+    id: String,
+    tags: ItemTags,
+}
+
+impl TagIndex {
+    fn tags_of(&self, kind: TagKind, id: &str) -> BTreeSet<String> {
+        self.items
+            .get(&kind)
+            .and_then(|items| items.get(id))
+            .map(ItemTags::all)
+            .unwrap_or_default()
+    }
+
+    fn edit(&mut self, kind: TagKind, id: &str, change: impl FnOnce(&mut ItemTags)) {
+        let before = self.tags_of(kind, id);
+        let items = self.items.entry(kind).or_default();
+        let tags = items.entry(id.to_string()).or_default();
+        change(tags);
+        if tags.user.is_empty() && tags.auto.is_empty() {
+            items.remove(id);
+        }
+        let after = self.tags_of(kind, id);
+        let item = ItemRef {
+            kind,
+            id: id.to_string(),
+        };
+        for tag in before.difference(&after) {
+            if let Some(carriers) = self.by_tag.get_mut(tag) {
+                carriers.remove(&item);
+                if carriers.is_empty() {
+                    self.by_tag.remove(tag);
+                }
+            }
+        }
+        for tag in after.difference(&before) {
+            self.by_tag
+                .entry(tag.clone())
+                .or_default()
+                .insert(item.clone());
+        }
+    }
+
+    pub fn add_user(&mut self, kind: TagKind, id: &str, tags: &[String]) {
+        self.edit(kind, id, |item| item.user.extend(tags.iter().cloned()));
+    }
+
+    fn set_user(&mut self, kind: TagKind, id: &str, tags: Vec<String>) {
+        self.edit(kind, id, |item| item.user = tags.into_iter().collect());
+    }
+
+    fn add_auto(&mut self, kind: TagKind, id: &str, tags: Vec<String>) {
+        self.edit(kind, id, |item| item.auto.extend(tags));
+    }
+
+    fn forget(&mut self, kind: TagKind, id: &str) {
+        self.edit(kind, id, |item| *item = ItemTags::default());
+    }
+
+    // A trailing '*' matches every tag with that prefix
+    fn carriers(&self, pattern: &str) -> BTreeSet<&ItemRef> {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => self
+                .by_tag
+                .range(prefix.to_string()..)
+                .take_while(|(tag, _)| tag.starts_with(prefix))
+                .flat_map(|(_, items)| items)
+                .collect(),
+            None => self.by_tag.get(pattern).into_iter().flatten().collect(),
+        }
+    }
+
+    // Space-separated terms must all match; "a|b" matches either and "-a" excludes
+    fn search(&self, query: &str) -> Result<BTreeSet<ItemRef>, String> {
+        let mut included: Option<BTreeSet<&ItemRef>> = None;
+        let mut excluded = BTreeSet::new();
+        for term in query.split_whitespace() {
+            let (negated, alternatives) = match term.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, term),
+            };
+            let mut found = BTreeSet::new();
+            for alternative in alternatives.split('|') {
+                let alternative = alternative.to_lowercase();
+                if alternative.is_empty() || alternative == "*" {
+                    return Err(format!("Invalid search term '{}'", term));
+                }
+                found.extend(self.carriers(&alternative));
+            }
+            if negated {
+                excluded.extend(found);
+            } else {
+                included = Some(match included {
+                    None => found,
+                    Some(so_far) => so_far.intersection(&found).copied().collect(),
+                });
+            }
+        }
+        let candidates: BTreeSet<ItemRef> = match included {
+            Some(items) => items.into_iter().cloned().collect(),
+            // Only exclusions: start from everything tagged
+            None => self
+                .items
+                .iter()
+                .flat_map(|(kind, items)| {
+                    items.keys().map(move |id| ItemRef {
+                        kind: *kind,
+                        id: id.clone(),
+                    })
+                })
+                .collect(),
+        };
+        Ok(candidates
+            .into_iter()
+            .filter(|item| !excluded.contains(item))
+            .collect())
+    }
+}
+
+fn tags_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("tags.json"))
+}
+
+// After a data directory move the index in memory is still at least as new as the copy there
+fn cached<'a>(app: &AppHandle, cache: &'a mut Option<Cached>) -> Result<&'a mut Cached, String> {
+    let path = tags_path(app)?;
+    match cache {
+        Some(cached) if cached.path != path => {
+            cached.path = path;
+            cached.dirty = true;
+        }
+        Some(_) => {}
+        None => {
+            *cache = Some(Cached {
+                index: storage::read_json(&path)?,
+                path,
+                dirty: false,
+            })
+        }
+    }
+    Ok(cache.as_mut().unwrap())
+}
+
+fn read<R>(app: &AppHandle, f: impl FnOnce(&TagIndex) -> R) -> Result<R, String> {
+    let mut cache = INDEX.lock().unwrap();
+    Ok(f(&cached(app, &mut cache)?.index))
+}
+
+// User edits are written straight away and only take effect once saved
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut TagIndex)) -> Result<(), String> {
+    let mut cache = INDEX.lock().unwrap();
+    let cached = cached(app, &mut cache)?;
+    let mut index = cached.index.clone();
+    change(&mut index);
+    storage::write_json(&cached.path, &index)?;
+    cached.index = index;
+    cached.dirty = false;
+    Ok(())
+}
+
+fn update_later(app: &AppHandle, change: impl FnOnce(&mut TagIndex)) -> Result<(), String> {
+    let mut cache = INDEX.lock().unwrap();
+    let cached = cached(app, &mut cache)?;
+    change(&mut cached.index);
+    cached.dirty = true;
+    Ok(())
+}
+
+pub fn flush() -> Result<(), String> {
+    let mut cache = INDEX.lock().unwrap();
+    match cache.as_mut() {
+        Some(cached) if cached.dirty => {
+            storage::write_json(&cached.path, &cached.index)?;
+            cached.dirty = false;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Auto tags recorded since the last flush are lost on a crash
+pub fn start() {
+    tauri::async_runtime::spawn(async {
+        let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = flush() {
+                tracing::warn!("Failed to persist tags: {}", e);
+            }
+        }
+    });
+}
+
+// Tags compare case-insensitively and are single search terms, so they are stored lowercased
+// and without the characters the query syntax uses
+pub fn normalize(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty()
+        || tag.len() > MAX_TAG_LEN
+        || tag.starts_with('-')
+        || tag.contains(|c: char| c.is_whitespace() || matches!(c, ',' | '|' | '*'))
+    {
+        return Err(format!(
+            "Invalid tag '{}': expected 1 to {} characters without spaces, commas, '|' or '*'",
+            tag, MAX_TAG_LEN
+        ));
+    }
+    Ok(tag)
+}
+
+// "prefix:value" with spaces in the value turned into dashes; None if that makes no valid tag
+pub fn auto_tag(prefix: &str, value: &str) -> Option<String> {
+    let value = value.split_whitespace().collect::<Vec<_>>().join("-");
+    if value.is_empty() {
+        return None;
+    }
+    normalize(&format!("{}:{}", prefix, value)).ok()
+}
+
+pub fn server_tag(app: &AppHandle) -> Option<String> {
+    profiles::active(app)
+        .ok()
+        .and_then(|p| auto_tag("server", &p.name))
+}
+
+// Failures only log; tagging never stands in the way of what is being tagged
+pub fn record_auto(app: &AppHandle, kind: TagKind, items: Vec<(String, Vec<String>)>) {
+    if items.is_empty() {
+        return;
+    }
+    let recorded = update_later(app, |index| {
+        for (id, tags) in items {
+            index.add_auto(kind, &id, tags);
+        }
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record tags: {}", e);
+    }
+}
+
+// Drops the tags of items that no longer exist
+pub fn forget(app: &AppHandle, kind: TagKind, ids: &[String]) {
+    if ids.is_empty() {
+        return;
+    }
+    let forgotten = update_later(app, |index| {
+        for id in ids {
+            index.forget(kind, id);
+        }
+    });
+    if let Err(e) = forgotten {
+        tracing::warn!("Failed to drop tags: {}", e);
+    }
+}
+
+// Models, persona and server of a chat reply, added to what its conversation already carries
+pub fn tag_chat(app: &AppHandle, conversation_id: Option<&str>, persona: &str, reply: &Value) {
+    let id = match conversation_id {
+        Some(id) => id,
+        None => return,
+    };
+    let mut models: BTreeSet<&str> = reply["model_chain"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    models.extend(
+        reply["voices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v["model"].as_str()),
+    );
+    let mut tags: Vec<String> = models
+        .into_iter()
+        .filter_map(|m| auto_tag("model", m))
+        .collect();
+    tags.extend(auto_tag("persona", persona));
+    tags.extend(server_tag(app));
+    record_auto(app, TagKind::Conversation, vec![(id.to_string(), tags)]);
+}
+
+#[tauri::command]
+pub fn get_tags(app: AppHandle, kind: TagKind) -> Result<BTreeMap<String, ItemTags>, String> {
+    read(&app, |index| {
+        index.items.get(&kind).cloned().unwrap_or_default()
+    })
+}
+
+// Every tag in use with how many items carry it, for building filters
+#[tauri::command]
+pub fn list_tags(app: AppHandle) -> Result<BTreeMap<String, usize>, String> {
+    read(&app, |index| {
+        index
+            .by_tag
+            .iter()
+            .map(|(tag, items)| (tag.clone(), items.len()))
+            .collect()
+    })
+}
+
+// Replaces the user tags of one item; auto tags stay
+#[tauri::command]
+pub fn set_user_tags(
+    app: AppHandle,
+    kind: TagKind,
+    id: String,
+    tags: Vec<String>,
+) -> Result<(), String> {
+    let tags = tags
+        .iter()
+        .map(|t| normalize(t))
+        .collect::<Result<Vec<String>, String>>()?;
+    update(&app, |index| index.set_user(kind, &id, tags))
+}
+
+// e.g. "model:llama* -severity:info server:prod|server:staging"
+#[tauri::command]
+pub fn search_by_tags(
+    app: AppHandle,
+    query: String,
+    kinds: Option<Vec<TagKind>>,
+    limit: Option<usize>,
+) -> Result<Vec<TagMatch>, String> {
+    read(&app, |index| {
+        Ok(index
+            .search(&query)?
+            .into_iter()
+            .filter(|item| kinds.as_ref().map_or(true, |k| k.contains(&item.kind)))
+            .take(limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+            .map(|item| TagMatch {
+                tags: index
+                    .items
+                    .get(&item.kind)
+                    .and_then(|items| items.get(&item.id))
+                    .cloned()
+                    .unwrap_or_default(),
+                kind: item.kind,
+                id: item.id,
+            })
+            .collect())
+    })?
+}
@@ -0,0 +1,134 @@
+// Periodic sampling of Agent-0 traffic and GPU readings into the metrics store
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::gpu::Gpu;
+use crate::metrics::{now_secs, synthetic_series, MetricsStore};
+use crate::{agent0, api, faults, heatmap, install_mode};
+
+pub const QPS_SERIES: &str = "agent0.qps";
+pub const VRAM_SERIES: &str = "gpu.vram_used_bytes";
+// 1 while /health answers, 0 while it does not
+pub const UP_SERIES: &str = "agent0.up";
+pub const LATENCY_SERIES: &str = "agent0.health_latency_ms";
+// One sample per training run Agent-0 refused to start, or per injected failure
+pub const TRAINING_FAILED_SERIES: &str = "agent0.training_failed";
+// Per-device series, so alert rules can target a single GPU
+pub fn device_series(index: u32, metric: &str) -> String {
+    format!("gpu.{}.{}", index, metric)
+}
+
+const REQUESTS_COUNTER: &str = "swarm_router_requests_total";
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Sum of a Prometheus counter across all of its label sets
+fn counter_total(exposition: &str, name: &str) -> Option<f64> {
+    let values: Vec<f64> = exposition
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter(|line| {
+            line.strip_prefix(name)
+                .map_or(false, |rest| rest.starts_with('{') || rest.starts_with(' '))
+        })
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum())
+    }
+}
+
+async fn requests_total() -> Result<Option<f64>, String> {
+    let text = agent0::get_raw(&agent0::base_url(), api::paths::METRICS, HEALTH_TIMEOUT)
+        .await?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read Agent-0 metrics: {}", e))?;
+    Ok(counter_total(&text, REQUESTS_COUNTER))
+}
+
+// Round-trip time of a successful health check in milliseconds
+async fn health_latency_ms() -> Option<f64> {
+    let started = Instant::now();
+    let response = agent0::get_raw(&agent0::base_url(), api::paths::HEALTH, HEALTH_TIMEOUT)
+        .await
+        .ok()?;
+    response
+        .status()
+        .is_success()
+        .then(|| started.elapsed().as_secs_f64() * 1000.0)
+}
+
+// Device series from NVML; skipped entirely in remote-only installs
+fn sample_gpus(app: &AppHandle, store: &MetricsStore) {
+    if install_mode::is_remote_only() {
+        return;
+    }
+    if let Ok(used) = app.state::<Gpu>().total_memory_used_bytes() {
+        store.record(VRAM_SERIES, used);
+    }
+    for stats in app.state::<Gpu>().stats().unwrap_or_default() {
+        if let Some(used) = stats.memory_used_bytes {
+            store.record(&device_series(stats.index, "vram_used_bytes"), used as f64);
+        }
+        if let Some(percent) = stats.utilization_percent {
+            store.record(
+                &device_series(stats.index, "utilization_percent"),
+                percent as f64,
+            );
+        }
+        if let Some(celsius) = stats.temperature_c {
+            store.record(&device_series(stats.index, "temperature_c"), celsius as f64);
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+        let mut previous: Option<(i64, f64)> = None;
+        loop {
+            ticker.tick().await;
+            let store = app.state::<MetricsStore>();
+            sample_gpus(&app, &store);
+            let measured = health_latency_ms().await;
+            // Injected samples go to their own series and never reach the heatmap
+            let (latency, synthetic) = match faults::injected_health(&app, measured) {
+                Some(injected) => (injected, true),
+                None => (measured, false),
+            };
+            let series = |name: &str| {
+                if synthetic {
+                    synthetic_series(name)
+                } else {
+                    name.to_string()
+                }
+            };
+            store.record(
+                &series(UP_SERIES),
+                if latency.is_some() { 1.0 } else { 0.0 },
+            );
+            if let Some(latency) = latency {
+                let sample = store.record_sample(&series(LATENCY_SERIES), latency);
+                if !synthetic {
+                    heatmap::observe(&app, sample);
+                }
+            }
+            match requests_total().await {
+                Ok(Some(total)) => {
+                    let now = now_secs();
+                    // A drop in the counter means the server restarted
+                    if let Some((ts, last)) = previous {
+                        if now > ts && total >= last {
+                            store.record(QPS_SERIES, (total - last) / (now - ts) as f64);
+                        }
+                    }
+                    previous = Some((now, total));
+                }
+                _ => previous = None,
+            }
+        }
+    });
+}
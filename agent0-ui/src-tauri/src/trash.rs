@@ -0,0 +1,271 @@
+// Soft delete: removed items wait in a trash for a retention period before they are purged
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::metrics::now_secs;
+use crate::tags::{self, TagKind};
+use crate::{agent0, api, storage};
+
+const RETENTION_SECS: i64 = 30 * 86400;
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    Conversation,
+    Template,
+    Adapter,
+    Backup,
+}
+
+impl TrashKind {
+    // Directory under the data dir that holds items of this kind; conversations live on the server
+    fn local_dir(self) -> Option<&'static str> {
+        match self {
+            TrashKind::Conversation => None,
+            TrashKind::Template => Some("templates"),
+            TrashKind::Adapter => Some("adapters"),
+            TrashKind::Backup => Some("backups"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    id: String,
+    kind: TrashKind,
+    name: String,
+    // Server a conversation was deleted from, so it is restored to the same one
+    #[serde(default)]
+    base_url: Option<String>,
+    deleted_at: i64,
+    expires_at: i64,
+}
+
+fn trash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join("trash"))
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(trash_dir(app)?.join("index.json"))
+}
+
+fn load_index(app: &AppHandle) -> Result<Vec<TrashEntry>, String> {
+    storage::read_json(&index_path(app)?)
+}
+
+fn save_index(app: &AppHandle, entries: &[TrashEntry]) -> Result<(), String> {
+    storage::write_json(&index_path(app)?, &entries)
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(|c| c == '/' || c == '\\') || name.starts_with('.') {
+        return Err(format!("Invalid name '{}'", name));
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> Result<(), String> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+fn new_entry(kind: TrashKind, name: &str, base_url: Option<String>) -> TrashEntry {
+    let now = now_secs();
+    TrashEntry {
+        id: hex::encode(rand::random::<[u8; 8]>()),
+        kind,
+        name: name.to_string(),
+        base_url,
+        deleted_at: now,
+        expires_at: now + RETENTION_SECS,
+    }
+}
+
+// Where a trashed item's content is kept while it waits
+fn stash_path(app: &AppHandle, entry: &TrashEntry) -> Result<PathBuf, String> {
+    let dir = trash_dir(app)?.join(&entry.id);
+    Ok(match entry.kind {
+        TrashKind::Conversation => dir.join("conversation.json"),
+        _ => dir.join(&entry.name),
+    })
+}
+
+pub async fn trash_conversation(app: &AppHandle, id: &str) -> Result<TrashEntry, String> {
+    let base = agent0::base_url();
+    let conversation = api::get_conversation(&base, id).await?;
+    let entry = new_entry(TrashKind::Conversation, id, Some(base.clone()));
+    // Keep a copy before the server forgets it
+    storage::write_json(&stash_path(app, &entry)?, &conversation)?;
+    api::delete_conversation(&base, id).await?;
+    Ok(entry)
+}
+
+// Puts a trashed conversation back on the server it came from
+async fn restore_conversation(app: &AppHandle, entry: &TrashEntry) -> Result<(), String> {
+    let conversation: Value = storage::read_json(&stash_path(app, entry)?)?;
+    let base = entry.base_url.clone().unwrap_or_else(agent0::base_url);
+    api::create_conversation(&base, &conversation).await
+}
+
+// Reverses a trash_conversation whose entry never reached the index
+pub async fn undo_trash_conversation(app: &AppHandle, entry: &TrashEntry) -> Result<(), String> {
+    restore_conversation(app, entry).await?;
+    purge(app, entry)
+}
+
+fn trash_local(
+    app: &AppHandle,
+    kind: TrashKind,
+    dir: &str,
+    name: &str,
+) -> Result<TrashEntry, String> {
+    check_name(name)?;
+    let original = storage::data_dir(app)?.join(dir).join(name);
+    if !original.exists() {
+        return Err(format!("No {} named '{}'", dir.trim_end_matches('s'), name));
+    }
+    let entry = new_entry(kind, name, None);
+    let stash = stash_path(app, &entry)?;
+    if let Some(parent) = stash.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::rename(&original, &stash)
+        .map_err(|e| format!("Failed to move {} to trash: {}", name, e))?;
+    Ok(entry)
+}
+
+fn purge(app: &AppHandle, entry: &TrashEntry) -> Result<(), String> {
+    let dir = trash_dir(app)?.join(&entry.id);
+    if dir.exists() {
+        remove_path(&dir)?;
+    }
+    Ok(())
+}
+
+// Once purged, conversations and templates are gone for good and so are their tags
+fn forget_tags(app: &AppHandle, purged: &[TrashEntry]) {
+    for (kind, tag_kind) in [
+        (TrashKind::Conversation, TagKind::Conversation),
+        (TrashKind::Template, TagKind::Template),
+    ] {
+        let names: Vec<String> = purged
+            .iter()
+            .filter(|e| e.kind == kind)
+            .map(|e| e.name.clone())
+            .collect();
+        tags::forget(app, tag_kind, &names);
+    }
+}
+
+fn purge_expired(app: &AppHandle) -> Result<(), String> {
+    let now = now_secs();
+    let (expired, kept): (Vec<TrashEntry>, Vec<TrashEntry>) = load_index(app)?
+        .into_iter()
+        .partition(|e| e.expires_at <= now);
+    if expired.is_empty() {
+        return Ok(());
+    }
+    for entry in &expired {
+        purge(app, entry)?;
+    }
+    save_index(app, &kept)?;
+    forget_tags(app, &expired);
+    Ok(())
+}
+
+pub fn add_to_index(app: &AppHandle, added: &[TrashEntry]) -> Result<(), String> {
+    let mut entries = load_index(app)?;
+    entries.extend_from_slice(added);
+    save_index(app, &entries)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = purge_expired(&app) {
+                tracing::warn!("Failed to purge trash: {}", e);
+            }
+        }
+    });
+}
+
+// The only way the app deletes conversations, templates, adapters and backups
+#[tauri::command]
+pub async fn move_to_trash(
+    app: AppHandle,
+    kind: TrashKind,
+    name: String,
+) -> Result<TrashEntry, String> {
+    let entry = match kind.local_dir() {
+        Some(dir) => trash_local(&app, kind, dir, &name)?,
+        None => trash_conversation(&app, &name).await?,
+    };
+    add_to_index(&app, &[entry.clone()])?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn list_trash(app: AppHandle) -> Result<Vec<TrashEntry>, String> {
+    let mut entries = load_index(&app)?;
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+    Ok(entries)
+}
+
+#[tauri::command]
+pub async fn restore_from_trash(app: AppHandle, id: String) -> Result<TrashEntry, String> {
+    let mut entries = load_index(&app)?;
+    let index = entries
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| format!("Nothing in the trash with id '{}'", id))?;
+    let entry = entries[index].clone();
+    let stash = stash_path(&app, &entry)?;
+    if !stash.exists() {
+        return Err(format!("Trashed copy of '{}' is missing", entry.name));
+    }
+    match entry.kind.local_dir() {
+        Some(dir) => {
+            let original = storage::data_dir(&app)?.join(dir).join(&entry.name);
+            if original.exists() {
+                return Err(format!(
+                    "Cannot restore '{}': an item with that name exists again",
+                    entry.name
+                ));
+            }
+            if let Some(parent) = original.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            fs::rename(&stash, &original)
+                .map_err(|e| format!("Failed to restore {}: {}", entry.name, e))?;
+        }
+        None => restore_conversation(&app, &entry).await?,
+    }
+    purge(&app, &entry)?;
+    entries.remove(index);
+    save_index(&app, &entries)?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn empty_trash(app: AppHandle) -> Result<usize, String> {
+    let entries = load_index(&app)?;
+    for entry in &entries {
+        purge(&app, entry)?;
+    }
+    save_index(&app, &[])?;
+    forget_tags(&app, &entries);
+    Ok(entries.len())
+}
@@ -0,0 +1,559 @@
+// Removes what the app and its installers left on the system, reporting each item
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::firewall::FirewallRequest;
+use crate::{audit, keychain, managed, remote_store, smtp, storage};
+
+// The data is removed and the app quits this long after the report, so it still reaches the UI
+const EXIT_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct CleanupOptions {
+    autostart: bool,
+    protocol_handlers: bool,
+    // The rules the UI added; the app does not keep their ports
+    firewall_rules: Vec<FirewallRequest>,
+    services: bool,
+    keychain: bool,
+    // Everything under the data directory, then the app quits
+    data: bool,
+    // Report what would be removed without touching anything
+    dry_run: bool,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        CleanupOptions {
+            autostart: true,
+            protocol_handlers: true,
+            firewall_rules: Vec::new(),
+            services: true,
+            keychain: true,
+            data: false,
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Autostart,
+    ProtocolHandler,
+    FirewallRule,
+    Service,
+    Keychain,
+    Data,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Removed,
+    // Found on a dry run
+    WouldRemove,
+    // The data directory, removed as the app quits
+    RemovedOnExit,
+    NotFound,
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CleanupItem {
+    category: Category,
+    target: String,
+    outcome: Outcome,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CleanupReport {
+    dry_run: bool,
+    removed: usize,
+    failed: usize,
+    items: Vec<CleanupItem>,
+}
+
+// Something found on disk or in the OS, with the steps that remove it
+struct Artifact {
+    category: Category,
+    target: String,
+    remove: Box<dyn FnOnce() -> Result<(), String>>,
+}
+
+impl Artifact {
+    fn new(
+        category: Category,
+        target: String,
+        remove: impl FnOnce() -> Result<(), String> + 'static,
+    ) -> Self {
+        Artifact {
+            category,
+            target,
+            remove: Box::new(remove),
+        }
+    }
+
+    fn file(category: Category, path: PathBuf) -> Self {
+        Artifact::new(category, path.display().to_string(), move || {
+            remove_path(&path)
+        })
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<Output, String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))
+}
+
+fn succeeded(output: Output, action: &str) -> Result<(), String> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to {}: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn remove_path(path: &Path) -> Result<(), String> {
+    let removed = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    removed.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+#[cfg(unix)]
+fn home() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+// Files directly in `dir` whose name passes `keep`
+#[cfg(unix)]
+fn matching(dir: &Path, keep: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_str().map_or(false, &keep))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    found.sort();
+    found
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const RUN_VALUE: &str = "Agent-0 Desktop";
+    const PROTOCOL_KEY: &str = r"HKCU\Software\Classes\agent0";
+    // Registered by services/install_windows.ps1
+    const SERVICE: &str = "Agent0Council";
+
+    fn reg_exists(args: &[&str]) -> bool {
+        let mut query = vec!["query"];
+        query.extend_from_slice(args);
+        run("reg", &query).map_or(false, |o| o.status.success())
+    }
+
+    pub fn autostart() -> Vec<Artifact> {
+        if !reg_exists(&[RUN_KEY, "/v", RUN_VALUE]) {
+            return Vec::new();
+        }
+        vec![Artifact::new(
+            Category::Autostart,
+            format!(r"{}\{}", RUN_KEY, RUN_VALUE),
+            || {
+                succeeded(
+                    run("reg", &["delete", RUN_KEY, "/v", RUN_VALUE, "/f"])?,
+                    "remove the autostart entry",
+                )
+            },
+        )]
+    }
+
+    pub fn protocol_handlers() -> Vec<Artifact> {
+        if !reg_exists(&[PROTOCOL_KEY]) {
+            return Vec::new();
+        }
+        vec![Artifact::new(
+            Category::ProtocolHandler,
+            PROTOCOL_KEY.to_string(),
+            || {
+                succeeded(
+                    run("reg", &["delete", PROTOCOL_KEY, "/f"])?,
+                    "remove the protocol handler",
+                )
+            },
+        )]
+    }
+
+    // Services need elevation; the UAC prompt is the user's consent
+    pub fn services() -> Vec<Artifact> {
+        if !run("sc.exe", &["query", SERVICE]).map_or(false, |o| o.status.success()) {
+            return Vec::new();
+        }
+        vec![Artifact::new(
+            Category::Service,
+            SERVICE.to_string(),
+            || {
+                let script = format!(
+                    "$p = Start-Process cmd -Verb RunAs -Wait -PassThru -WindowStyle Hidden -ArgumentList '/c sc.exe stop {0} & sc.exe delete {0}'; exit $p.ExitCode",
+                    SERVICE
+                );
+                succeeded(
+                    run("powershell", &["-NoProfile", "-Command", &script])?,
+                    "remove the Agent-0 service",
+                )
+            },
+        )]
+    }
+}
+
+// Login items and servers both live in LaunchAgents; the app's own identifier marks autostart
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    const AUTOSTART_LABEL: &str = "com.agent0.desktop";
+    const LABEL_PREFIX: &str = "com.agent0.";
+    const LSREGISTER: &str = "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
+
+    fn launch_agents(autostart: bool) -> Vec<Artifact> {
+        let dir = match home() {
+            Some(home) => home.join("Library/LaunchAgents"),
+            None => return Vec::new(),
+        };
+        matching(&dir, |name| {
+            name.starts_with(LABEL_PREFIX)
+                && name.ends_with(".plist")
+                && name.starts_with(AUTOSTART_LABEL) == autostart
+        })
+        .into_iter()
+        .map(|path| {
+            let category = if autostart {
+                Category::Autostart
+            } else {
+                Category::Service
+            };
+            Artifact::new(category, path.display().to_string(), move || {
+                // Not loaded is fine; the file is what lingers
+                let _ = run("launchctl", &["unload", &path.display().to_string()]);
+                remove_path(&path)
+            })
+        })
+        .collect()
+    }
+
+    pub fn autostart() -> Vec<Artifact> {
+        launch_agents(true)
+    }
+
+    // URL schemes come from the bundle's Info.plist; Launch Services keeps them until told
+    pub fn protocol_handlers() -> Vec<Artifact> {
+        let bundle = std::env::current_exe().ok().and_then(|exe| {
+            exe.ancestors()
+                .find(|p| p.extension().map_or(false, |e| e == "app"))
+                .map(Path::to_path_buf)
+        });
+        match bundle {
+            Some(bundle) => vec![Artifact::new(
+                Category::ProtocolHandler,
+                bundle.display().to_string(),
+                move || {
+                    succeeded(
+                        run(LSREGISTER, &["-u", &bundle.display().to_string()])?,
+                        "unregister the app's URL schemes",
+                    )
+                },
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    pub fn services() -> Vec<Artifact> {
+        launch_agents(false)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::*;
+
+    const DESKTOP_PREFIX: &str = "agent0";
+    // Installed by services/install_linux.sh
+    const SYSTEM_UNITS: [&str; 2] = ["agent0.service", "swarm_metrics.service"];
+
+    fn xdg(var: &str, fallback: &str) -> Option<PathBuf> {
+        std::env::var_os(var)
+            .map(PathBuf::from)
+            .or_else(|| home().map(|h| h.join(fallback)))
+    }
+
+    fn desktop_file(name: &str) -> bool {
+        name.starts_with(DESKTOP_PREFIX) && name.ends_with(".desktop")
+    }
+
+    pub fn autostart() -> Vec<Artifact> {
+        xdg("XDG_CONFIG_HOME", ".config")
+            .map(|dir| matching(&dir.join("autostart"), desktop_file))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|path| Artifact::file(Category::Autostart, path))
+            .collect()
+    }
+
+    // Desktop entries that claim a URL scheme; the desktop database is refreshed after
+    pub fn protocol_handlers() -> Vec<Artifact> {
+        let dir = match xdg("XDG_DATA_HOME", ".local/share") {
+            Some(dir) => dir.join("applications"),
+            None => return Vec::new(),
+        };
+        matching(&dir, desktop_file)
+            .into_iter()
+            .filter(|path| {
+                fs::read_to_string(path).map_or(false, |text| text.contains("x-scheme-handler/"))
+            })
+            .map(|path| {
+                let dir = dir.clone();
+                Artifact::new(
+                    Category::ProtocolHandler,
+                    path.display().to_string(),
+                    move || {
+                        remove_path(&path)?;
+                        let _ = run("update-desktop-database", &[&dir.display().to_string()]);
+                        Ok(())
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn services() -> Vec<Artifact> {
+        let mut found: Vec<Artifact> = xdg("XDG_CONFIG_HOME", ".config")
+            .map(|dir| {
+                matching(&dir.join("systemd/user"), |name| {
+                    name.starts_with(DESKTOP_PREFIX) && name.ends_with(".service")
+                })
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(|path| {
+                Artifact::new(Category::Service, path.display().to_string(), move || {
+                    let unit = path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string();
+                    let _ = run("systemctl", &["--user", "disable", "--now", &unit]);
+                    remove_path(&path)?;
+                    let _ = run("systemctl", &["--user", "daemon-reload"]);
+                    Ok(())
+                })
+            })
+            .collect();
+        // System units need root; pkexec shows the desktop's own auth prompt
+        for unit in SYSTEM_UNITS.iter() {
+            let path = Path::new("/etc/systemd/system").join(unit);
+            if !path.exists() {
+                continue;
+            }
+            found.push(Artifact::new(
+                Category::Service,
+                path.display().to_string(),
+                move || {
+                    let command = format!(
+                        "systemctl disable --now {} ; rm -f '{}' && systemctl daemon-reload",
+                        unit,
+                        path.display()
+                    );
+                    succeeded(
+                        run("pkexec", &["sh", "-c", &command])?,
+                        "remove the Agent-0 service",
+                    )
+                },
+            ));
+        }
+        found
+    }
+}
+
+fn keychain_entries(app: &AppHandle) -> Result<Vec<Artifact>, String> {
+    let mut names = vec![
+        remote_store::SECRET_NAME.to_string(),
+        smtp::PASSWORD_SECRET.to_string(),
+    ];
+    names.extend(managed::secret_names(app)?);
+    let mut found = Vec::new();
+    for name in names {
+        if keychain::get(&name)?.is_some() {
+            found.push(Artifact::new(Category::Keychain, name.clone(), move || {
+                keychain::delete(&name)
+            }));
+        }
+    }
+    Ok(found)
+}
+
+// The custom location first, then the default one that points at it
+fn data_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let mut dirs = vec![storage::data_dir(app)?];
+    let default = storage::default_data_dir(app)?;
+    if !dirs.contains(&default) {
+        dirs.push(default);
+    }
+    Ok(dirs.into_iter().filter(|dir| dir.exists()).collect())
+}
+
+fn firewall_rules(rules: &[FirewallRequest]) -> Vec<Artifact> {
+    rules
+        .iter()
+        .filter(|rule| rule.installed() != Some(false))
+        .map(|rule| {
+            let rule = *rule;
+            Artifact::new(Category::FirewallRule, rule.name().to_string(), move || {
+                rule.remove()
+            })
+        })
+        .collect()
+}
+
+fn cleanup(app: &AppHandle, options: &CleanupOptions) -> Result<CleanupReport, String> {
+    let mut found = Vec::new();
+    if options.autostart {
+        found.extend(platform::autostart());
+    }
+    if options.protocol_handlers {
+        found.extend(platform::protocol_handlers());
+    }
+    found.extend(firewall_rules(&options.firewall_rules));
+    if options.services {
+        found.extend(platform::services());
+    }
+    if options.keychain {
+        found.extend(keychain_entries(app)?);
+    }
+    // Last, so the steps above could still read settings and secrets
+    if options.data && options.dry_run {
+        found.extend(
+            data_dirs(app)?
+                .into_iter()
+                .map(|dir| Artifact::file(Category::Data, dir)),
+        );
+    }
+
+    let mut items = Vec::new();
+    for artifact in found {
+        let result = if options.dry_run {
+            Ok(())
+        } else {
+            (artifact.remove)()
+        };
+        items.push(CleanupItem {
+            category: artifact.category,
+            target: artifact.target,
+            outcome: match (&result, options.dry_run) {
+                (Err(_), _) => Outcome::Failed,
+                (Ok(()), true) => Outcome::WouldRemove,
+                (Ok(()), false) => Outcome::Removed,
+            },
+            error: result.err(),
+        });
+    }
+    // Background tasks keep writing until the app quits, so the data goes then
+    if options.data && !options.dry_run {
+        for dir in data_dirs(app)? {
+            items.push(CleanupItem {
+                category: Category::Data,
+                target: dir.display().to_string(),
+                outcome: Outcome::RemovedOnExit,
+                error: None,
+            });
+        }
+    }
+    // Asked-for categories with nothing on this system are listed too
+    for (wanted, category) in [
+        (options.autostart, Category::Autostart),
+        (options.protocol_handlers, Category::ProtocolHandler),
+        (!options.firewall_rules.is_empty(), Category::FirewallRule),
+        (options.services, Category::Service),
+        (options.keychain, Category::Keychain),
+        (options.data, Category::Data),
+    ] {
+        if wanted && !items.iter().any(|i| i.category == category) {
+            items.push(CleanupItem {
+                category,
+                target: String::new(),
+                outcome: Outcome::NotFound,
+                error: None,
+            });
+        }
+    }
+    Ok(CleanupReport {
+        dry_run: options.dry_run,
+        removed: items
+            .iter()
+            .filter(|i| matches!(i.outcome, Outcome::Removed | Outcome::RemovedOnExit))
+            .count(),
+        failed: items
+            .iter()
+            .filter(|i| i.outcome == Outcome::Failed)
+            .count(),
+        items,
+    })
+}
+
+// Run by the uninstaller, or from settings before removing the app by hand
+#[tauri::command]
+pub fn uninstall_cleanup(
+    app: AppHandle,
+    options: Option<CleanupOptions>,
+) -> Result<CleanupReport, String> {
+    let options = options.unwrap_or_default();
+    let report = cleanup(&app, &options)?;
+    if options.dry_run {
+        return Ok(report);
+    }
+    // Removing the data takes the audit log with it
+    if !options.data {
+        audit::record(
+            &app,
+            "uninstall",
+            "uninstall_cleanup",
+            &json!({ "services": options.services, "keychain": options.keychain }),
+            &Ok(json!({ "removed": report.removed, "failed": report.failed })),
+        );
+    } else {
+        let dirs = data_dirs(&app)?;
+        let handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(EXIT_DELAY).await;
+            // Settings writes from tasks still running would otherwise land in what is removed
+            storage::hold_writes(true);
+            for dir in dirs {
+                if let Err(e) = remove_path(&dir) {
+                    tracing::warn!("{}", e);
+                }
+            }
+            handle.exit(0);
+        });
+    }
+    Ok(report)
+}
@@ -0,0 +1,348 @@
+// Opt-in recording of Agent-0 HTTP exchanges into a cassette, and replay of one in place of the server
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::managed::{self, REDACTED};
+use crate::metrics::now_secs;
+use crate::storage;
+
+const CASSETTE_VERSION: u32 = 1;
+// Long sessions stop recording rather than grow without bound
+const MAX_EXCHANGES: usize = 5000;
+// Credentials that never match the sensitive-name pattern but must not leave the machine
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+// Response headers the app reads; the rest are noise in a bug report
+const KEPT_RESPONSE_HEADERS: &[&str] = &["content-type", "etag"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum VcrMode {
+    Off,
+    Record,
+    Replay,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Exchange {
+    method: String,
+    // Path and query only, so a cassette replays against whichever server is active
+    path: String,
+    request_headers: Vec<(String, String)>,
+    request_body: Option<String>,
+    status: u16,
+    response_headers: Vec<(String, String)>,
+    response_body: String,
+    recorded_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Cassette {
+    version: u32,
+    app_version: String,
+    recorded_at: i64,
+    exchanges: Vec<Exchange>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct VcrStatus {
+    mode: VcrMode,
+    exchanges: usize,
+    // Where the recording will be saved, or the cassette being replayed
+    path: Option<String>,
+    // Replay requests that had no recorded answer
+    misses: usize,
+}
+
+struct Vcr {
+    mode: VcrMode,
+    path: Option<PathBuf>,
+    cassette: Option<Cassette>,
+    // Replay position per request, so repeated calls get the responses in recorded order
+    cursors: HashMap<String, usize>,
+    misses: usize,
+}
+
+static VCR: Lazy<Mutex<Vcr>> = Lazy::new(|| {
+    Mutex::new(Vcr {
+        mode: VcrMode::Off,
+        path: None,
+        cassette: None,
+        cursors: HashMap::new(),
+        misses: 0,
+    })
+});
+
+// Values under sensitive-looking keys, at any depth
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if managed::sensitive(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+// Non-JSON bodies are kept as they are; Agent-0 only sends secrets inside JSON
+fn redact_body(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => text.to_string(),
+    }
+}
+
+fn redact_path(url: &reqwest::Url) -> String {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if managed::sensitive(&name) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    if pairs.is_empty() {
+        return url.path().to_string();
+    }
+    let mut redacted = url.clone();
+    redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    format!("{}?{}", url.path(), redacted.query().unwrap_or_default())
+}
+
+fn redact_headers(
+    headers: &reqwest::header::HeaderMap,
+    keep: Option<&[&str]>,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| keep.map_or(true, |keep| keep.contains(&name.as_str())))
+        .map(|(name, value)| {
+            let name = name.as_str().to_string();
+            let value = if SECRET_HEADERS.contains(&name.as_str()) || managed::sensitive(&name) {
+                REDACTED.to_string()
+            } else {
+                value.to_str().unwrap_or_default().to_string()
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+fn request_body(request: &reqwest::Request) -> Option<String> {
+    request
+        .body()
+        .and_then(|b| b.as_bytes())
+        .map(|b| redact_body(&String::from_utf8_lossy(b)))
+}
+
+fn key(method: &str, path: &str, body: &Option<String>) -> String {
+    format!("{} {} {}", method, path, body.as_deref().unwrap_or(""))
+}
+
+fn response(
+    status: u16,
+    headers: &[(String, String)],
+    body: Vec<u8>,
+) -> Result<reqwest::Response, String> {
+    let mut builder = hyper::Response::builder().status(status);
+    for (name, value) in headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder
+        .body(body)
+        .map(reqwest::Response::from)
+        .map_err(|e| format!("Failed to rebuild recorded response: {}", e))
+}
+
+async fn execute(request: reqwest::Request) -> Result<reqwest::Response, String> {
+    reqwest::Client::new()
+        .execute(request)
+        .await
+        .map_err(|e| format!("Failed to reach Agent-0: {}", e))
+}
+
+fn replay(request: &reqwest::Request) -> Result<reqwest::Response, String> {
+    let method = request.method().to_string();
+    let path = redact_path(request.url());
+    let body = request_body(request);
+    let wanted = key(&method, &path, &body);
+    let mut vcr = VCR.lock().unwrap();
+    let found: Vec<Exchange> = vcr
+        .cassette
+        .as_ref()
+        .map(|c| {
+            c.exchanges
+                .iter()
+                .filter(|e| key(&e.method, &e.path, &e.request_body) == wanted)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    if found.is_empty() {
+        vcr.misses += 1;
+        return Err(format!("No recorded response for {} {}", method, path));
+    }
+    // Once the recorded answers run out the last one keeps being served
+    let cursor = vcr.cursors.entry(wanted).or_insert(0);
+    let exchange = &found[(*cursor).min(found.len() - 1)];
+    *cursor += 1;
+    response(
+        exchange.status,
+        &exchange.response_headers,
+        exchange.response_body.clone().into_bytes(),
+    )
+}
+
+async fn record(request: reqwest::Request) -> Result<reqwest::Response, String> {
+    let method = request.method().to_string();
+    let path = redact_path(request.url());
+    let request_headers = redact_headers(request.headers(), None);
+    let request_body = request_body(&request);
+    let live = execute(request).await?;
+    let status = live.status().as_u16();
+    let headers = redact_headers(live.headers(), Some(KEPT_RESPONSE_HEADERS));
+    let bytes = live
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read Agent-0 response for {}: {}", path, e))?
+        .to_vec();
+    let exchange = Exchange {
+        method,
+        path: path.clone(),
+        request_headers,
+        request_body,
+        status,
+        response_headers: headers.clone(),
+        response_body: redact_body(&String::from_utf8_lossy(&bytes)),
+        recorded_at: now_secs(),
+    };
+    if let Some(cassette) = VCR.lock().unwrap().cassette.as_mut() {
+        if cassette.exchanges.len() < MAX_EXCHANGES {
+            cassette.exchanges.push(exchange);
+        }
+    }
+    // The app gets the real body; only the cassette is redacted
+    response(status, &headers, bytes)
+}
+
+// Every Agent-0 request in agent0.rs goes out through here
+pub async fn send(builder: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+    let request = builder
+        .build()
+        .map_err(|e| format!("Failed to build Agent-0 request: {}", e))?;
+    let mode = VCR.lock().unwrap().mode;
+    match mode {
+        VcrMode::Off => execute(request).await,
+        VcrMode::Replay => replay(&request),
+        VcrMode::Record => record(request).await,
+    }
+}
+
+fn status(vcr: &Vcr) -> VcrStatus {
+    VcrStatus {
+        mode: vcr.mode,
+        exchanges: vcr.cassette.as_ref().map_or(0, |c| c.exchanges.len()),
+        path: vcr.path.as_ref().map(|p| p.display().to_string()),
+        misses: vcr.misses,
+    }
+}
+
+fn save(vcr: &Vcr) -> Result<(), String> {
+    match (&vcr.path, &vcr.cassette) {
+        (Some(path), Some(cassette)) => storage::write_json(path, cassette),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn get_vcr_status() -> VcrStatus {
+    status(&VCR.lock().unwrap())
+}
+
+#[tauri::command]
+pub fn start_recording(app: AppHandle) -> Result<VcrStatus, String> {
+    let path = storage::data_dir(&app)?.join("cassettes").join(format!(
+        "agent0-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    let mut vcr = VCR.lock().unwrap();
+    if vcr.mode != VcrMode::Off {
+        return Err("Stop the current recording or replay first".to_string());
+    }
+    *vcr = Vcr {
+        mode: VcrMode::Record,
+        path: Some(path),
+        cassette: Some(Cassette {
+            version: CASSETTE_VERSION,
+            app_version: app.package_info().version.to_string(),
+            recorded_at: now_secs(),
+            exchanges: Vec::new(),
+        }),
+        cursors: HashMap::new(),
+        misses: 0,
+    };
+    Ok(status(&vcr))
+}
+
+// Saves a recording to its cassette file; the returned status names it for attaching to a report
+#[tauri::command]
+pub fn stop_vcr() -> Result<VcrStatus, String> {
+    let mut vcr = VCR.lock().unwrap();
+    if vcr.mode == VcrMode::Record {
+        save(&vcr)?;
+    }
+    let stopped = status(&vcr);
+    vcr.mode = VcrMode::Off;
+    vcr.cassette = None;
+    vcr.cursors.clear();
+    Ok(stopped)
+}
+
+#[tauri::command]
+pub fn start_replay(path: String) -> Result<VcrStatus, String> {
+    let path = PathBuf::from(path);
+    let bytes =
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let cassette: Cassette = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if cassette.version > CASSETTE_VERSION {
+        return Err(format!(
+            "Cassette version {} is newer than this app supports",
+            cassette.version
+        ));
+    }
+    let mut vcr = VCR.lock().unwrap();
+    if vcr.mode != VcrMode::Off {
+        return Err("Stop the current recording or replay first".to_string());
+    }
+    *vcr = Vcr {
+        mode: VcrMode::Replay,
+        path: Some(path),
+        cassette: Some(cassette),
+        cursors: HashMap::new(),
+        misses: 0,
+    };
+    Ok(status(&vcr))
+}
@@ -1,4 +1,6 @@
 // Agent-0 FastAPI client with proper error handling
+import { invoke } from '@tauri-apps/api/tauri';
+
 const API_BASE = 'http://localhost:8000';
 
 export interface ChatMessage {
@@ -43,23 +45,14 @@ export class Agent0Client {
     this.baseUrl = baseUrl;
   }
 
-  async sendMessage(prompt: string, sessionId: string = 'ui_session'): Promise<ChatResponse> {
-    const response = await fetch(`${this.baseUrl}/chat`, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify({
-        prompt,
-        session_id: sessionId,
-      }),
-    });
-
-    if (!response.ok) {
-      throw new Error(`Chat request failed: ${response.status}`);
-    }
-
-    return response.json();
+  // Sent by the backend, which checks the persona's quota, records costs and tags, and answers
+  // offline in demo builds
+  async sendMessage(
+    prompt: string,
+    sessionId: string = 'ui_session',
+    persona?: string
+  ): Promise<ChatResponse> {
+    return invoke<ChatResponse>('send_chat', { prompt, sessionId, persona });
   }
 
   async getHealth(): Promise<MetricsResponse> {
@@ -0,0 +1,63 @@
+// Live Agent-0 events relayed by the backend, which picks WebSocket, SSE or long-polling
+import { invoke } from '@tauri-apps/api/tauri';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+export interface StreamMessage {
+  type: 'start' | 'agent0_token' | 'agent0_complete' | 'stream_complete' | 'error';
+  text: string;
+  partial?: boolean;
+  progress?: number;
+  confidence?: number;
+  meta?: any;
+  session_id: string;
+}
+
+export interface TransportStatus {
+  transport: 'web_socket' | 'sse' | 'long_poll' | null;
+  connected: boolean;
+  connected_since: number | null;
+  last_event_at: number | null;
+  events: number;
+}
+
+export class Agent0Events {
+  private unlisten: UnlistenFn[] = [];
+  private closed = false;
+
+  constructor(
+    private onMessage?: (message: StreamMessage) => void,
+    private onConnect?: () => void,
+    private onDisconnect?: () => void
+  ) {}
+
+  async connect(): Promise<void> {
+    this.unlisten.push(
+      await listen<StreamMessage>('server-event', (event) => this.onMessage?.(event.payload)),
+      await listen<TransportStatus>('transport-changed', (event) => this.notify(event.payload))
+    );
+    // Unmounted while the listeners were being registered
+    if (this.closed) {
+      this.disconnect();
+      return;
+    }
+    try {
+      this.notify(await invoke<TransportStatus>('get_transport_status'));
+    } catch (error) {
+      console.error('Failed to read the event transport status:', error);
+    }
+  }
+
+  private notify(status: TransportStatus): void {
+    if (status.connected) {
+      this.onConnect?.();
+    } else {
+      this.onDisconnect?.();
+    }
+  }
+
+  disconnect(): void {
+    this.closed = true;
+    this.unlisten.forEach((unlisten) => unlisten());
+    this.unlisten = [];
+  }
+}
@@ -1,80 +0,0 @@
-// WebSocket client for real-time streaming
-export interface StreamMessage {
-  type: 'start' | 'agent0_token' | 'agent0_complete' | 'stream_complete' | 'error';
-  text: string;
-  partial?: boolean;
-  progress?: number;
-  confidence?: number;
-  meta?: any;
-  session_id: string;
-}
-
-export class Agent0WebSocket {
-  private ws: WebSocket | null = null;
-  private reconnectAttempts = 0;
-  private maxReconnectAttempts = 5;
-  private reconnectInterval = 1000;
-
-  constructor(
-    private url: string = 'ws://localhost:8765',
-    private onMessage?: (message: StreamMessage) => void,
-    private onError?: (error: Event) => void,
-    private onConnect?: () => void,
-    private onDisconnect?: () => void
-  ) {}
-
-  connect(): void {
-    try {
-      this.ws = new WebSocket(this.url);
-
-      this.ws.onopen = () => {
-        console.log('WebSocket connected');
-        this.reconnectAttempts = 0;
-        this.onConnect?.();
-      };
-
-      this.ws.onmessage = (event) => {
-        try {
-          const message: StreamMessage = JSON.parse(event.data);
-          this.onMessage?.(message);
-        } catch (error) {
-          console.error('Failed to parse WebSocket message:', error);
-        }
-      };
-
-      this.ws.onclose = () => {
-        console.log('WebSocket disconnected');
-        this.onDisconnect?.();
-        this.attemptReconnect();
-      };
-
-      this.ws.onerror = (error) => {
-        console.error('WebSocket error:', error);
-        this.onError?.(error);
-      };
-    } catch (error) {
-      console.error('Failed to create WebSocket:', error);
-      this.attemptReconnect();
-    }
-  }
-
-  private attemptReconnect(): void {
-    if (this.reconnectAttempts < this.maxReconnectAttempts) {
-      setTimeout(() => {
-        this.reconnectAttempts++;
-        console.log(`Reconnecting... (${this.reconnectAttempts}/${this.maxReconnectAttempts})`);
-        this.connect();
-      }, this.reconnectInterval * this.reconnectAttempts);
-    }
-  }
-
-  sendMessage(message: any): void {
-    if (this.ws?.readyState === WebSocket.OPEN) {
-      this.ws.send(JSON.stringify(message));
-    }
-  }
-
-  disconnect(): void {
-    this.ws?.close();
-  }
-} 
\ No newline at end of file
@@ -1,6 +1,6 @@
 import React, { useState, useEffect, useRef } from 'react';
 import { agent0Client, ChatResponse } from '../api/agent0';
-import { Agent0WebSocket, StreamMessage } from '../api/websocket';
+import { Agent0Events, StreamMessage } from '../api/events';
 
 interface Message {
   id: string;
@@ -16,22 +16,23 @@ export const ChatWindow: React.FC = () => {
   const [isLoading, setIsLoading] = useState(false);
   const [isConnected, setIsConnected] = useState(false);
   const messagesEndRef = useRef<HTMLDivElement>(null);
-  const wsRef = useRef<Agent0WebSocket | null>(null);
+  const eventsRef = useRef<Agent0Events | null>(null);
 
   useEffect(() => {
-    // Initialize WebSocket connection
-    wsRef.current = new Agent0WebSocket(
-      'ws://localhost:8765',
+    // Server events arrive through the backend over whichever transport gets through
+    eventsRef.current = new Agent0Events(
       handleStreamMessage,
-      handleWebSocketError,
       () => setIsConnected(true),
       () => setIsConnected(false)
     );
 
-    wsRef.current.connect();
+    eventsRef.current.connect().catch((error) => {
+      console.error('Failed to subscribe to server events:', error);
+      setIsConnected(false);
+    });
 
     return () => {
-      wsRef.current?.disconnect();
+      eventsRef.current?.disconnect();
     };
   }, []);
 
@@ -63,11 +64,6 @@ export const ChatWindow: React.FC = () => {
     }
   };
 
-  const handleWebSocketError = (error: Event) => {
-    console.error('WebSocket error:', error);
-    setIsConnected(false);
-  };
-
   const sendMessage = async () => {
     if (!input.trim() || isLoading) return;
 
@@ -91,30 +87,23 @@ export const ChatWindow: React.FC = () => {
     setIsLoading(true);
 
     try {
-      // Use WebSocket for streaming if connected, fallback to REST API
-      if (isConnected && wsRef.current) {
-        wsRef.current.sendMessage({
-          prompt: input,
-          session_id: 'ui_session',
-        });
-      } else {
-        // Fallback to REST API
-        const response = await agent0Client.sendMessage(input);
-        setMessages(prev => 
-          prev.map(msg => 
-            msg.isStreaming 
-              ? { ...msg, text: response.text, isStreaming: false }
-              : msg
-          )
-        );
-        setIsLoading(false);
-      }
+      // Tokens stream in as server events; the reply settles the message either way
+      const response = await agent0Client.sendMessage(input);
+      setMessages(prev => 
+        prev.map(msg => 
+          msg.isStreaming 
+            ? { ...msg, text: response.text, isStreaming: false }
+            : msg
+        )
+      );
+      setIsLoading(false);
     } catch (error) {
       console.error('Failed to send message:', error);
+      // Quota refusals come back as readable text, so they are shown as they are
       setMessages(prev => 
         prev.map(msg => 
           msg.isStreaming 
-            ? { ...msg, text: 'Error: Failed to get response', isStreaming: false }
+            ? { ...msg, text: `Error: ${error}`, isStreaming: false }
             : msg
         )
       );